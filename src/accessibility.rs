@@ -0,0 +1,201 @@
+use eframe::egui::Color32;
+
+use crate::config::AccessibilityConfig;
+
+/// Accessibility preferences reported by the OS.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SystemPreferences {
+    /// The "Show animations in Windows" setting.
+    pub animations_enabled: bool,
+    /// The colours of the active high contrast theme, if one is on.
+    pub high_contrast: Option<ContrastPalette>,
+}
+
+impl Default for SystemPreferences {
+    fn default() -> Self {
+        Self {
+            animations_enabled: true,
+            high_contrast: None,
+        }
+    }
+}
+
+/// System contrast colours, all fully opaque.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContrastPalette {
+    /// Window background.
+    pub background: Color32,
+    /// Window text, used for the border and running indicators.
+    pub foreground: Color32,
+    /// Selection highlight, used for the foreground indicator and keyboard focus.
+    pub highlight: Color32,
+    /// Hyperlink colour, used for items requesting attention.
+    pub hot: Color32,
+}
+
+impl Default for ContrastPalette {
+    /// The colours of the built-in "Night sky" contrast theme.
+    fn default() -> Self {
+        Self {
+            background: Color32::BLACK,
+            foreground: Color32::WHITE,
+            highlight: Color32::from_rgb(0x1a, 0xeb, 0xff),
+            hot: Color32::from_rgb(0xff, 0xff, 0x00),
+        }
+    }
+}
+
+/// The accessibility modes in effect after applying config overrides to the OS preferences.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AccessibilityModes {
+    /// Replaces hover magnification and bouncing with instant state changes.
+    pub reduce_motion: bool,
+    /// Draws the dock with these solid colours instead of the configured ones.
+    pub high_contrast: Option<ContrastPalette>,
+}
+
+/// Combines the OS preferences with the overrides in `config`.
+///
+/// Forcing high contrast on while the OS has no contrast theme uses `ContrastPalette::default()`.
+pub fn resolve_modes(config: &AccessibilityConfig, system: &SystemPreferences) -> AccessibilityModes {
+    let high_contrast = match config.high_contrast {
+        None => system.high_contrast,
+        Some(true) => Some(system.high_contrast.unwrap_or_default()),
+        Some(false) => None,
+    };
+
+    AccessibilityModes {
+        reduce_motion: config.reduce_motion.unwrap_or(!system.animations_enabled),
+        high_contrast,
+    }
+}
+
+/// The state of a dock item as exposed to screen readers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemStatus<'a> {
+    pub window_count: usize,
+    /// Whether the item owns the foreground window.
+    pub active: bool,
+    pub launching: bool,
+    pub needs_attention: bool,
+    pub launch_error: Option<&'a str>,
+}
+
+/// Describes an item's state in a short phrase read after its name,
+/// e.g. "2 windows, active, needs attention".
+///
+/// # Examples
+///
+/// ```
+/// let status = ItemStatus { window_count: 1, ..Default::default() };
+/// assert_eq!(describe_item(&status), "1 window");
+/// ```
+pub fn describe_item(status: &ItemStatus) -> String {
+    let mut parts = vec![match status.window_count {
+        0 if status.launching => "launching".to_string(),
+        0 => "not running".to_string(),
+        1 => "1 window".to_string(),
+        n => format!("{n} windows"),
+    }];
+
+    if status.active {
+        parts.push("active".to_string());
+    }
+    if status.needs_attention {
+        parts.push("needs attention".to_string());
+    }
+    if let Some(error) = status.launch_error {
+        parts.push(format!("failed to launch: {error}"));
+    }
+
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_follow_the_os_by_default() {
+        let system = SystemPreferences {
+            animations_enabled: false,
+            high_contrast: Some(ContrastPalette::default()),
+        };
+        let modes = resolve_modes(&AccessibilityConfig::default(), &system);
+
+        assert!(modes.reduce_motion);
+        assert_eq!(modes.high_contrast, system.high_contrast);
+        assert_eq!(
+            resolve_modes(&AccessibilityConfig::default(), &SystemPreferences::default()),
+            AccessibilityModes::default()
+        );
+    }
+
+    #[test]
+    fn config_overrides_the_os() {
+        let system = SystemPreferences {
+            animations_enabled: false,
+            high_contrast: Some(ContrastPalette {
+                background: Color32::WHITE,
+                ..Default::default()
+            }),
+        };
+        let off = AccessibilityConfig {
+            reduce_motion: Some(false),
+            high_contrast: Some(false),
+        };
+        let on = AccessibilityConfig {
+            reduce_motion: Some(true),
+            high_contrast: Some(true),
+        };
+
+        assert_eq!(resolve_modes(&off, &system), AccessibilityModes::default());
+        assert_eq!(resolve_modes(&on, &system).high_contrast, system.high_contrast);
+        assert_eq!(
+            resolve_modes(&on, &SystemPreferences::default()),
+            AccessibilityModes {
+                reduce_motion: true,
+                high_contrast: Some(ContrastPalette::default()),
+            }
+        );
+    }
+
+    #[test]
+    fn describes_running_state_and_window_count() {
+        let status = |window_count| ItemStatus {
+            window_count,
+            ..Default::default()
+        };
+
+        assert_eq!(describe_item(&status(0)), "not running");
+        assert_eq!(describe_item(&status(1)), "1 window");
+        assert_eq!(describe_item(&status(3)), "3 windows");
+    }
+
+    #[test]
+    fn lists_every_notable_state() {
+        let status = ItemStatus {
+            window_count: 2,
+            active: true,
+            needs_attention: true,
+            ..Default::default()
+        };
+
+        assert_eq!(describe_item(&status), "2 windows, active, needs attention");
+    }
+
+    #[test]
+    fn reports_launch_progress_and_failures() {
+        let launching = ItemStatus {
+            launching: true,
+            ..Default::default()
+        };
+        let failed = ItemStatus {
+            launch_error: Some("file not found"),
+            ..Default::default()
+        };
+
+        assert_eq!(describe_item(&launching), "launching");
+        assert_eq!(describe_item(&failed), "not running, failed to launch: file not found");
+    }
+}
@@ -0,0 +1,68 @@
+use crate::config::BounceRepeat;
+
+/// Returns how far above its resting position an icon should be drawn `elapsed`
+/// seconds after a bounce animation started.
+///
+/// Each bounce follows a parabola that peaks at `height` halfway through `duration`.
+///
+/// # Examples
+///
+/// ```
+/// let peak = bounce_offset(0.3, 0.6, 10.0, BounceRepeat::Count(1));
+/// assert_eq!(peak, 10.0);
+/// assert_eq!(bounce_offset(0.6, 0.6, 10.0, BounceRepeat::Count(1)), 0.0);
+/// ```
+pub fn bounce_offset(elapsed: f32, duration: f32, height: f32, repeat: BounceRepeat) -> f32 {
+    if duration <= 0.0 || elapsed < 0.0 || is_bounce_finished(elapsed, duration, repeat) {
+        return 0.0;
+    }
+
+    let phase = (elapsed % duration) / duration;
+    height * 4.0 * phase * (1.0 - phase)
+}
+
+/// Returns `true` once every requested bounce has completed.
+pub fn is_bounce_finished(elapsed: f32, duration: f32, repeat: BounceRepeat) -> bool {
+    match repeat {
+        BounceRepeat::Continuous => duration <= 0.0,
+        BounceRepeat::Count(count) => duration <= 0.0 || elapsed >= duration * count as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounce_starts_and_ends_at_rest() {
+        assert_eq!(bounce_offset(0.0, 0.6, 10.0, BounceRepeat::Count(2)), 0.0);
+        assert_eq!(bounce_offset(1.2, 0.6, 10.0, BounceRepeat::Count(2)), 0.0);
+    }
+
+    #[test]
+    fn bounce_peaks_halfway_through_each_cycle() {
+        let first = bounce_offset(0.3, 0.6, 10.0, BounceRepeat::Count(2));
+        let second = bounce_offset(0.9, 0.6, 10.0, BounceRepeat::Count(2));
+        assert!((first - 10.0).abs() < 1e-4);
+        assert!((second - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn counted_bounce_finishes_after_requested_cycles() {
+        assert!(!is_bounce_finished(1.1, 0.6, BounceRepeat::Count(2)));
+        assert!(is_bounce_finished(1.2, 0.6, BounceRepeat::Count(2)));
+        assert_eq!(bounce_offset(5.0, 0.6, 10.0, BounceRepeat::Count(2)), 0.0);
+    }
+
+    #[test]
+    fn continuous_bounce_never_finishes() {
+        assert!(!is_bounce_finished(1_000.0, 0.6, BounceRepeat::Continuous));
+        assert!(bounce_offset(1_000.3, 0.6, 10.0, BounceRepeat::Continuous) > 0.0);
+    }
+
+    #[test]
+    fn zero_duration_disables_bouncing() {
+        assert_eq!(bounce_offset(0.1, 0.0, 10.0, BounceRepeat::Continuous), 0.0);
+        assert!(is_bounce_finished(0.1, 0.0, BounceRepeat::Continuous));
+    }
+}
@@ -1,32 +1,167 @@
 // ! Future Improvements: Use IShellItemImageFactory for getting icons
 use eframe::{self, App, Frame, egui};
 use egui::{Context, TextureHandle};
-use std::collections::HashMap;
-use std::path::Path;
-use std::process::Command;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
 
-use crate::config::Config;
+use crate::accessibility::{AccessibilityModes, ItemStatus, describe_item, resolve_modes};
+use crate::animation::{bounce_offset, is_bounce_finished};
+use crate::config::{BounceRepeat, Config, ForegroundIndicatorStyle, WidgetPlacement};
 use crate::events::{self, WindowEvent};
+use crate::first_run::{FirstRunWizard, WizardOutcome};
+use crate::focus::FocusTracker;
+use crate::hotkeys::{self, HotkeyAction};
+use crate::icon_packs::{IconPacks, IconRasters, IconRef};
+use crate::icon_style::style_icon;
+use crate::import::{Import, taskbar, write_import};
+use crate::launch::LaunchTracker;
+use crate::layout::{DockLayout, MonitorGeometry, ViewState, compute_layout, menu_bar_rect};
+use crate::menu_bar::format_clock;
+use crate::menu_model::{MenuCommand, MenuItem, MenuItemKind};
+use crate::pinned::{find_pin, merge_pinned};
+use crate::plugins::{PluginEvent, PluginHost};
+use crate::rules::RuleSet;
+use crate::svg::size_bucket;
+use crate::theme::{
+    self, CLASSIC_THEME, FrameStyle, IndicatorStyle, ItemState, SkinImage, SkinLayer, SystemAppearance, Theme,
+    TooltipStyle, apply_tooltip_style, load_skin_image, paint_fill, paint_frame, paint_item_background,
+    paint_reflection, paint_shelf,
+};
+use crate::widgets::{WidgetGeometry, WidgetHost, WidgetStyle};
 use crate::windows::{
-    DockIcon, focus_or_minimize_window, get_uwp_icon, get_window_title, hicon_to_color_image,
-    is_uwp_app, update_running_apps,
+    DockIcon, MenuEntry, MenuPosition, MenuReader, MenuSnapshot, ProcessResolver, close_window,
+    context_menu_key_down, focus_or_minimize_window, get_file_icon, get_local_time, get_primary_monitor, get_system_appearance,
+    get_system_preferences, get_uwp_background_color, get_uwp_icon_path, get_window_own_hicon, get_window_title, hicon_to_color_image, is_own_window, is_uwp_app,
+    load_png_as_color_image, prevent_activation, release_top_edge, reserve_top_edge, show_menu_model, show_popup_menu,
+    start_menu_pin_shortcuts, taskbar_pin_shortcuts, update_running_apps,
 };
 
-use windows::Win32::Foundation::{POINT, RECT};
+use windows::Win32::Foundation::{HWND, POINT, RECT};
+use windows::core::HSTRING;
 use windows::Win32::UI::WindowsAndMessaging::{
-    FindWindowW, GetCursorPos, GetWindowRect, IsWindow,
+    FindWindowW, GetCursorPos, GetForegroundWindow, GetWindowRect, IsWindow,
 };
 
+/// Title of the menu bar viewport, used to find its native window.
+const MENU_BAR_TITLE: &str = "FerroDock Menu Bar";
+
+/// Storage key of the saved desktop widget positions and sizes.
+const WIDGET_GEOMETRY_KEY: &str = "widget_geometry";
+
+/// Storage key of the theme picked from the dock's menu.
+const THEME_KEY: &str = "theme";
+
+/// Storage key of whether the first-run wizard was answered.
+const FIRST_RUN_KEY: &str = "first_run_done";
+
+/// Size of the first-run wizard's window.
+const FIRST_RUN_SIZE: egui::Vec2 = egui::vec2(420.0, 480.0);
+
+/// Size of the corner handle that resizes a desktop widget.
+const WIDGET_RESIZE_GRIP: f32 = 14.0;
+
+/// How many icons drawn at an old size bucket are redrawn per frame, so a new bucket does not
+/// stall one frame on every icon at once.
+const ICON_REDRAWS_PER_FRAME: usize = 4;
+
+/// Prefix of the `icon_textures` keys of plugin dock item icons, which are not app paths.
+const PLUGIN_TEXTURE_PREFIX: &str = "plugin:";
+
+/// The menu bar window and the strip reserved for it, in logical points.
+pub struct MenuBarReservation {
+    pub hwnd: HWND,
+    /// The strip asked for, along the top of the monitor.
+    pub requested: egui::Rect,
+    /// The strip the shell granted, which may lie below other top app bars.
+    pub granted: egui::Rect,
+}
+
 pub struct FerroDock {
     pub config: Config,
+    pub rules: RuleSet,
+    pub processes: ProcessResolver,
     pub dock_items: Vec<DockIcon>,
     pub icon_textures: HashMap<String, TextureHandle>,
+    pub focus: FocusTracker,
+    /// Applications that requested attention, keyed by path, with the time the request arrived.
+    pub attention: HashMap<String, Instant>,
+    pub launches: LaunchTracker,
+    pub monitor: MonitorGeometry,
+    /// Reduce-motion and high-contrast modes from the OS and config.
+    pub accessibility: AccessibilityModes,
+    /// The layout drawn in the last frame, used to hit-test the pointer.
+    pub layout: DockLayout,
+    /// Hover, scroll and overflow popup state the next layout is computed from.
+    pub view: ViewState,
+    /// The window geometry last sent to the viewport, or `None` before the first frame.
+    pub window_rect: Option<egui::Rect>,
+    /// The item selected with the keyboard while the dock has focus.
+    pub keyboard_selection: Option<usize>,
+    /// Set by the focus hotkey; the dock window is focused on the next frame.
+    pub focus_requested: bool,
+    /// The dock window was asked for focus and has not reported it yet.
+    pub focus_pending: bool,
+    /// Whether the Apps key was down in the last frame, to open the actions menu once per press.
+    pub menu_key_down: bool,
+    /// The menu bar window and its reserved strip, once it exists.
+    pub menu_bar: Option<MenuBarReservation>,
+    /// Reads the foreground window's menus without blocking the UI.
+    pub menu_reader: MenuReader,
+    /// The window whose menus the menu bar shows or waits for; menus read from others are stale.
+    pub menu_target: Option<HWND>,
+    /// The menus of the foreground window, shown in the menu bar.
+    pub native_menu: Option<MenuSnapshot>,
+    /// A top-level menu to show once its fresh snapshot arrives: the read request, the menu's
+    /// index and where to show it.
+    pub pending_menu_open: Option<(u64, usize, MenuPosition)>,
+    pub widgets: WidgetHost,
+    /// Loaded WebAssembly plugins and what they added to the dock.
+    pub plugins: PluginHost,
+    /// When plugins subscribed to ticks were last sent one.
+    pub plugin_tick: Instant,
+    /// The name of the theme in use, and the theme itself.
+    pub theme_name: String,
+    pub theme: Theme,
+    /// The light or dark mode and accent colour Windows is set to.
+    pub appearance: SystemAppearance,
+    /// Textures of the theme's skin images by path, or `None` for images that failed to load.
+    pub skin_textures: HashMap<PathBuf, Option<TextureHandle>>,
+    /// Dock items, by path, whose icon is drawn for another size bucket and due to be redrawn.
+    pub stale_icons: HashSet<String>,
+    /// The window icon handle of dock items, by path, for which no icon source loaded, so their
+    /// icons are only looked up again once the handle changes.
+    pub failed_icons: HashMap<String, isize>,
+    /// Plugin dock item icons that failed to load, so they are not read again every frame.
+    pub failed_plugin_icons: HashSet<PathBuf>,
+    /// Icon packs consulted before an application's own icon.
+    pub icon_packs: IconPacks,
+    /// Rasters of SVG and executable icons, drawn at the size of the current bucket.
+    pub icon_rasters: IconRasters,
+    /// The scalable source of each dock item's icon that has one, to render it again when the
+    /// size bucket changes.
+    pub scalable_icons: HashMap<String, IconRef>,
+    /// The size bucket icons are rasterized for, from the largest size they are drawn at.
+    pub icon_bucket: u32,
+    /// Offers to pin the taskbar's apps on the first start, until it is answered.
+    pub first_run: Option<FirstRunWizard>,
+    /// Whether the first-run wizard was answered, saved so it is only offered once.
+    pub first_run_done: bool,
+    /// The tooltip style last handed to egui, to notice when the theme changes it.
+    applied_tooltip_style: Option<TooltipStyle>,
     pub pending_sync_frames: u8,
-    pub position_set: bool,
     event_receiver: Receiver<WindowEvent>,
 }
 
+/// A theme picked from the dock's menu, saved with the `theme` setting it replaced so that
+/// editing the setting later takes effect again.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct ThemeChoice {
+    configured: Option<String>,
+    chosen: String,
+}
+
 impl Default for FerroDock {
     /// Creates a dock with default configuration and empty application and texture collections.
     ///
@@ -34,16 +169,63 @@ impl Default for FerroDock {
     ///
     /// ```
     /// let dock = FerroDock::default();
-    /// assert!(!dock.position_set);
+    /// assert!(dock.window_rect.is_none());
     /// ```
     fn default() -> Self {
+        let config = Config::default();
+        let monitor = get_primary_monitor();
+        let accessibility = resolve_modes(&config.accessibility, &get_system_preferences());
+        let widgets = WidgetHost::new(&config.widgets);
+        let plugins = PluginHost::default();
+        let dock_widgets = widgets.placed(WidgetPlacement::Dock).count();
+        let layout = compute_layout(&monitor, &config, dock_widgets, &ViewState::default());
+        let event_receiver = events::start_event_listener(hotkeys::bindings(&config.hotkeys));
+        let appearance = get_system_appearance();
+        let (theme_name, theme) = configured_theme(&config, &appearance);
+        let icon_packs = IconPacks::load(&config.icon_packs);
+
         Self {
-            config: Config::default(),
+            rules: RuleSet::new(&config.effective_window_rules()),
+            processes: ProcessResolver::default(),
+            config,
             dock_items: Vec::new(),
             icon_textures: HashMap::new(),
+            focus: FocusTracker::default(),
+            attention: HashMap::new(),
+            launches: LaunchTracker::default(),
+            monitor,
+            accessibility,
+            layout,
+            view: ViewState::default(),
+            window_rect: None,
+            keyboard_selection: None,
+            focus_requested: false,
+            focus_pending: false,
+            menu_key_down: false,
+            menu_bar: None,
+            menu_reader: MenuReader::start(),
+            menu_target: None,
+            native_menu: None,
+            pending_menu_open: None,
+            widgets,
+            plugins,
+            plugin_tick: Instant::now(),
+            theme_name,
+            theme,
+            appearance,
+            skin_textures: HashMap::new(),
+            stale_icons: HashSet::new(),
+            failed_icons: HashMap::new(),
+            failed_plugin_icons: HashSet::new(),
+            icon_packs,
+            icon_rasters: IconRasters::default(),
+            scalable_icons: HashMap::new(),
+            icon_bucket: 0,
+            first_run: None,
+            first_run_done: false,
+            applied_tooltip_style: None,
             pending_sync_frames: 0,
-            position_set: false,
-            event_receiver: events::start_event_listener(),
+            event_receiver,
         }
     }
 }
@@ -55,23 +237,741 @@ impl FerroDock {
     ///
     /// ```
     /// let dock = FerroDock::new();
-    /// assert!(!dock.position_set);
+    /// assert!(dock.window_rect.is_none());
     /// ```
     pub fn new() -> Self {
-        let initial_icons = update_running_apps();
-        let event_receiver = events::start_event_listener();
+        let config = Config::load();
+        let rules = RuleSet::new(&config.effective_window_rules());
+        let mut processes = ProcessResolver::default();
+        let initial_icons = merge_pinned(update_running_apps(&rules, &mut processes), &config.pinned);
+        let event_receiver = events::start_event_listener(hotkeys::bindings(&config.hotkeys));
+
+        let mut focus = FocusTracker::default();
+        focus.apply_activation(unsafe { GetForegroundWindow() }, &initial_icons);
+
+        let monitor = get_primary_monitor();
+        let accessibility = resolve_modes(&config.accessibility, &get_system_preferences());
+        let widgets = WidgetHost::new(&config.widgets);
+        let plugins = PluginHost::load(&config);
+        let slot_count = initial_icons.len()
+            + widgets.placed(WidgetPlacement::Dock).count()
+            + plugins.dock_items().count();
+        let layout = compute_layout(&monitor, &config, slot_count, &ViewState::default());
+        let appearance = get_system_appearance();
+        let (theme_name, theme) = configured_theme(&config, &appearance);
+        let icon_packs = IconPacks::load(&config.icon_packs);
 
         Self {
-            config: Config::default(),
+            config,
+            rules,
+            processes,
             dock_items: initial_icons,
             icon_textures: HashMap::new(),
+            focus,
+            attention: HashMap::new(),
+            launches: LaunchTracker::default(),
+            monitor,
+            accessibility,
+            layout,
+            view: ViewState::default(),
+            window_rect: None,
+            keyboard_selection: None,
+            focus_requested: false,
+            focus_pending: false,
+            menu_key_down: false,
+            menu_bar: None,
+            menu_reader: MenuReader::start(),
+            menu_target: None,
+            native_menu: None,
+            pending_menu_open: None,
+            widgets,
+            plugins,
+            plugin_tick: Instant::now(),
+            theme_name,
+            theme,
+            appearance,
+            skin_textures: HashMap::new(),
+            stale_icons: HashSet::new(),
+            failed_icons: HashMap::new(),
+            failed_plugin_icons: HashSet::new(),
+            icon_packs,
+            icon_rasters: IconRasters::default(),
+            scalable_icons: HashMap::new(),
+            icon_bucket: 0,
+            first_run: None,
+            first_run_done: false,
+            applied_tooltip_style: None,
             pending_sync_frames: 0,
-            position_set: false,
             event_receiver,
         }
     }
 
+    /// Restores the state `App::save` stored in a previous session.
+    pub fn restore_state(&mut self, storage: Option<&dyn eframe::Storage>) {
+        if let Some(geometry) = storage.and_then(|storage| eframe::get_value(storage, WIDGET_GEOMETRY_KEY)) {
+            self.widgets.geometry = geometry;
+        }
+
+        let choice: Option<ThemeChoice> = storage.and_then(|storage| eframe::get_value(storage, THEME_KEY));
+        if let Some(choice) = choice.filter(|choice| choice.configured == self.config.theme) {
+            self.set_theme(&choice.chosen);
+        }
+
+        // Without storage the wizard could not remember being answered
+        self.first_run_done = storage.and_then(|storage| eframe::get_value(storage, FIRST_RUN_KEY)).unwrap_or(false);
+        if storage.is_some() && !self.first_run_done && self.config.pinned.is_empty() {
+            let wizard = FirstRunWizard::new(
+                taskbar::import(&taskbar_pin_shortcuts()).pinned,
+                taskbar::import(&start_menu_pin_shortcuts()).pinned,
+            );
+            self.first_run = (!wizard.is_empty()).then_some(wizard);
+        }
+    }
+
+    /// Switches to the theme called `name`, keeping the current one if it fails to load.
+    fn set_theme(&mut self, name: &str) {
+        match theme::load_theme(name, &self.config, &self.appearance) {
+            Ok(theme) => {
+                self.theme = theme;
+                self.theme_name = name.to_string();
+                self.skin_textures.clear();
+            }
+            Err(err) => eprintln!("Failed to load theme `{name}`: {err}"),
+        }
+    }
+
+    /// Whether the dark variants of the theme and icons are drawn.
+    fn is_dark(&self) -> bool {
+        self.appearance.is_dark(self.config.appearance)
+    }
+
+    /// The theme to draw with: the chosen one, redrawn in system colours in high contrast.
+    fn current_theme(&self) -> Theme {
+        match &self.accessibility.high_contrast {
+            Some(palette) => self.theme.with_contrast(palette),
+            None => self.theme.clone(),
+        }
+    }
+
+    /// The skin layer drawn with `image`, once its texture has loaded.
+    fn skin_layer(&self, image: Option<&SkinImage>) -> Option<SkinLayer> {
+        let image = image?;
+        let texture = self.skin_textures.get(&image.image)?.clone()?;
+        Some(SkinLayer::new(image, texture))
+    }
+
+    /// Shows the dock's own menu at the cursor, which lets the user switch themes.
+    fn open_theme_menu(&mut self) {
+        let names = theme::theme_names();
+        let themes = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| MenuItem {
+                label: name.clone(),
+                shortcut: None,
+                enabled: true,
+                checked: *name == self.theme_name,
+                kind: MenuItemKind::Command(MenuCommand::Command(index as u32)),
+            })
+            .collect();
+        let menu = [MenuItem {
+            label: "Theme".to_string(),
+            shortcut: None,
+            enabled: true,
+            checked: false,
+            kind: MenuItemKind::Submenu(themes),
+        }];
+
+        if let Some(MenuCommand::Command(index)) = show_menu_model(find_dock_window(), MenuPosition::Cursor, &menu)
+            && let Some(name) = names.get(index as usize)
+        {
+            self.set_theme(name);
+        }
+    }
+
+    /// How long a launch may take to show a window; an infinite setting never times out.
+    fn launch_timeout(&self) -> Duration {
+        Duration::try_from_secs_f32(self.config.launch_timeout_secs.max(0.0)).unwrap_or(Duration::MAX)
+    }
+
+    /// Re-enumerates running applications and drops focus history for windows that
+    /// have gone away.
+    fn refresh_dock_items(&mut self) {
+        let running = merge_pinned(update_running_apps(&self.rules, &mut self.processes), &self.config.pinned);
+        let previous = std::mem::replace(&mut self.dock_items, running);
+        let previous_paths: Vec<String> = previous.iter().map(|item| item.path.clone()).collect();
+
+        self.launches.update(&self.dock_items, self.launch_timeout());
+
+        // Keep items that are still launching (or failed to) visible so their state can be shown
+        for item in previous {
+            if self.launches.is_tracked(&item.path)
+                && !self.dock_items.iter().any(|i| i.path == item.path)
+            {
+                self.dock_items.push(DockIcon {
+                    hwnd: HWND(0),
+                    windows: Vec::new(),
+                    ..item
+                });
+            }
+        }
+
+        self.focus.retain_items(&self.dock_items);
+
+        let items = &self.dock_items;
+        self.attention
+            .retain(|path, _| items.iter().any(|i| i.path == *path));
+
+        let paths: Vec<String> = self.dock_items.iter().map(|item| item.path.clone()).collect();
+        if paths != previous_paths {
+            self.plugins.dispatch(&PluginEvent::DockChanged { items: paths });
+        }
+    }
+
+    /// Applies a single shell event to the focus, attention, texture and monitor state.
+    fn apply_window_event(&mut self, event: WindowEvent) {
+        let owner = |hwnd| {
+            self.dock_items
+                .iter()
+                .find(|i| i.windows.contains(&hwnd))
+                .map(|i| i.path.clone())
+        };
+
+        match event {
+            WindowEvent::WindowActivated(hwnd) => {
+                self.focus.apply_activation(hwnd, &self.dock_items);
+                if let Some(path) = self.focus.foreground_app() {
+                    self.attention.remove(path);
+                }
+
+                // The dock's own windows take the foreground while their menus are open
+                if self.config.menu_bar.enabled && !is_own_window(hwnd) {
+                    if self.native_menu.as_ref().is_some_and(|native| native.hwnd != hwnd) {
+                        self.native_menu = None;
+                    }
+                    self.menu_target = Some(hwnd);
+                    self.menu_reader.read(hwnd, None);
+                }
+
+                let item = self.focus.foreground_app().map(str::to_string);
+                self.plugins.dispatch(&PluginEvent::WindowActivated { item });
+            }
+            WindowEvent::WindowFlashed(hwnd) => {
+                if let Some(path) = owner(hwnd)
+                    && !self.focus.is_foreground_app(&path)
+                {
+                    self.attention.entry(path).or_insert_with(Instant::now);
+                }
+            }
+            WindowEvent::WindowRedrawn(hwnd) => {
+                // Titles change far more often than icons; only a new icon is reloaded
+                if let Some(item) = self.dock_items.iter_mut().find(|item| item.hwnd == hwnd)
+                    && let Some((hicon, _)) = get_window_own_hicon(hwnd)
+                    && hicon != item.hicon
+                {
+                    item.hicon = hicon;
+                    self.icon_textures.remove(&item.path);
+                }
+            }
+            WindowEvent::DisplayChanged => {
+                // Re-place the window even if its logical rect is unchanged, since the
+                // OS may have moved or rescaled it
+                self.monitor = get_primary_monitor();
+                self.window_rect = None;
+            }
+            WindowEvent::PreferencesChanged => {
+                self.accessibility = resolve_modes(&self.config.accessibility, &get_system_preferences());
+
+                let appearance = get_system_appearance();
+                if appearance != self.appearance {
+                    let was_dark = self.is_dark();
+                    self.appearance = appearance;
+                    self.set_theme(&self.theme_name.clone());
+
+                    // Packaged apps have logos for light and dark backgrounds
+                    if self.is_dark() != was_dark {
+                        self.icon_textures.retain(|path, _| !is_uwp_app(path));
+                        self.scalable_icons.retain(|path, _| !is_uwp_app(path));
+                        self.failed_icons.retain(|path, _| !is_uwp_app(path));
+                    }
+                }
+            }
+            WindowEvent::Hotkey(HotkeyAction::ActivateItem(index)) => {
+                if let Some(path) = self.visible_item(index).map(|item| item.path.clone()) {
+                    self.activate_item(&path, true);
+                }
+            }
+            WindowEvent::Hotkey(HotkeyAction::FocusDock) => {
+                self.focus_requested = true;
+                self.keyboard_selection.get_or_insert(0);
+            }
+            WindowEvent::WindowCreated => self.plugins.dispatch(&PluginEvent::WindowCreated),
+            WindowEvent::WindowDestroyed => self.plugins.dispatch(&PluginEvent::WindowDestroyed),
+        }
+    }
+
+    /// Returns the `index`th item shown in the dock. Items whose icon has not loaded yet are
+    /// not shown, so this is also the index used by the layout.
+    fn visible_item(&self, index: usize) -> Option<&DockIcon> {
+        self.dock_items
+            .iter()
+            .filter(|item| self.icon_textures.contains_key(&item.path))
+            .nth(index)
+    }
+
+    /// Does what clicking the item at `path` does: focuses its most recent window, minimizes
+    /// it if it is already in the foreground, or launches the application if it has no window.
+    ///
+    /// With `cycle`, activating the foreground application steps to its next window instead.
+    fn activate_item(&mut self, path: &str, cycle: bool) {
+        let Some(item) = self.dock_items.iter().find(|i| i.path == path) else {
+            return;
+        };
+
+        let target = cycle
+            .then(|| self.focus.next_window(item))
+            .flatten()
+            .unwrap_or_else(|| self.focus.most_recent_window(item));
+
+        if target.0 != 0 && unsafe { IsWindow(target).as_bool() } {
+            focus_or_minimize_window(target);
+        } else if self.launches.pending_since(path).is_none() {
+            self.launches.launch(path);
+        }
+    }
+
+    /// Shows the native actions menu of the item at `path` and runs the chosen action.
+    fn open_item_menu(&mut self, path: &str, position: MenuPosition) {
+        let Some(item) = self.dock_items.iter().find(|i| i.path == path) else {
+            return;
+        };
+
+        let target = self.focus.most_recent_window(item);
+        let running = target.0 != 0;
+        let plugin_entries: Vec<(usize, String, String)> = self
+            .plugins
+            .menu_entries()
+            .map(|(plugin, entry)| (plugin, entry.id.clone(), entry.label.clone()))
+            .collect();
+        let mut entries = vec![
+            MenuEntry {
+                label: if running { "Show" } else { "Open" },
+                enabled: true,
+            },
+            MenuEntry {
+                label: "New window",
+                enabled: running,
+            },
+            MenuEntry {
+                label: "Close window",
+                enabled: running,
+            },
+        ];
+        let built_in = entries.len();
+        entries.extend(plugin_entries.iter().map(|(_, _, label)| MenuEntry { label, enabled: true }));
+
+        match show_popup_menu(find_dock_window(), position, &entries) {
+            Some(0) => self.activate_item(path, false),
+            Some(1) => self.launches.launch(path),
+            Some(2) => close_window(target),
+            Some(index) => {
+                if let Some((plugin, id, _)) = plugin_entries.get(index - built_in) {
+                    self.plugins.choose_menu_entry(*plugin, id, path);
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Moves the keyboard selection and operates the selected item while the dock has focus.
+    ///
+    /// Arrow keys, Home and End move the selection, Shift+F10 or the Apps key opens the item's
+    /// actions menu and Escape ends keyboard navigation. The selected item holds egui's focus, so
+    /// Enter or Space activates it like a click. A selection made by the focus hotkey is kept
+    /// until the window gets the focus it asked for.
+    fn handle_keyboard(&mut self, ctx: &Context, item_count: usize) {
+        let focused = ctx.input(|i| i.viewport().focused) == Some(true);
+        let menu_key_down = focused && context_menu_key_down();
+        let menu_key_pressed = menu_key_down && !self.menu_key_down;
+        self.menu_key_down = menu_key_down;
+
+        if item_count == 0 {
+            self.keyboard_selection = None;
+            return;
+        }
+        if !focused {
+            if !(self.focus_requested || self.focus_pending) {
+                self.keyboard_selection = None;
+            }
+            return;
+        }
+        self.focus_pending = false;
+
+        let Some(selected) = self.keyboard_selection else {
+            if ctx.input(|i| i.key_pressed(egui::Key::Tab) || i.key_pressed(egui::Key::ArrowRight)) {
+                self.keyboard_selection = Some(0);
+            }
+            return;
+        };
+
+        let last = item_count - 1;
+        let pressed = |key| ctx.input(|i| i.key_pressed(key));
+        let selected = if pressed(egui::Key::ArrowLeft) || pressed(egui::Key::ArrowUp) {
+            selected.saturating_sub(1)
+        } else if pressed(egui::Key::ArrowRight) || pressed(egui::Key::ArrowDown) {
+            (selected + 1).min(last)
+        } else if pressed(egui::Key::Home) {
+            0
+        } else if pressed(egui::Key::End) {
+            last
+        } else {
+            selected.min(last)
+        };
+        self.keyboard_selection = Some(selected);
+
+        if pressed(egui::Key::Escape) {
+            self.keyboard_selection = None;
+            self.view.overflow_open = false;
+            return;
+        }
+
+        // Bring the selection into view
+        if let (Some(first), Some(last_shown)) = (self.layout.items.first(), self.layout.items.last()) {
+            if selected < first.index {
+                self.view.scroll = selected;
+            } else if selected > last_shown.index && self.layout.max_scroll > 0 {
+                self.view.scroll = selected + 1 - self.layout.items.len();
+            }
+        }
+        if self.layout.more.is_some() {
+            self.view.overflow_open = selected >= self.layout.items.len();
+        }
+
+        let Some(path) = self.visible_item(selected).map(|item| item.path.clone()) else {
+            return;
+        };
+
+        // Enter and Space reach the focused item as a click
+        if menu_key_pressed || ctx.input(|i| i.modifiers.shift && i.key_pressed(egui::Key::F10)) {
+            let anchor = self
+                .layout
+                .items
+                .iter()
+                .chain(self.layout.overflow_popup.iter().flat_map(|popup| &popup.items))
+                .find(|item| item.index == selected)
+                .map(|item| item.slot.center_top());
+            let position = anchor.zip(self.window_rect).map_or(MenuPosition::Cursor, |(anchor, window)| {
+                MenuPosition::Above(to_screen_point(window.min, anchor, ctx.pixels_per_point()))
+            });
+            self.open_item_menu(&path, position);
+        }
+    }
+
+    /// Draws the menu bar: the foreground app's name and menus on the left and the status area,
+    /// ending with the clock, on the right. Clicking the app name opens its actions menu.
+    fn draw_menu_bar(&mut self, ctx: &Context, bar: egui::Rect) {
+        let twenty_four_hour_clock = self.config.menu_bar.twenty_four_hour_clock;
+        let bar_style = self.current_theme().menu_bar;
+        let text_color = bar_style.text_color;
+
+        let foreground_app = self
+            .focus
+            .foreground_app()
+            .and_then(|path| self.dock_items.iter().find(|i| i.path == path))
+            .map(|item| (item.path.clone(), app_name(item)));
+        let menu_titles: Vec<(usize, String, bool)> = self
+            .native_menu
+            .iter()
+            .flat_map(|native| native.menu.titles())
+            .map(|(index, item)| (index, item.label.clone(), item.enabled))
+            .collect();
+        let widget_style = self.widget_style(WidgetPlacement::MenuBar);
+        let mut menu_requested = None;
+        let mut title_clicked = None;
+
+        let frame = egui::Frame::none().inner_margin(egui::Margin::symmetric(12.0, 0.0));
+        egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+            let screen = ctx.screen_rect();
+            paint_fill(ui.painter(), screen, egui::Rounding::ZERO, bar_style.fill);
+            if let Some(separator) = bar_style.separator {
+                let y = screen.bottom() - separator.width / 2.0;
+                ui.painter().hline(screen.x_range(), y, separator);
+            }
+
+            ui.horizontal_centered(|ui| {
+                if let Some((path, name)) = &foreground_app {
+                    let label = egui::Label::new(egui::RichText::new(name).strong().color(text_color))
+                        .sense(egui::Sense::click());
+                    let response = ui.add(label);
+
+                    if response.clicked() {
+                        menu_requested = Some((path.clone(), response.rect.center_bottom()));
+                    }
+                }
+
+                for (index, title, enabled) in &menu_titles {
+                    let label = egui::Label::new(egui::RichText::new(title).color(text_color))
+                        .sense(egui::Sense::click());
+                    let response = ui.add_enabled(*enabled, label);
+
+                    if response.clicked() {
+                        title_clicked = Some((*index, response.rect.left_bottom()));
+                    }
+                }
+
+                // Status items are laid out from the right edge, starting with the clock
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let clock = format_clock(&get_local_time(), twenty_four_hour_clock);
+                    ui.label(egui::RichText::new(clock).color(text_color));
+
+                    for instance in self.widgets.placed(WidgetPlacement::MenuBar) {
+                        let size = egui::vec2(instance.widget.default_size().x, ui.available_height());
+                        ui.allocate_ui(size, |ui| instance.widget.ui(ui, &widget_style));
+                    }
+                });
+            });
+        });
+
+        if let Some((path, anchor)) = menu_requested {
+            let at = to_screen_point(bar.min, anchor, ctx.pixels_per_point());
+            self.open_item_menu(&path, MenuPosition::Below(at));
+        }
+
+        if let Some((index, anchor)) = title_clicked {
+            let at = to_screen_point(bar.min, anchor, ctx.pixels_per_point());
+            self.open_native_menu(index, MenuPosition::Below(at));
+        }
+    }
+
+    /// Takes the menus read since the last frame, dropping those of windows no longer in front,
+    /// and shows a menu that was waiting for its snapshot.
+    fn receive_native_menus(&mut self, ctx: &Context) {
+        while let Some(snapshot) = self.menu_reader.try_recv() {
+            if self.menu_target != Some(snapshot.hwnd) {
+                continue;
+            }
+
+            let request = snapshot.request;
+            self.native_menu = (!snapshot.menu.items.is_empty()).then_some(snapshot);
+            if let Some((_, index, position)) = self.pending_menu_open.take_if(|(pending, ..)| *pending == request) {
+                self.show_native_menu(index, position);
+            }
+        }
+
+        // Snapshots arrive without a window event to wake the UI
+        if self.menu_reader.is_reading() {
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+    }
+
+    /// Opens the `index`th top-level menu of the foreground window. Classic menus are read
+    /// again first, as apps fill in their submenus when they open, and shown once read. Menus
+    /// read through UI Automation open the app's own dropdown instead.
+    fn open_native_menu(&mut self, index: usize, position: MenuPosition) {
+        let Some(native) = &self.native_menu else {
+            return;
+        };
+        let hwnd = native.hwnd;
+
+        match native.menu.items.get(index).map(|item| &item.kind) {
+            Some(MenuItemKind::Submenu(_)) => {
+                let request = self.menu_reader.read(hwnd, Some(index));
+                self.pending_menu_open = Some((request, index, position));
+            }
+            Some(MenuItemKind::Command(command)) => self.menu_reader.invoke(hwnd, *command),
+            Some(MenuItemKind::Separator) | None => {}
+        }
+    }
+
+    /// Shows the `index`th top-level menu as last read and carries out the chosen command.
+    fn show_native_menu(&self, index: usize, position: MenuPosition) {
+        let Some(native) = &self.native_menu else {
+            return;
+        };
+
+        if let Some(MenuItemKind::Submenu(children)) = native.menu.items.get(index).map(|item| &item.kind)
+            && let Some(command) = show_menu_model(find_dock_window(), position, children)
+        {
+            self.menu_reader.invoke(native.hwnd, command);
+        }
+    }
+
+    /// Reserves the menu bar strip once its window exists, and again whenever the strip moves.
+    fn reserve_menu_bar(&mut self, bar: egui::Rect) {
+        if self.menu_bar.as_ref().is_some_and(|reserved| reserved.requested == bar) {
+            return;
+        }
+
+        // The window is created at the end of the frame that first shows it
+        let hwnd = unsafe { FindWindowW(None, &HSTRING::from(MENU_BAR_TITLE)) };
+        if hwnd.0 == 0 {
+            return;
+        }
+
+        // Clicking the menu bar must not take the foreground from the app it describes
+        prevent_activation(hwnd);
+
+        let scale = self.monitor.scale_factor();
+        let granted = reserve_top_edge(
+            hwnd,
+            RECT {
+                left: (bar.min.x * scale).round() as i32,
+                top: (bar.min.y * scale).round() as i32,
+                right: (bar.max.x * scale).round() as i32,
+                bottom: (bar.max.y * scale).round() as i32,
+            },
+        );
+        let granted = egui::Rect::from_min_max(
+            egui::pos2(granted.left as f32 / scale, granted.top as f32 / scale),
+            egui::pos2(granted.right as f32 / scale, granted.bottom as f32 / scale),
+        );
+        self.menu_bar = Some(MenuBarReservation {
+            hwnd,
+            requested: bar,
+            granted,
+        });
+    }
+
+    /// Gives the menu bar strip back to other windows.
+    fn release_menu_bar(&mut self) {
+        if let Some(reserved) = self.menu_bar.take() {
+            release_top_edge(reserved.hwnd);
+        }
+    }
+
+    /// The colours widgets at `placement` are drawn with.
+    fn widget_style(&self, placement: WidgetPlacement) -> WidgetStyle {
+        let theme = self.current_theme();
+
+        WidgetStyle {
+            placement,
+            text_color: theme.menu_bar.text_color,
+            accent_color: theme.indicators.foreground,
+            warning_color: theme.indicators.attention,
+        }
+    }
+
+    /// Shows each desktop widget in its own borderless window. Dragging a widget moves it and
+    /// its bottom-right corner resizes it; the new geometry is saved with the app state.
+    fn draw_desktop_widgets(&mut self, ctx: &Context) {
+        let style = self.widget_style(WidgetPlacement::Desktop);
+        // Widget windows are exactly the widget's size, leaving no room for a drop shadow
+        let frame_style = FrameStyle {
+            shadow: None,
+            ..self.current_theme().frame
+        };
+        let frame = egui::Frame::none().inner_margin(12.0);
+
+        let mut changed = Vec::new();
+        for (instance, geometry) in self.widgets.desktop_geometry(self.monitor.usable_area()) {
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of(("ferro_widget", &instance.id)),
+                egui::ViewportBuilder::default()
+                    .with_title(format!("FerroDock Widget {}", instance.id))
+                    .with_decorations(false)
+                    .with_transparent(true)
+                    .with_taskbar(false)
+                    .with_position(geometry.pos)
+                    .with_inner_size(geometry.size),
+                |ctx, _class| {
+                    egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+                        let window = ctx.screen_rect();
+                        paint_frame(ui.painter(), window.shrink(frame_style.border.width / 2.0), &frame_style);
+                        let drag = ui.interact(window, egui::Id::new("widget_move"), egui::Sense::drag());
+                        if drag.drag_started() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                        }
+
+                        instance.widget.ui(ui, &style);
+
+                        let grip = egui::Rect::from_min_max(window.max - egui::Vec2::splat(WIDGET_RESIZE_GRIP), window.max);
+                        let resize = ui.interact(grip, egui::Id::new("widget_resize"), egui::Sense::drag());
+                        paint_resize_grip(ui.painter(), grip, style.text_color.gamma_multiply(0.5));
+                        if resize.drag_started() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::BeginResize(
+                                egui::viewport::ResizeDirection::SouthEast,
+                            ));
+                        }
+                    });
+
+                    // Pick up moves and resizes made through the OS
+                    let rects = ctx.input(|i| i.viewport().outer_rect.zip(i.viewport().inner_rect));
+                    if let Some((outer, inner)) = rects {
+                        let actual = WidgetGeometry {
+                            pos: outer.min,
+                            size: inner.size(),
+                        };
+                        let moved = (actual.pos - geometry.pos).length() > 0.5
+                            || (actual.size - geometry.size).length() > 0.5;
+                        if moved {
+                            changed.push((instance.id.clone(), actual));
+                        }
+                    }
+                },
+            );
+        }
+
+        self.widgets.geometry.extend(changed);
+    }
+
+    /// Shows the first-run wizard in its own window until it is answered, then pins the
+    /// chosen apps and saves them to `config.toml`.
+    fn draw_first_run_wizard(&mut self, ctx: &Context) {
+        let Some(wizard) = &mut self.first_run else {
+            return;
+        };
+
+        let outcome = ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("ferro_first_run"),
+            egui::ViewportBuilder::default()
+                .with_title("Welcome to FerroDock")
+                .with_resizable(false)
+                .with_position(self.monitor.usable_area().center() - FIRST_RUN_SIZE / 2.0)
+                .with_inner_size(FIRST_RUN_SIZE),
+            |ctx, _class| {
+                let closed = ctx.input(|i| i.viewport().close_requested());
+                let outcome = egui::CentralPanel::default().show(ctx, |ui| wizard.ui(ui)).inner;
+                outcome.or(closed.then_some(WizardOutcome::Skip))
+            },
+        );
+        let Some(outcome) = outcome else {
+            return;
+        };
+
+        self.first_run = None;
+        self.first_run_done = true;
+        if let WizardOutcome::Pin(pinned) = outcome {
+            let import = Import {
+                pinned,
+                ..Import::default()
+            };
+            if let Err(err) = write_import(&import) {
+                eprintln!("Failed to save pinned apps: {err}");
+            }
+            self.config.pinned.extend(import.pinned);
+            self.refresh_dock_items();
+        }
+    }
+
+    /// Returns `true` while any item is still bouncing or launching.
+    fn is_animating(&self) -> bool {
+        let Config {
+            attention_bounce,
+            bounce_duration,
+            ..
+        } = self.config;
+
+        let bouncing = !self.accessibility.reduce_motion
+            && self.attention.values().any(|started| {
+                !is_bounce_finished(started.elapsed().as_secs_f32(), bounce_duration, attention_bounce)
+            });
+
+        // Pending launches keep frames coming so their timeout is noticed
+        self.launches.has_pending() || bouncing
+    }
+
     /// Processes queued window events and refreshes the dock state when events are available.
+    /// Batches of only redraws skip the refresh, since they cannot change the set of windows.
     ///
     /// Stale icon textures are removed after a refresh.
     ///
@@ -81,153 +981,409 @@ impl FerroDock {
     /// let mut dock = FerroDock::new();
     /// let refreshed = dock.process_window_events();
     ///
-    /// assert!(!refreshed || dock.pending_sync_frames == 15);
+    /// assert!(!refreshed || dock.pending_sync_frames <= 15);
     /// ```
     ///
     /// Returns `true` if at least one queued event was processed, `false` otherwise.
     fn process_window_events(&mut self) -> bool {
-        let mut did_something = false;
-
-        while let Ok(_event) = self.event_receiver.try_recv() {
-            did_something = true;
-        }
+        let events: Vec<WindowEvent> = self.event_receiver.try_iter().collect();
+        let did_something = !events.is_empty();
 
         if did_something {
-            self.pending_sync_frames = 15;
-            self.dock_items = update_running_apps();
+            // Redraws do not add or remove windows, so they need no re-enumeration
+            let windows_changed = events.iter().any(|event| !matches!(event, WindowEvent::WindowRedrawn(_)));
+            if windows_changed {
+                self.pending_sync_frames = 15;
+                self.refresh_dock_items();
+            }
+
+            for event in events {
+                self.apply_window_event(event);
+            }
 
             // Garbage-collect stale textures for applications no longer in the dock
             let active_paths: std::collections::HashSet<&String> =
                 self.dock_items.iter().map(|i| &i.path).collect();
-            self.icon_textures
-                .retain(|path, _| active_paths.contains(path));
+            self.icon_textures.retain(|path, _| {
+                active_paths.contains(path) || path.starts_with(PLUGIN_TEXTURE_PREFIX)
+            });
+            self.scalable_icons.retain(|path, _| active_paths.contains(path));
+            let drawn_icons: HashSet<&IconRef> = self.scalable_icons.values().collect();
+            self.icon_rasters.retain_icons(|icon| drawn_icons.contains(icon));
+            self.failed_icons.retain(|path, _| active_paths.contains(path));
         }
 
         did_something
     }
 
-    fn draw_dock_ui(&mut self, ui: &mut egui::Ui) {
+    fn draw_dock_ui(&mut self, ui: &mut egui::Ui, layout: &DockLayout) {
         let Config {
-            background_color,
-            border_color,
-            border_width,
-            left_margin,
-            right_margin,
-            top_margin,
-            bottom_margin,
-            icon_spacing,
-            icon_size,
             show_active_indicators,
-            hover_scale,
+            foreground_indicator_style,
+            attention_bounce,
+            bounce_height,
+            bounce_duration,
             ..
         } = self.config;
+        let reduce_motion = self.accessibility.reduce_motion;
 
-        let frame = egui::Frame::none()
-            .fill(background_color)
-            .stroke(egui::Stroke::new(border_width, border_color))
-            .rounding(egui::Rounding::from(self.config.rounding))
-            .inner_margin(egui::Margin {
-                left: left_margin,
-                right: right_margin,
-                top: top_margin,
-                bottom: bottom_margin + if show_active_indicators { 4.0 } else { 0.0 },
+        let theme = self.current_theme();
+        let IndicatorStyle {
+            running: running_indicator_color,
+            foreground: foreground_indicator_color,
+            attention: attention_indicator_color,
+            launch_error: launch_error_color,
+        } = theme.indicators;
+        let border_color = theme.frame.border.color;
+
+        let skin = theme.skin.clone().unwrap_or_default();
+        let [background, shelf, reflection, overlay, indicator] =
+            [&skin.background, &skin.shelf, &skin.reflection, &skin.overlay, &skin.indicator]
+                .map(|image| self.skin_layer(image.as_ref()));
+        // A skin's background replaces the frame unless it asks to be drawn over it
+        let paint_panel = |painter: &egui::Painter, rect: egui::Rect| {
+            if background.is_none() || skin.keep_frame {
+                paint_frame(painter, rect, &theme.frame);
+            }
+            if let Some(background) = &background {
+                background.paint(painter, rect, egui::Color32::WHITE);
+            }
+        };
+
+        // A theme's 3D shelf stands in for the dock's frame, in front of any skin background;
+        // the overflow popup keeps its frame
+        let dock_rect = layout.dock;
+        match &theme.shelf {
+            Some(style) => {
+                if let Some(background) = &background {
+                    background.paint(ui.painter(), dock_rect, egui::Color32::WHITE);
+                }
+                paint_shelf(ui.painter(), dock_rect, style);
+            }
+            None => paint_panel(ui.painter(), dock_rect),
+        }
+        if let Some(shelf) = &shelf {
+            shelf.paint(ui.painter(), shelf.bottom_strip(dock_rect), egui::Color32::WHITE);
+        }
+        let dock_response = ui.interact(dock_rect, egui::Id::new("ferro_dock_frame"), egui::Sense::hover());
+
+        let visible_items: Vec<_> = self
+            .dock_items
+            .iter()
+            .filter_map(|item| Some((item, self.icon_textures.get(&item.path)?)))
+            .collect();
+
+        let dock_widget_count = self.widgets.placed(WidgetPlacement::Dock).count();
+        let plugin_item_count = self.plugins.dock_items().count();
+        let dock_widget_style = self.widget_style(WidgetPlacement::Dock);
+
+        if dock_response.hovered() && layout.max_scroll > 0 {
+            let delta = ui.input(|i| i.raw_scroll_delta);
+            let delta = if delta.x != 0.0 { delta.x } else { delta.y };
+            if delta < 0.0 {
+                self.view.scroll = (self.view.scroll + 1).min(layout.max_scroll);
+            } else if delta > 0.0 {
+                self.view.scroll = self.view.scroll.saturating_sub(1);
+            }
+        }
+
+        for (rect, forward) in [(layout.scroll_back, false), (layout.scroll_forward, true)] {
+            let Some(rect) = rect else { continue };
+            let response = ui.interact(rect, egui::Id::new(("dock_scroll", forward)), egui::Sense::click());
+            paint_scroll_arrow(ui.painter(), rect, forward, border_color.gamma_multiply(4.0));
+            response.widget_info(|| {
+                let label = if forward { "Scroll forward" } else { "Scroll back" };
+                egui::WidgetInfo::labeled(egui::WidgetType::Button, label)
             });
 
-        let frame_response = frame.show(ui, |ui| {
-            ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
-                ui.spacing_mut().item_spacing.x = icon_spacing;
-
-                for item in &self.dock_items {
-                    if let Some(texture) = self.icon_textures.get(&item.path) {
-                        ui.vertical(|ui| {
-                            ui.spacing_mut().item_spacing.y = 2.0;
-
-                            let (rect, response) = ui.allocate_exact_size(icon_size, egui::Sense::click());
-                            let is_hovered = response.hovered();
-
-                            let display_size = if is_hovered {
-                                icon_size * hover_scale
-                            } else {
-                                icon_size
-                            };
-
-                            let icon_rect = egui::Rect::from_center_size(rect.center(), display_size);
-                            let image = egui::Image::new(texture);
-                            image.paint_at(ui, icon_rect);
-
-                            // App title tooltip
-                            let app_title = if item.hwnd.0 != 0 {
-                                let title = get_window_title(item.hwnd);
-                                if title.is_empty() {
-                                    Path::new(&item.path)
-                                        .file_stem()
-                                        .and_then(|s| s.to_str())
-                                        .unwrap_or("App")
-                                        .to_string()
-                                } else {
-                                    title
-                                }
-                            } else {
-                                Path::new(&item.path)
-                                    .file_stem()
-                                    .and_then(|s| s.to_str())
-                                    .unwrap_or("App")
-                                    .to_string()
-                            };
-
-                            let response = response.on_hover_text(app_title);
-
-                            if response.clicked() {
-                                if item.hwnd.0 != 0 && unsafe { IsWindow(item.hwnd).as_bool() } {
-                                    focus_or_minimize_window(item.hwnd);
-                                } else {
-                                    if is_uwp_app(&item.path) {
-                                        let _ = Command::new("explorer.exe").arg(&item.path).spawn();
-                                    } else {
-                                        let _ = Command::new(&item.path).spawn();
-                                    }
-                                }
-                            }
-
-                            // macOS Active Indicator Dot
-                            if show_active_indicators {
-                                let has_window = item.hwnd.0 != 0;
-                                let dot_color = if has_window {
-                                    egui::Color32::from_rgba_unmultiplied(240, 240, 245, 220)
-                                } else {
-                                    egui::Color32::TRANSPARENT
-                                };
-
-                                let dot_center = egui::pos2(rect.center().x, rect.max.y + 2.0);
-                                ui.painter().circle_filled(dot_center, 2.5, dot_color);
-                            }
-                        });
+            if response.clicked() {
+                self.view.scroll = if forward {
+                    (self.view.scroll + 1).min(layout.max_scroll)
+                } else {
+                    self.view.scroll.saturating_sub(1)
+                };
+            }
+        }
+
+        if let Some(rect) = layout.more {
+            let response = ui.interact(rect, egui::Id::new("dock_more"), egui::Sense::click());
+            let hidden = visible_items.len() + dock_widget_count + plugin_item_count - layout.items.len();
+            paint_more_item(ui.painter(), rect, hidden, border_color);
+            response.widget_info(|| {
+                egui::WidgetInfo::selected(
+                    egui::WidgetType::Button,
+                    self.view.overflow_open,
+                    format!("{hidden} more items"),
+                )
+            });
+
+            if response.on_hover_text(format!("{hidden} more")).clicked() {
+                self.view.overflow_open = !self.view.overflow_open;
+            }
+        } else {
+            self.view.overflow_open = false;
+        }
+
+        if let Some(popup) = &layout.overflow_popup {
+            paint_panel(ui.painter(), popup.rect);
+        }
+
+        // Item actions run after drawing, once the items are no longer borrowed
+        let mut clicked = None;
+        let mut menu_requested = None;
+        let mut plugin_item_clicked = None;
+
+        let popup_items = layout.overflow_popup.iter().flat_map(|popup| &popup.items);
+        for (position, item_layout) in layout.items.iter().chain(popup_items).enumerate() {
+            let Some(&(item, texture)) = visible_items.get(item_layout.index) else {
+                // Slots after the items hold the dock widgets, then the plugins' items
+                let widget_index = item_layout.index - visible_items.len();
+                if let Some(instance) = self.widgets.placed(WidgetPlacement::Dock).nth(widget_index) {
+                    ui.allocate_ui_at_rect(item_layout.icon, |ui| instance.widget.ui(ui, &dock_widget_style));
+                } else if let Some((plugin, plugin_item)) = self.plugins.dock_items().nth(widget_index - dock_widget_count) {
+                    let id = egui::Id::new(("plugin_item", plugin, &plugin_item.id));
+                    let response = ui.interact(item_layout.slot, id, egui::Sense::click());
+
+                    let texture_key = plugin_texture_key(&self.plugins.plugins[plugin].manifest.name, &plugin_item.id);
+                    match self.icon_textures.get(&texture_key) {
+                        Some(texture) => egui::Image::new(texture).paint_at(ui, item_layout.icon),
+                        None => paint_plugin_item(ui.painter(), item_layout.icon, &plugin_item.label, foreground_indicator_color),
+                    }
+
+                    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, &plugin_item.label));
+                    if response.on_hover_text(&plugin_item.label).clicked() {
+                        plugin_item_clicked = Some((plugin, plugin_item.id.clone()));
                     }
                 }
+                continue;
+            };
+            let rect = item_layout.slot;
+            let response = ui.interact(rect, egui::Id::new(("dock_item", &item.path)), egui::Sense::click());
+
+            let attention_started = self.attention.get(&item.path);
+            let launch_started = self.launches.pending_since(&item.path);
+            let launch_error = self.launches.error(&item.path);
+
+            // Launches bounce until the app shows a window or the launch times out
+            let bounce = match (launch_started, attention_started) {
+                _ if reduce_motion => 0.0,
+                (Some(started), _) => bounce_offset(
+                    started.elapsed().as_secs_f32(),
+                    bounce_duration,
+                    bounce_height,
+                    BounceRepeat::Continuous,
+                ),
+                (None, Some(started)) => bounce_offset(
+                    started.elapsed().as_secs_f32(),
+                    bounce_duration,
+                    bounce_height,
+                    attention_bounce,
+                ),
+                (None, None) => 0.0,
+            };
+
+            let icon_rect = item_layout.icon.translate(egui::vec2(0.0, -bounce));
+            let is_foreground = show_active_indicators && self.focus.is_foreground_app(&item.path);
+
+            let item_style = theme.items.resolve(ItemState {
+                active: self.focus.is_foreground_app(&item.path),
+                attention: attention_started.is_some(),
+                hovered: response.hovered(),
+                pressed: response.is_pointer_button_down_on(),
+            });
+            paint_item_background(ui.painter(), icon_rect.expand(3.0), &item_style);
+
+            if is_foreground && foreground_indicator_style == ForegroundIndicatorStyle::Glow {
+                paint_glow(ui.painter(), icon_rect, foreground_indicator_color);
+            }
+
+            // Without motion, a launching item is shown dimmed instead of bouncing
+            let tint = if reduce_motion && launch_started.is_some() {
+                item_style.tint.gamma_multiply(110.0 / 255.0)
+            } else {
+                item_style.tint
+            };
+            egui::Image::new(texture).tint(tint).paint_at(ui, icon_rect);
+
+            // Items in the dock row are mirrored in its floor, where they rest unmagnified
+            if let Some(style) = &theme.reflection
+                && position < layout.items.len()
+            {
+                let painter = ui.painter().with_clip_rect(dock_rect);
+                paint_reflection(&painter, texture.id(), icon_rect, item_layout.slot.bottom(), tint, style);
+            }
+
+            let badge_center = icon_rect.right_top() + egui::vec2(-4.0, 4.0);
+            if launch_error.is_some() {
+                ui.painter().circle_filled(badge_center, 7.0, launch_error_color);
+                ui.painter().text(
+                    badge_center,
+                    egui::Align2::CENTER_CENTER,
+                    "!",
+                    egui::FontId::proportional(11.0),
+                    egui::Color32::WHITE,
+                );
+            } else if let Some(text) = self.plugins.badge(&item.path) {
+                // Plugin badges widen to fit their text, up to four characters
+                let width = 14.0 + 6.0 * text.chars().count().saturating_sub(1) as f32;
+                let badge = egui::Rect::from_center_size(badge_center, egui::vec2(width, 14.0));
+                ui.painter().rect_filled(badge, egui::Rounding::same(7.0), attention_indicator_color);
+                ui.painter().text(
+                    badge.center(),
+                    egui::Align2::CENTER_CENTER,
+                    text,
+                    egui::FontId::proportional(10.0),
+                    egui::Color32::WHITE,
+                );
+            }
+
+            // App title tooltip
+            let window_title = match (&item.name, item.hwnd.0) {
+                (None, hwnd) if hwnd != 0 => get_window_title(item.hwnd),
+                _ => String::new(),
+            };
+            let app_title = if window_title.is_empty() {
+                app_name(item)
+            } else {
+                window_title
+            };
+
+            // Expose the item to screen readers as a button named after the app
+            let status = ItemStatus {
+                window_count: item.windows.len(),
+                active: self.focus.is_foreground_app(&item.path),
+                launching: launch_started.is_some(),
+                needs_attention: attention_started.is_some(),
+                launch_error,
+            };
+            response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, &app_title));
+            ui.ctx().accesskit_node_builder(response.id, |node| {
+                node.set_description(describe_item(&status));
+                node.add_action(egui::accesskit::Action::ShowContextMenu);
+            });
+
+            let app_title = match launch_error {
+                Some(error) => format!("{app_title}: failed to launch ({error})"),
+                None => app_title,
+            };
+
+            let response = response.on_hover_text(app_title);
+            let menu_action_requested = ui.input(|i| {
+                i.has_accesskit_action_request(response.id, egui::accesskit::Action::ShowContextMenu)
             });
+
+            if response.clicked() {
+                clicked = Some(item.path.clone());
+            } else if response.secondary_clicked() || menu_action_requested {
+                menu_requested = Some(item.path.clone());
+            }
+
+            // Keep egui's focus, which AccessKit reports, in step with the keyboard selection
+            if response.gained_focus() {
+                self.keyboard_selection = Some(item_layout.index);
+            } else if self.keyboard_selection == Some(item_layout.index) && !response.has_focus() {
+                response.request_focus();
+            }
+
+            if self.keyboard_selection == Some(item_layout.index) {
+                ui.painter().rect_stroke(
+                    rect.expand(3.0),
+                    egui::Rounding::same(rect.width() * 0.25),
+                    egui::Stroke::new(2.0, foreground_indicator_color),
+                );
+            }
+
+            // macOS Active Indicator Dot
+            if show_active_indicators {
+                let has_window = item.hwnd.0 != 0;
+                let dot_color = if attention_started.is_some() {
+                    attention_indicator_color
+                } else if has_window {
+                    running_indicator_color
+                } else {
+                    egui::Color32::TRANSPARENT
+                };
+
+                let dot_center = egui::pos2(rect.center().x, rect.max.y + 4.0);
+
+                if let Some(sprite) = &indicator {
+                    if is_foreground || dot_color != egui::Color32::TRANSPARENT {
+                        let tint = if attention_started.is_some() {
+                            attention_indicator_color
+                        } else {
+                            egui::Color32::WHITE
+                        };
+                        sprite.paint(ui.painter(), egui::Rect::from_center_size(dot_center, sprite.size()), tint);
+                    }
+                } else {
+                    match foreground_indicator_style {
+                        ForegroundIndicatorStyle::Dot if is_foreground => {
+                            ui.painter().circle_filled(dot_center, 3.5, foreground_indicator_color);
+                        }
+                        ForegroundIndicatorStyle::Underline if is_foreground => {
+                            let half_width = rect.width() * 0.2;
+                            ui.painter().line_segment(
+                                [
+                                    egui::pos2(dot_center.x - half_width, dot_center.y),
+                                    egui::pos2(dot_center.x + half_width, dot_center.y),
+                                ],
+                                egui::Stroke::new(3.0, foreground_indicator_color),
+                            );
+                        }
+                        _ => {
+                            ui.painter().circle_filled(dot_center, 2.5, dot_color);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(reflection) = &reflection {
+            reflection.paint(ui.painter(), reflection.bottom_strip(dock_rect), egui::Color32::WHITE);
+        }
+        if let Some(overlay) = &overlay {
+            overlay.paint(ui.painter(), dock_rect, egui::Color32::WHITE);
+        }
+
+        if let Some(path) = clicked {
+            self.activate_item(&path, false);
+            self.view.overflow_open = false;
+        }
+        if let Some(path) = menu_requested {
+            self.open_item_menu(&path, MenuPosition::Cursor);
+        }
+        if let Some((plugin, id)) = plugin_item_clicked {
+            self.plugins.click_dock_item(plugin, &id);
+            self.view.overflow_open = false;
+        }
+
+        // Right-clicking the dock itself, between or around its items, opens the theme menu
+        let background_clicked = ui.input(|i| {
+            i.pointer.secondary_clicked()
+                && i.pointer
+                    .interact_pos()
+                    .is_some_and(|pos| dock_rect.contains(pos) && layout.item_at(pos).is_none())
         });
+        if background_clicked {
+            self.open_theme_menu();
+        }
 
         // Hardware Win32 Cursor Position Hit-Testing for Passthrough
         let is_cursor_over_dock = unsafe {
             let mut cursor_pt = POINT::default();
             if GetCursorPos(&mut cursor_pt).is_ok() {
-                let main_hwnd = FindWindowW(windows::core::w!("eframe"), None);
-                let main_hwnd = if main_hwnd.0 == 0 {
-                    FindWindowW(None, windows::core::w!("FerroDock"))
-                } else {
-                    main_hwnd
-                };
+                let main_hwnd = find_dock_window();
 
                 if main_hwnd.0 != 0 {
                     let mut win_rect = RECT::default();
                     if GetWindowRect(main_hwnd, &mut win_rect).is_ok() {
                         let ppp = ui.ctx().pixels_per_point();
-                        let dock_rect = frame_response.response.rect;
 
-                        let dock_left = win_rect.left + (dock_rect.min.x * ppp) as i32;
-                        let dock_top = win_rect.top + (dock_rect.min.y * ppp) as i32;
-                        let dock_right = win_rect.left + (dock_rect.max.x * ppp) as i32;
-                        let dock_bottom = win_rect.top + (dock_rect.max.y * ppp) as i32;
+                        let hit_rect = layout.interactive_rect();
+                        let dock_left = win_rect.left + (hit_rect.min.x * ppp) as i32;
+                        let dock_top = win_rect.top + (hit_rect.min.y * ppp) as i32;
+                        let dock_right = win_rect.left + (hit_rect.max.x * ppp) as i32;
+                        let dock_bottom = win_rect.top + (hit_rect.max.y * ppp) as i32;
 
                         cursor_pt.x >= dock_left
                             && cursor_pt.x <= dock_right
@@ -237,18 +1393,135 @@ impl FerroDock {
                         false
                     }
                 } else {
-                    frame_response.response.hovered()
+                    dock_response.hovered()
                 }
             } else {
                 false
             }
-        } || frame_response.response.hovered();
+        } || dock_response.hovered();
 
         ui.ctx()
             .send_viewport_cmd(egui::ViewportCommand::MousePassthrough(!is_cursor_over_dock));
     }
 }
 
+/// The theme named by the `theme` setting, or the classic look if it is unset or fails to load.
+fn configured_theme(config: &Config, appearance: &SystemAppearance) -> (String, Theme) {
+    let name = config.theme.as_deref().unwrap_or(CLASSIC_THEME);
+    match theme::load_theme(name, config, appearance) {
+        Ok(theme) => (name.to_string(), theme),
+        Err(err) => {
+            eprintln!("Failed to load theme `{name}`, using the classic look: {err}");
+            (CLASSIC_THEME.to_string(), Theme::from_config(config))
+        }
+    }
+}
+
+/// The `icon_textures` key of a plugin dock item's icon.
+fn plugin_texture_key(plugin: &str, id: &str) -> String {
+    format!("{PLUGIN_TEXTURE_PREFIX}{plugin}/{id}")
+}
+
+/// Draws a plugin dock item without an icon as a rounded tile with its label's initial.
+fn paint_plugin_item(painter: &egui::Painter, rect: egui::Rect, label: &str, color: egui::Color32) {
+    painter.rect_filled(rect.shrink(rect.width() * 0.08), egui::Rounding::same(rect.width() * 0.22), color);
+    let initial = label.chars().next().map(|c| c.to_uppercase().to_string()).unwrap_or_default();
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        initial,
+        egui::FontId::proportional(rect.height() * 0.5),
+        egui::Color32::WHITE,
+    );
+}
+
+/// The name of the application behind `item`: its configured name, or its executable's name.
+fn app_name(item: &DockIcon) -> String {
+    match &item.name {
+        Some(name) => name.clone(),
+        None => Path::new(&item.path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("App")
+            .to_string(),
+    }
+}
+
+/// Paints the diagonal lines of a resize handle in the bottom-right corner of `rect`.
+fn paint_resize_grip(painter: &egui::Painter, rect: egui::Rect, color: egui::Color32) {
+    let stroke = egui::Stroke::new(1.0, color);
+    for inset in [4.0, 8.0] {
+        painter.line_segment(
+            [
+                egui::pos2(rect.max.x - inset, rect.max.y - 2.0),
+                egui::pos2(rect.max.x - 2.0, rect.max.y - inset),
+            ],
+            stroke,
+        );
+    }
+}
+
+/// Converts a point inside a viewport at `window_min` to physical screen pixels.
+fn to_screen_point(window_min: egui::Pos2, point: egui::Pos2, pixels_per_point: f32) -> POINT {
+    let screen = (window_min.to_vec2() + point.to_vec2()) * pixels_per_point;
+    POINT {
+        x: screen.x as i32,
+        y: screen.y as i32,
+    }
+}
+
+/// Finds the dock's own top-level window.
+fn find_dock_window() -> HWND {
+    unsafe {
+        let hwnd = FindWindowW(windows::core::w!("eframe"), None);
+        if hwnd.0 == 0 {
+            FindWindowW(None, windows::core::w!("FerroDock"))
+        } else {
+            hwnd
+        }
+    }
+}
+
+/// Paints a soft halo behind the icon of the foreground application.
+fn paint_glow(painter: &egui::Painter, icon_rect: egui::Rect, color: egui::Color32) {
+    let center = icon_rect.center();
+    let base_radius = icon_rect.width().max(icon_rect.height()) * 0.5;
+
+    for step in 0..4 {
+        let radius = base_radius + 2.0 * step as f32;
+        let alpha = 40u8.saturating_sub(step * 10);
+        painter.circle_filled(center, radius, color.gamma_multiply(alpha as f32 / 255.0));
+    }
+}
+
+/// Paints a chevron pointing towards the items scrolled out of view.
+fn paint_scroll_arrow(painter: &egui::Painter, rect: egui::Rect, forward: bool, color: egui::Color32) {
+    let center = rect.center();
+    let half = (rect.width() * 0.3).min(5.0);
+    let dir = if forward { 1.0 } else { -1.0 };
+
+    painter.add(egui::Shape::line(
+        vec![
+            center + egui::vec2(-dir * half * 0.5, -half),
+            center + egui::vec2(dir * half * 0.5, 0.0),
+            center + egui::vec2(-dir * half * 0.5, half),
+        ],
+        egui::Stroke::new(2.0, color),
+    ));
+}
+
+/// Paints the "more" item as a rounded tile with the number of collapsed items.
+fn paint_more_item(painter: &egui::Painter, rect: egui::Rect, hidden: usize, color: egui::Color32) {
+    painter.rect_filled(rect.shrink(2.0), egui::Rounding::same(rect.width() * 0.2), color);
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        format!("+{hidden}"),
+        egui::FontId::proportional(rect.height() * 0.35),
+        egui::Color32::WHITE,
+    );
+}
+
 impl App for FerroDock {
     /// Updates the dock's position, application state, icon textures, and rendered UI.
     ///
@@ -260,75 +1533,259 @@ impl App for FerroDock {
     /// let mut frame = eframe::Frame::default();
     ///
     /// dock.update(&context, &mut frame);
-    /// assert!(dock.position_set);
+    /// assert!(dock.window_rect.is_some());
     /// ```
     fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
-        if !self.position_set {
-            self.position_set = true;
-            let mut work_area = RECT::default();
-            unsafe {
-                let _ = windows::Win32::UI::WindowsAndMessaging::SystemParametersInfoW(
-                    windows::Win32::UI::WindowsAndMessaging::SPI_GETWORKAREA,
-                    0,
-                    Some(&mut work_area as *mut _ as *mut _),
-                    windows::Win32::UI::WindowsAndMessaging::SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-                );
+        let tooltip_style = self.current_theme().tooltip;
+        if self.applied_tooltip_style != Some(tooltip_style) {
+            apply_tooltip_style(ctx, &tooltip_style);
+            self.applied_tooltip_style = Some(tooltip_style);
+        }
+
+        if let Some(skin) = &self.theme.skin {
+            for path in skin.images() {
+                if self.skin_textures.contains_key(path) {
+                    continue;
+                }
+                let texture = match load_skin_image(path) {
+                    Ok(image) => Some(ctx.load_texture(path.display().to_string(), image, egui::TextureOptions::LINEAR)),
+                    Err(err) => {
+                        eprintln!("Skipping skin image: {err}");
+                        None
+                    }
+                };
+                self.skin_textures.insert(path.to_path_buf(), texture);
             }
-            let hdc = unsafe { windows::Win32::Graphics::Gdi::GetDC(None) };
-            let dpi = if hdc.is_invalid() {
-                96.0
-            } else {
-                let d = unsafe { windows::Win32::Graphics::Gdi::GetDeviceCaps(hdc, windows::Win32::Graphics::Gdi::LOGPIXELSX) } as f32;
-                let _ = unsafe { windows::Win32::Graphics::Gdi::ReleaseDC(None, hdc) };
-                d
-            };
-            let scale_factor = (dpi / 96.0).max(1.0);
-            let work_left = (work_area.left as f32) / scale_factor;
-            let work_right = (work_area.right as f32) / scale_factor;
-            let work_bottom = (work_area.bottom as f32) / scale_factor;
-            let work_w = work_right - work_left;
-            let dock_width = (work_w * 0.5).min(750.0);
-            let dock_height = 80.0;
-            let pos_x = work_left + (work_w - dock_width) / 2.0;
-            let pos_y = work_bottom - dock_height - 2.0;
+        }
 
-            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(pos_x, pos_y)));
-            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(dock_width, dock_height)));
+        if self.process_window_events() || self.is_animating() {
+            ctx.request_repaint();
         }
+        self.receive_native_menus(ctx);
 
-        if self.process_window_events() {
+        // Launches time out, their process exits or their error expires without any shell event
+        // to notice it; the items of dropped launches may need to leave the dock
+        if !self.launches.is_empty() && self.launches.update(&self.dock_items, self.launch_timeout()) {
+            self.refresh_dock_items();
             ctx.request_repaint();
         }
+        if let Some(expiry) = self.launches.next_expiry() {
+            ctx.request_repaint_after(expiry);
+        }
 
         if self.pending_sync_frames > 0 {
             self.pending_sync_frames -= 1;
-            self.dock_items = update_running_apps();
+            self.refresh_dock_items();
+
+            // Windows often become dock-worthy only after their activation event arrived
+            let foreground = unsafe { GetForegroundWindow() };
+            if self.focus.foreground_window() != Some(foreground) {
+                self.focus.apply_activation(foreground, &self.dock_items);
+            }
+
             ctx.request_repaint();
         }
 
+        // Scalable icons are rasterized for the largest size they are drawn at, magnified, in
+        // physical pixels; a new bucket, e.g. on a display with another scale, renders them again
+        let largest_icon = self.config.icon_size.max_elem() * self.config.hover_scale.max(1.0);
+        let bucket = size_bucket(largest_icon * ctx.pixels_per_point());
+        if bucket != self.icon_bucket {
+            self.icon_bucket = bucket;
+            self.icon_rasters.retain_bucket(bucket);
+            // Until redrawn, icons keep their old texture, scaled
+            self.stale_icons.extend(self.scalable_icons.keys().cloned());
+            // Styled icons are drawn at the bucket's size too
+            let style = &self.config.icon_style;
+            if style.normalize || style.plate || style.shadow {
+                let app_icons = self.icon_textures.keys().filter(|path| !path.starts_with(PLUGIN_TEXTURE_PREFIX));
+                self.stale_icons.extend(app_icons.cloned());
+            }
+            self.failed_icons.clear();
+        }
+
+        let dark = self.is_dark();
+        let mut redraws = ICON_REDRAWS_PER_FRAME;
         for icon in &self.dock_items {
-            if !self.icon_textures.contains_key(&icon.path) {
-                let color_image = if is_uwp_app(&icon.path) {
-                    get_uwp_icon(&icon.path)
-                } else {
-                    hicon_to_color_image(icon.hicon)
-                };
+            let missing =
+                !self.icon_textures.contains_key(&icon.path) && self.failed_icons.get(&icon.path) != Some(&icon.hicon.0);
+            let redraw = redraws > 0 && self.stale_icons.remove(&icon.path);
+            if redraw {
+                redraws -= 1;
+            }
+            if missing || redraw {
+                // Pins, then rules, then icon packs; an override that fails to load falls back
+                // to the application's own icon
+                let override_icon = find_pin(&self.config.pinned, &icon.path)
+                    .and_then(|pin| pin.icon.as_ref())
+                    .map(|path| IconRef::parse(&path.to_string_lossy(), Path::new("")))
+                    .or_else(|| icon.icon.as_deref().map(|text| IconRef::parse(text, Path::new(""))))
+                    .or_else(|| self.icon_packs.lookup(&icon.path, icon.aumid.as_deref(), icon.class.as_deref()));
+                let uwp_icon = std::iter::once_with(|| {
+                    is_uwp_app(&icon.path)
+                        .then(|| get_uwp_icon_path(&icon.path, dark))
+                        .flatten()
+                        .map(IconRef::Image)
+                })
+                .flatten();
+
+                // Packaged apps' logos are drawn on their tile colour
+                let style = &self.config.icon_style;
+                let background = (style.plate && is_uwp_app(&icon.path))
+                    .then(|| get_uwp_background_color(&icon.path))
+                    .flatten();
+                let styled = |image| style_icon(image, style, self.icon_bucket as usize, background);
 
-                if let Some(color_image) = color_image {
-                    let texture = ctx.load_texture(&icon.path, color_image, Default::default());
-                    self.icon_textures.insert(icon.path.clone(), texture);
+                let mut texture = None;
+                for source in override_icon.into_iter().chain(uwp_icon) {
+                    if source.is_scalable() {
+                        texture = self.icon_rasters.texture(ctx, &source, self.icon_bucket, styled);
+                        if texture.is_some() {
+                            self.scalable_icons.insert(icon.path.clone(), source);
+                        }
+                    } else {
+                        texture = source
+                            .load(self.icon_bucket)
+                            .map(|image| ctx.load_texture(&icon.path, styled(image), Default::default()));
+                    }
+                    if texture.is_some() {
+                        break;
+                    }
+                }
+
+                let texture = texture.or_else(|| {
+                    // Pinned applications that are not running have no window to ask
+                    let color_image = if icon.windows.is_empty() {
+                        get_file_icon(&icon.path)
+                    } else {
+                        hicon_to_color_image(icon.hicon)
+                    };
+                    color_image.map(|image| ctx.load_texture(&icon.path, styled(image), Default::default()))
+                });
+                match texture {
+                    Some(texture) => {
+                        self.icon_textures.insert(icon.path.clone(), texture);
+                    }
+                    None => {
+                        self.failed_icons.insert(icon.path.clone(), icon.hicon.0);
+                    }
+                }
+            }
+        }
+        if !self.stale_icons.is_empty() {
+            let dock_items = &self.dock_items;
+            self.stale_icons.retain(|path| dock_items.iter().any(|item| &item.path == path));
+            ctx.request_repaint();
+        }
+
+        for (plugin, item) in self.plugins.dock_items() {
+            let plugin = &self.plugins.plugins[plugin];
+            let key = plugin_texture_key(&plugin.manifest.name, &item.id);
+            let Some(icon) = item.icon.as_ref().map(|icon| plugin.dir.join(icon)) else {
+                continue;
+            };
+            if self.icon_textures.contains_key(&key) || self.failed_plugin_icons.contains(&icon) {
+                continue;
+            }
+            match load_png_as_color_image(&icon) {
+                Some(color_image) => {
+                    let texture = ctx.load_texture(&key, color_image, Default::default());
+                    self.icon_textures.insert(key, texture);
                 }
+                None => {
+                    eprintln!("Skipping plugin icon {}", icon.display());
+                    self.failed_plugin_icons.insert(icon);
+                }
+            }
+        }
+
+        self.view.hovered = ctx
+            .pointer_hover_pos()
+            .and_then(|pos| self.layout.item_at(pos));
+        let item_count = self
+            .dock_items
+            .iter()
+            .filter(|item| self.icon_textures.contains_key(&item.path))
+            .count();
+        self.handle_keyboard(ctx, item_count);
+        if std::mem::take(&mut self.focus_requested) {
+            self.focus_pending = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+
+        if self.config.menu_bar.enabled {
+            let requested = menu_bar_rect(&self.monitor, self.config.menu_bar.height);
+            // Until the shell grants a strip, the bar is shown where it asked for one
+            let bar = self
+                .menu_bar
+                .as_ref()
+                .filter(|reserved| reserved.requested == requested)
+                .map_or(requested, |reserved| reserved.granted);
+
+            // Keep the clock current
+            ctx.request_repaint_after(Duration::from_secs(1));
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of("ferro_menu_bar"),
+                egui::ViewportBuilder::default()
+                    .with_title(MENU_BAR_TITLE)
+                    .with_decorations(false)
+                    .with_always_on_top()
+                    .with_resizable(false)
+                    .with_taskbar(false)
+                    .with_position(bar.min)
+                    .with_inner_size(bar.size()),
+                |ctx, _class| self.draw_menu_bar(ctx, bar),
+            );
+            self.reserve_menu_bar(requested);
+        } else {
+            self.release_menu_bar();
+        }
+
+        if self.plugins.wants("tick") {
+            if self.plugin_tick.elapsed() >= Duration::from_secs(1) {
+                self.plugin_tick = Instant::now();
+                self.plugins.dispatch(&PluginEvent::Tick);
+            }
+            ctx.request_repaint_after(Duration::from_secs(1).saturating_sub(self.plugin_tick.elapsed()));
+        }
+        for (id, placement, widget) in self.plugins.take_new_widgets() {
+            self.widgets.add(id, placement, widget);
+        }
+
+        if let Some(next_update) = self.widgets.tick(Instant::now()) {
+            ctx.request_repaint_after(next_update);
+        }
+        self.draw_desktop_widgets(ctx);
+        self.draw_first_run_wizard(ctx);
+
+        // Hover magnification is an animation too
+        let view = if self.accessibility.reduce_motion {
+            ViewState {
+                hovered: None,
+                ..self.view
             }
+        } else {
+            self.view
+        };
+        let slot_count =
+            item_count + self.widgets.placed(WidgetPlacement::Dock).count() + self.plugins.dock_items().count();
+        let layout = compute_layout(&self.monitor, &self.config, slot_count, &view);
+        self.view.scroll = self.view.scroll.min(layout.max_scroll);
+
+        // The window follows its content, so re-apply its geometry whenever it changes
+        if self.window_rect != Some(layout.window) {
+            self.window_rect = Some(layout.window);
+            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(layout.window.min));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(layout.window.size()));
         }
 
-        egui::Area::new(egui::Id::new("ferro_dock_area"))
-            .anchor(
-                egui::Align2::CENTER_BOTTOM,
-                egui::vec2(0.0, -self.config.bottom_margin),
-            )
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none())
             .show(ctx, |ui| {
-                self.draw_dock_ui(ui);
+                self.draw_dock_ui(ui, &layout);
             });
+
+        self.layout = layout;
     }
 
     /// Uses a fully transparent color to clear the application viewport.
@@ -342,6 +1799,24 @@ impl App for FerroDock {
     fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
         egui::Color32::TRANSPARENT.to_normalized_gamma_f32()
     }
+
+    /// Saves where desktop widgets were moved to and how they were sized, the theme in use and
+    /// whether the first-run wizard was answered.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, WIDGET_GEOMETRY_KEY, &self.widgets.geometry);
+
+        let choice = ThemeChoice {
+            configured: self.config.theme.clone(),
+            chosen: self.theme_name.clone(),
+        };
+        eframe::set_value(storage, THEME_KEY, &choice);
+        eframe::set_value(storage, FIRST_RUN_KEY, &self.first_run_done);
+    }
+
+    /// Gives the menu bar strip back to other windows.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.release_menu_bar();
+    }
 }
 
 #[cfg(test)]
@@ -353,28 +1828,78 @@ mod tests {
     /// supply their own event channel instead of spinning up the real Win32
     /// shell-hook listener thread.
     fn make_dock_with_receiver(receiver: Receiver<WindowEvent>) -> FerroDock {
+        let config = Config::default();
+        let monitor = MonitorGeometry::default();
+        let accessibility = AccessibilityModes::default();
+        let layout = compute_layout(&monitor, &config, 0, &ViewState::default());
+
         FerroDock {
-            config: Config::default(),
+            rules: RuleSet::new(&config.effective_window_rules()),
+            processes: ProcessResolver::default(),
+            config,
             dock_items: Vec::new(),
             icon_textures: HashMap::new(),
+            focus: FocusTracker::default(),
+            attention: HashMap::new(),
+            launches: LaunchTracker::default(),
+            monitor,
+            accessibility,
+            layout,
+            view: ViewState::default(),
+            window_rect: None,
+            keyboard_selection: None,
+            focus_requested: false,
+            focus_pending: false,
+            menu_key_down: false,
+            menu_bar: None,
+            menu_reader: MenuReader::start(),
+            menu_target: None,
+            native_menu: None,
+            pending_menu_open: None,
+            widgets: WidgetHost::default(),
+            plugins: PluginHost::default(),
+            plugin_tick: Instant::now(),
+            theme_name: CLASSIC_THEME.to_string(),
+            theme: Theme::default(),
+            appearance: SystemAppearance::default(),
+            skin_textures: HashMap::new(),
+            stale_icons: HashSet::new(),
+            failed_icons: HashMap::new(),
+            failed_plugin_icons: HashSet::new(),
+            icon_packs: IconPacks::default(),
+            icon_rasters: IconRasters::default(),
+            scalable_icons: HashMap::new(),
+            icon_bucket: 0,
+            first_run: None,
+            first_run_done: false,
+            applied_tooltip_style: None,
             pending_sync_frames: 0,
-            position_set: false,
             event_receiver: receiver,
         }
     }
 
     #[test]
-    fn default_initializes_position_set_to_false() {
+    fn default_leaves_window_unplaced() {
         let dock = FerroDock::default();
-        assert!(!dock.position_set);
+        assert!(dock.window_rect.is_none());
         assert_eq!(dock.pending_sync_frames, 0);
         assert!(dock.icon_textures.is_empty());
     }
 
     #[test]
-    fn new_initializes_position_set_to_false() {
+    fn new_leaves_window_unplaced() {
         let dock = FerroDock::new();
-        assert!(!dock.position_set);
+        assert!(dock.window_rect.is_none());
+
+        // Every slot is in the row unless the overflow policy collapsed or scrolled some away
+        let slot_count = dock.dock_items.len()
+            + dock.widgets.placed(WidgetPlacement::Dock).count()
+            + dock.plugins.dock_items().count();
+        if dock.layout.more.is_none() && dock.layout.max_scroll == 0 {
+            assert_eq!(dock.layout.items.len(), slot_count);
+        } else {
+            assert!(dock.layout.items.len() < slot_count);
+        }
     }
 
     #[test]
@@ -435,4 +1960,173 @@ mod tests {
             assert!(active_paths.contains(path));
         }
     }
+
+    #[test]
+    fn display_change_forces_window_to_be_placed_again() {
+        let (sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+        dock.window_rect = Some(dock.layout.window);
+
+        sender.send(WindowEvent::DisplayChanged).unwrap();
+        assert!(dock.process_window_events());
+
+        assert!(dock.window_rect.is_none());
+    }
+
+    fn item(path: &str, hwnd: isize) -> DockIcon {
+        DockIcon {
+            path: path.to_string(),
+            name: None,
+            icon: None,
+            aumid: None,
+            class: None,
+            hicon: windows::Win32::UI::WindowsAndMessaging::HICON(0),
+            hwnd: HWND(hwnd),
+            windows: vec![HWND(hwnd)],
+        }
+    }
+
+    fn blank_texture() -> TextureHandle {
+        egui::Context::default().load_texture(
+            "blank",
+            egui::ColorImage::new([1, 1], egui::Color32::TRANSPARENT),
+            egui::TextureOptions::default(),
+        )
+    }
+
+    #[test]
+    fn redraws_keep_icons_whose_handle_is_unchanged() {
+        let (sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+        // Not a real window, so it has no icon that could have changed
+        dock.dock_items.push(item("C:\\ticking\\clock.exe", 0x7fff_fff0));
+        dock.icon_textures.insert("C:\\ticking\\clock.exe".to_string(), blank_texture());
+
+        sender.send(WindowEvent::WindowRedrawn(HWND(0x7fff_fff0))).unwrap();
+        assert!(dock.process_window_events());
+
+        assert!(dock.icon_textures.contains_key("C:\\ticking\\clock.exe"));
+        assert_eq!(dock.pending_sync_frames, 0, "redraws must not re-enumerate windows");
+    }
+
+    #[test]
+    fn flashing_windows_ask_for_attention_until_activated() {
+        let (_sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+        dock.dock_items = vec![item("C:\\a.exe", 0x7fff_fff1), item("C:\\b.exe", 0x7fff_fff2)];
+
+        dock.apply_window_event(WindowEvent::WindowFlashed(HWND(0x7fff_fff2)));
+        dock.apply_window_event(WindowEvent::WindowFlashed(HWND(0x7fff_fff3)));
+        assert_eq!(dock.attention.keys().collect::<Vec<_>>(), ["C:\\b.exe"]);
+
+        dock.apply_window_event(WindowEvent::WindowActivated(HWND(0x7fff_fff2)));
+        assert!(dock.attention.is_empty());
+    }
+
+    #[test]
+    fn focus_hotkey_requests_focus_and_selects_the_first_item() {
+        let (sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+
+        sender.send(WindowEvent::Hotkey(HotkeyAction::FocusDock)).unwrap();
+        dock.process_window_events();
+
+        assert!(dock.focus_requested);
+        assert_eq!(dock.keyboard_selection, Some(0));
+    }
+
+    /// Runs `handle_keyboard` in a frame where the dock has `focused` and `keys` were pressed.
+    fn keyboard_frame(dock: &mut FerroDock, focused: bool, keys: &[egui::Key]) {
+        let ctx = egui::Context::default();
+        let mut input = egui::RawInput::default();
+        input.viewports.insert(
+            egui::ViewportId::ROOT,
+            egui::ViewportInfo {
+                focused: Some(focused),
+                ..Default::default()
+            },
+        );
+        input.events = keys
+            .iter()
+            .map(|&key| egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: egui::Modifiers::NONE,
+            })
+            .collect();
+
+        ctx.begin_frame(input);
+        dock.handle_keyboard(&ctx, 3);
+        let _ = ctx.end_frame();
+    }
+
+    #[test]
+    fn focus_hotkey_selection_survives_until_the_dock_is_focused() {
+        let (sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+
+        sender.send(WindowEvent::Hotkey(HotkeyAction::FocusDock)).unwrap();
+        dock.process_window_events();
+        keyboard_frame(&mut dock, false, &[]);
+        assert_eq!(dock.keyboard_selection, Some(0));
+
+        dock.focus_requested = false;
+        dock.focus_pending = true;
+        keyboard_frame(&mut dock, false, &[]);
+        assert_eq!(dock.keyboard_selection, Some(0));
+
+        keyboard_frame(&mut dock, true, &[egui::Key::ArrowRight]);
+        assert_eq!(dock.keyboard_selection, Some(1));
+        assert!(!dock.focus_pending);
+
+        // Losing focus afterwards ends keyboard navigation
+        keyboard_frame(&mut dock, false, &[]);
+        assert_eq!(dock.keyboard_selection, None);
+    }
+
+    #[test]
+    fn keyboard_moves_the_selection_within_the_items() {
+        let (_sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+
+        keyboard_frame(&mut dock, true, &[egui::Key::ArrowLeft]);
+        assert_eq!(dock.keyboard_selection, None);
+
+        keyboard_frame(&mut dock, true, &[egui::Key::Tab]);
+        assert_eq!(dock.keyboard_selection, Some(0));
+
+        keyboard_frame(&mut dock, true, &[egui::Key::End]);
+        assert_eq!(dock.keyboard_selection, Some(2));
+        keyboard_frame(&mut dock, true, &[egui::Key::ArrowRight]);
+        assert_eq!(dock.keyboard_selection, Some(2));
+        keyboard_frame(&mut dock, true, &[egui::Key::Home]);
+        assert_eq!(dock.keyboard_selection, Some(0));
+
+        keyboard_frame(&mut dock, true, &[egui::Key::Escape]);
+        assert_eq!(dock.keyboard_selection, None);
+    }
+
+    #[test]
+    fn infinite_launch_timeouts_never_expire() {
+        let (_sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+
+        dock.config.launch_timeout_secs = f32::INFINITY;
+        assert_eq!(dock.launch_timeout(), Duration::MAX);
+        dock.config.launch_timeout_secs = -1.0;
+        assert_eq!(dock.launch_timeout(), Duration::ZERO);
+    }
+
+    #[test]
+    fn reduce_motion_stops_attention_bounces() {
+        let (_sender, receiver) = mpsc::channel();
+        let mut dock = make_dock_with_receiver(receiver);
+        dock.attention.insert("C:\\a.exe".to_string(), Instant::now());
+        assert!(dock.is_animating());
+
+        dock.accessibility.reduce_motion = true;
+        assert!(!dock.is_animating());
+    }
 }
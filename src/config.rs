@@ -1,9 +1,18 @@
 use eframe::egui;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+const CONFIG_FILE_NAME: &str = "config.toml";
 
 // Configuration
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct Config {
+    /// The theme to draw with: a built-in theme such as `macos`, or the name of a file in the
+    /// `themes` directory. Unset, the dock is drawn with the colours below.
+    pub theme: Option<String>,
+    /// Whether the light or dark variant of the theme is drawn, e.g. `macos-light` for `macos`.
+    pub appearance: Appearance,
     pub rounding: f32,
     pub icon_size: egui::Vec2,
     pub icon_spacing: f32,
@@ -14,13 +23,380 @@ pub struct Config {
     pub left_margin: f32,
     pub top_margin: f32,
     pub bottom_margin: f32,
+    /// Distance between the dock window and the bottom of the work area.
+    pub screen_edge_gap: f32,
     pub show_active_indicators: bool,
+    pub foreground_indicator_style: ForegroundIndicatorStyle,
+    pub foreground_indicator_color: egui::Color32,
+    pub attention_indicator_color: egui::Color32,
+    pub attention_bounce: BounceRepeat,
+    pub bounce_height: f32,
+    pub bounce_duration: f32,
+    pub launch_timeout_secs: f32,
+    pub launch_error_color: egui::Color32,
     pub hover_scale: f32,
+    /// What happens when the items no longer fit on the monitor.
+    pub overflow: OverflowPolicy,
+    /// The smallest icon edge `OverflowPolicy::Shrink` scales icons down to.
+    pub min_icon_size: f32,
+    pub hotkeys: HotkeyConfig,
+    pub accessibility: AccessibilityConfig,
+    pub menu_bar: MenuBarConfig,
+    /// Applications kept in the dock while they are not running, shown first in this order.
+    pub pinned: Vec<PinnedItem>,
+    /// Icon packs in the `icon_packs` directory to draw app icons from, the first match winning.
+    pub icon_packs: Vec<String>,
+    /// Post-processing that gives icons of different shapes and paddings a uniform look.
+    pub icon_style: IconStyleConfig,
+    /// Widgets to show, in the order they appear in the dock or menu bar.
+    pub widgets: Vec<WidgetConfig>,
+    pub plugins: PluginConfig,
+    /// Rules deciding which windows appear in the dock, evaluated in order before the
+    /// built-in rules from `default_window_rules`, which decide the windows these leave open.
+    pub window_rules: Vec<WindowRule>,
+}
+
+impl Config {
+    /// Loads `config.toml` from next to the executable, falling back to the working directory.
+    ///
+    /// A missing file yields the default configuration; a malformed one is reported and ignored.
+    pub fn load() -> Self {
+        let Some(path) = config_path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path).map(|text| toml::from_str::<Config>(&text)) {
+            Ok(Ok(config)) => config,
+            Ok(Err(err)) => {
+                eprintln!("Ignoring invalid {}: {err}", path.display());
+                Self::default()
+            }
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    /// The rules windows are matched against: the configured ones, then the built-in ones.
+    pub fn effective_window_rules(&self) -> Vec<WindowRule> {
+        self.window_rules.iter().cloned().chain(default_window_rules()).collect()
+    }
+}
+
+/// The file settings are written to: the `config.toml` that is read, or a new one next to
+/// the executable when there is none yet.
+pub fn writable_config_path() -> PathBuf {
+    config_path()
+        .or_else(|| {
+            let exe = std::env::current_exe().ok()?;
+            Some(exe.parent()?.join(CONFIG_FILE_NAME))
+        })
+        .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME))
+}
+
+fn config_path() -> Option<PathBuf> {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(CONFIG_FILE_NAME)));
+
+    beside_exe
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(CONFIG_FILE_NAME)))
+        .find(|path| path.is_file())
+}
+
+/// A rule deciding whether, and how, a top-level window appears in the dock.
+///
+/// Every matcher that is set must match for the rule to apply; unset matchers match anything.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WindowRule {
+    /// Glob matched case-insensitively against the full executable path, e.g. `"*\\notepad.exe"`.
+    pub exe: Option<String>,
+    /// Window class name, compared exactly.
+    pub class: Option<String>,
+    /// Regular expression searched for in the window title.
+    pub title: Option<String>,
+    /// Application User Model ID of a packaged app, compared case-insensitively.
+    pub aumid: Option<String>,
+    pub tool_window: Option<bool>,
+    pub app_window: Option<bool>,
+    pub owned: Option<bool>,
+    pub cloaked: Option<bool>,
+    pub action: RuleAction,
+}
+
+/// What happens to a window matched by a `WindowRule`.
+///
+/// In `config.toml` this is written as `"include"`, `"exclude"`,
+/// `{ group_under = "C:\\path\\to\\app.exe" }`, `{ rename = "Name" }` or
+/// `{ icon = "C:\\icons\\app.svg" }`.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    #[default]
+    Include,
+    Exclude,
+    /// Shows the window under the dock item of another application path.
+    GroupUnder(String),
+    /// Overrides the name shown for the window's dock item.
+    Rename(String),
+    /// Draws the window's dock item with a PNG, ICO or SVG file, or an icon inside an
+    /// executable or DLL written as `path,index`.
+    Icon(String),
+}
+
+/// The built-in rules, mirroring how the Windows taskbar decides which windows to show.
+pub fn default_window_rules() -> Vec<WindowRule> {
+    let exclude = |rule: WindowRule| WindowRule {
+        action: RuleAction::Exclude,
+        ..rule
+    };
+
+    let mut rules = vec![
+        exclude(WindowRule {
+            tool_window: Some(true),
+            ..Default::default()
+        }),
+        exclude(WindowRule {
+            owned: Some(true),
+            app_window: Some(false),
+            ..Default::default()
+        }),
+        exclude(WindowRule {
+            cloaked: Some(true),
+            ..Default::default()
+        }),
+    ];
+
+    // Known background system apps that aren't user-facing
+    for exe in [
+        "TextInputHost.exe",           // Windows IME
+        "SearchHost.exe",              // Windows Search
+        "StartMenuExperienceHost.exe", // Start Menu
+        "ShellExperienceHost.exe",     // Shell components
+        "LockApp.exe",                 // Lock screen
+    ] {
+        rules.push(exclude(WindowRule {
+            exe: Some(format!("*\\{exe}")),
+            ..Default::default()
+        }));
+    }
+
+    rules
+}
+
+/// How often an item bounces once an animation is triggered.
+///
+/// In `config.toml` this is written as `{ count = 3 }` or `"continuous"`.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BounceRepeat {
+    Count(u32),
+    Continuous,
+}
+
+/// How the item of the application owning the foreground window is distinguished.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForegroundIndicatorStyle {
+    /// A larger, brighter version of the running indicator dot.
+    Dot,
+    /// A short bar underneath the icon.
+    Underline,
+    /// A soft halo behind the icon.
+    Glow,
+}
+
+/// Global keyboard shortcuts, written like `"ctrl+alt+d"`. An empty string disables a shortcut.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HotkeyConfig {
+    /// Modifiers held with 1–9 to activate, cycle or launch the nth dock item.
+    pub item_modifiers: String,
+    /// Brings the dock to the foreground so it can be operated with the keyboard.
+    pub focus_dock: String,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        // Win+digit and Win+Alt+digit already belong to the taskbar
+        Self {
+            item_modifiers: "ctrl+alt".to_string(),
+            focus_dock: "ctrl+alt+d".to_string(),
+        }
+    }
+}
+
+/// Overrides for the OS accessibility preferences. Unset values follow the OS.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Replaces animations with instant state changes; follows "Show animations in Windows".
+    pub reduce_motion: Option<bool>,
+    /// Draws the dock with solid system contrast colours; follows the contrast theme.
+    pub high_contrast: Option<bool>,
+}
+
+/// The menu bar along the top edge of the primary monitor.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MenuBarConfig {
+    /// Off by default, as the strip takes screen space from every other window.
+    pub enabled: bool,
+    /// Height of the strip, which is reserved so maximized windows stay below it.
+    pub height: f32,
+    pub background_color: egui::Color32,
+    pub text_color: egui::Color32,
+    /// Shows the clock as 14:05 rather than 2:05 PM.
+    pub twenty_four_hour_clock: bool,
+}
+
+impl Default for MenuBarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            height: 26.0,
+            background_color: egui::Color32::from_rgba_unmultiplied(20, 20, 25, 230),
+            text_color: egui::Color32::from_rgb(240, 240, 245),
+            twenty_four_hour_clock: true,
+        }
+    }
+}
+
+/// Steps applied to every icon before it is drawn. All are off by default.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct IconStyleConfig {
+    /// Crops transparent borders and scales what remains to the same visual size.
+    pub normalize: bool,
+    /// Share of the icon's edge the visible content is scaled to, between 0.1 and 1.
+    pub content_scale: f32,
+    /// Places icons on a squircle plate in the packaged app's `BackgroundColor`, or else the
+    /// icon's dominant colour.
+    pub plate: bool,
+    /// Draws a soft shadow below each icon.
+    pub shadow: bool,
+}
+
+impl Default for IconStyleConfig {
+    fn default() -> Self {
+        Self {
+            normalize: false,
+            content_scale: 0.84,
+            plate: false,
+            shadow: false,
+        }
+    }
+}
+
+/// An application kept in the dock while it is not running.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PinnedItem {
+    /// Full path of the executable, matched case-insensitively against running applications.
+    pub path: String,
+    /// Name shown instead of the executable's.
+    pub name: Option<String>,
+    /// A PNG, ICO or SVG file drawn instead of the application's icon, or an icon inside an
+    /// executable or DLL written as `path,index`.
+    pub icon: Option<PathBuf>,
+}
+
+/// A widget and where it is shown.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WidgetConfig {
+    /// The built-in widget to show: `clock`, `calendar`, `system` or `disk`.
+    pub kind: String,
+    /// Names the widget's saved position and size. Defaults to its kind, numbered when the
+    /// same kind appears more than once.
+    pub id: Option<String>,
+    pub placement: WidgetPlacement,
+    /// Overrides how often the widget refreshes its data.
+    pub update_interval_secs: Option<f32>,
+    /// Widget-specific settings, checked against the settings the widget declares.
+    pub settings: toml::Table,
+}
+
+/// Where a widget is drawn.
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WidgetPlacement {
+    /// In its own borderless window, which can be dragged and resized.
+    #[default]
+    Desktop,
+    /// In a slot after the dock items.
+    Dock,
+    /// In the menu bar's status area, left of the clock.
+    MenuBar,
+}
+
+/// WebAssembly plugins loaded from the `plugins` directory.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PluginConfig {
+    pub enabled: bool,
+    /// The permissions granted to each plugin, by the name in its manifest. A plugin only
+    /// loads once every permission it declares has been granted here.
+    pub approved: BTreeMap<String, Vec<PluginPermission>>,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            approved: BTreeMap::new(),
+        }
+    }
+}
+
+/// Something a plugin may do through the host API.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginPermission {
+    /// Add its own items to the dock.
+    DockItems,
+    /// Add entries to the context menu of every dock item.
+    ContextMenu,
+    /// Show badges on dock items.
+    Badges,
+    /// Show widgets.
+    Widgets,
+    /// Receive window and dock events.
+    Events,
+    /// Read the configuration.
+    Config,
+}
+
+/// How the dock fits more items than the monitor has room for.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Shrinks every icon down to `min_icon_size`, then scrolls.
+    Shrink,
+    /// Keeps icons at full size and scrolls with the mouse wheel or the arrows at the dock ends.
+    Scroll,
+    /// Keeps icons at full size and gathers the excess into a "more" item with a popup grid.
+    Collapse,
+}
+
+/// Which variant of a theme is used when it has light and dark ones.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Appearance {
+    Light,
+    Dark,
+    /// Follows the Windows mode setting for the taskbar, switching when it changes.
+    Auto,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            theme: None,
+            appearance: Appearance::Dark,
             rounding: 16.0,
             icon_size: egui::vec2(40.0, 40.0),
             icon_spacing: 10.0,
@@ -31,8 +407,130 @@ impl Default for Config {
             left_margin: 14.0,
             top_margin: 8.0,
             bottom_margin: 8.0,
+            screen_edge_gap: 10.0,
             show_active_indicators: true,
+            foreground_indicator_style: ForegroundIndicatorStyle::Dot,
+            foreground_indicator_color: egui::Color32::from_rgba_unmultiplied(255, 255, 255, 255),
+            attention_indicator_color: egui::Color32::from_rgba_unmultiplied(255, 149, 0, 255),
+            attention_bounce: BounceRepeat::Count(3),
+            bounce_height: 14.0,
+            bounce_duration: 0.6,
+            launch_timeout_secs: 20.0,
+            launch_error_color: egui::Color32::from_rgb(255, 69, 58),
             hover_scale: 1.25,
+            overflow: OverflowPolicy::Shrink,
+            min_icon_size: 24.0,
+            hotkeys: HotkeyConfig::default(),
+            accessibility: AccessibilityConfig::default(),
+            menu_bar: MenuBarConfig::default(),
+            pinned: Vec::new(),
+            icon_packs: Vec::new(),
+            icon_style: IconStyleConfig::default(),
+            widgets: Vec::new(),
+            plugins: PluginConfig::default(),
+            window_rules: Vec::new(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let config: Config = toml::from_str("icon_spacing = 4.0").unwrap();
+
+        assert_eq!(config.icon_spacing, 4.0);
+        assert_eq!(config.rounding, Config::default().rounding);
+        assert_eq!(config.effective_window_rules(), default_window_rules());
+        assert!(!config.menu_bar.enabled);
+    }
+
+    #[test]
+    fn window_rules_come_before_the_built_in_rules() {
+        let config: Config = toml::from_str(
+            r#"
+            [[window_rules]]
+            exe = "*\\steam.exe"
+            title = "^Friends"
+            action = "exclude"
+
+            [[window_rules]]
+            class = "Chrome_WidgetWin_1"
+            action = { rename = "Browser" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.window_rules,
+            vec![
+                WindowRule {
+                    exe: Some("*\\steam.exe".to_string()),
+                    title: Some("^Friends".to_string()),
+                    action: RuleAction::Exclude,
+                    ..Default::default()
+                },
+                WindowRule {
+                    class: Some("Chrome_WidgetWin_1".to_string()),
+                    action: RuleAction::Rename("Browser".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+        // The built-in exclusions still apply to windows the configured rules leave open
+        let rules = config.effective_window_rules();
+        assert_eq!(rules[..2], config.window_rules[..]);
+        assert_eq!(rules[2..], default_window_rules()[..]);
+    }
+
+    #[test]
+    fn widgets_keep_their_settings_table() {
+        let config: Config = toml::from_str(
+            r#"
+            [[widgets]]
+            kind = "clock"
+            placement = "menu_bar"
+            settings = { seconds = true }
+
+            [[widgets]]
+            kind = "disk"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.widgets.len(), 2);
+        assert_eq!(config.widgets[0].placement, WidgetPlacement::MenuBar);
+        assert_eq!(config.widgets[0].settings.get("seconds"), Some(&toml::Value::Boolean(true)));
+        assert_eq!(config.widgets[1].placement, WidgetPlacement::Desktop);
+        assert!(config.widgets[1].settings.is_empty());
+    }
+
+    #[test]
+    fn plugin_approvals_are_listed_by_plugin_name() {
+        let config: Config = toml::from_str(
+            r#"
+            [plugins.approved]
+            pomodoro = ["badges", "events"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.plugins.enabled);
+        assert_eq!(
+            config.plugins.approved["pomodoro"],
+            vec![PluginPermission::Badges, PluginPermission::Events]
+        );
+        assert!(toml::from_str::<Config>("[plugins.approved]\nx = [\"files\"]").is_err());
+    }
+
+    #[test]
+    fn bounce_repeat_accepts_count_or_continuous() {
+        let counted: Config = toml::from_str("attention_bounce = { count = 5 }").unwrap();
+        let continuous: Config = toml::from_str(r#"attention_bounce = "continuous""#).unwrap();
+
+        assert_eq!(counted.attention_bounce, BounceRepeat::Count(5));
+        assert_eq!(continuous.attention_bounce, BounceRepeat::Continuous);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::config::{Config, WindowRule};
+use crate::rules::RuleSet;
+use crate::windows::{
+    IconSource, InspectDepth, ProcessResolver, Verdict, WindowInspection,
+    enumerate_top_level_windows, get_window_class, get_window_title, inspect_window,
+};
+
+const USAGE: &str = "usage: ferrodock diagnose [--format table|json]";
+const MAX_TITLE_CHARS: usize = 40;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// One top-level window as reported by `ferrodock diagnose`.
+#[derive(Debug, serde::Serialize)]
+pub struct DiagnosticRow {
+    pub hwnd: String,
+    pub pid: u32,
+    pub class: String,
+    pub title: String,
+    /// Executable path of the process behind the window, unless it was never resolved.
+    pub path: Option<String>,
+    pub aumid: Option<String>,
+    /// Why `path` is only the executable name, when the process could not be opened.
+    pub identity_error: Option<String>,
+    /// The dock item the window is grouped under, after `group_under` rules.
+    pub dock_item: Option<String>,
+    pub icon_source: &'static str,
+    pub shown: bool,
+    pub reason: String,
+}
+
+/// Runs `ferrodock diagnose`, printing why each top-level window is or is not in the dock.
+///
+/// `args` are the arguments following `diagnose`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let format = parse_format(args)?;
+
+    let config = Config::load();
+    let window_rules = config.effective_window_rules();
+    let rules = RuleSet::new(&window_rules);
+    let mut processes = ProcessResolver::default();
+
+    let rows: Vec<DiagnosticRow> = enumerate_top_level_windows()
+        .into_iter()
+        .map(|hwnd| {
+            let inspection = inspect_window(hwnd, &rules, &mut processes, InspectDepth::Full);
+            diagnostic_row(&inspection, &window_rules)
+        })
+        .collect();
+
+    match format {
+        OutputFormat::Table => print!("{}", format_table(&rows)),
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&rows).map_err(|err| err.to_string())?;
+            println!("{json}");
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_format(args: &[String]) -> Result<OutputFormat, String> {
+    let mut format = OutputFormat::Table;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--json" => "json",
+            "--format" => args.next().map(String::as_str).unwrap_or_default(),
+            other => other.strip_prefix("--format=").unwrap_or(other),
+        };
+
+        format = match value {
+            "table" => OutputFormat::Table,
+            "json" => OutputFormat::Json,
+            _ => return Err(format!("unexpected argument `{arg}`\n{USAGE}")),
+        };
+    }
+
+    Ok(format)
+}
+
+fn diagnostic_row(inspection: &WindowInspection, rules: &[WindowRule]) -> DiagnosticRow {
+    let facts = inspection.facts.as_ref();
+
+    DiagnosticRow {
+        hwnd: format!("{:#010x}", inspection.hwnd.0),
+        pid: inspection.process_id,
+        class: get_window_class(inspection.hwnd),
+        title: get_window_title(inspection.hwnd),
+        path: facts.map(|f| f.exe_path.clone()).filter(|path| !path.is_empty()),
+        aumid: facts.and_then(|f| f.aumid.clone()),
+        identity_error: inspection.identity_error.as_ref().map(|e| e.to_string()),
+        dock_item: inspection.icon.as_ref().map(|i| i.path.clone()),
+        icon_source: icon_source_name(inspection.icon_source),
+        shown: matches!(inspection.verdict, Verdict::Shown(_)),
+        reason: describe_verdict(&inspection.verdict, rules),
+    }
+}
+
+fn icon_source_name(source: IconSource) -> &'static str {
+    match source {
+        IconSource::WindowMessage => "WM_GETICON",
+        IconSource::WindowClass => "window class",
+        IconSource::ShellFileInfo => "shell file info",
+        IconSource::PackageManifest => "package manifest",
+        IconSource::None => "-",
+    }
+}
+
+/// Explains a verdict, quoting the rule responsible for it.
+pub fn describe_verdict(verdict: &Verdict, rules: &[WindowRule]) -> String {
+    let rule = |index: usize| match rules.get(index) {
+        Some(rule) => format!("rule #{index} ({rule})"),
+        None => format!("rule #{index}"),
+    };
+
+    match verdict {
+        Verdict::Shown(Some(index)) => format!("included by {}", rule(*index)),
+        Verdict::Shown(None) => "included: no include/exclude rule matched".to_string(),
+        Verdict::Invisible => "excluded: window is not visible".to_string(),
+        Verdict::UnresolvedProcess(reason) => format!("excluded: process not resolved: {reason}"),
+        Verdict::OwnWindow => "excluded: FerroDock's own window".to_string(),
+        Verdict::ExcludedByRule(index) => format!("excluded by {}", rule(*index)),
+        Verdict::NoIcon => "excluded: no icon available".to_string(),
+    }
+}
+
+/// Renders rows as a plain-text table with one line per window.
+pub fn format_table(rows: &[DiagnosticRow]) -> String {
+    let header = ["HWND", "PID", "SHOWN", "ICON", "PATH", "CLASS", "TITLE", "REASON"];
+    let cells: Vec<[String; 8]> = rows
+        .iter()
+        .map(|row| {
+            [
+                row.hwnd.clone(),
+                row.pid.to_string(),
+                if row.shown { "yes" } else { "no" }.to_string(),
+                row.icon_source.to_string(),
+                row.path.clone().unwrap_or_else(|| "-".to_string()),
+                row.class.clone(),
+                truncate(&row.title, MAX_TITLE_CHARS),
+                match &row.identity_error {
+                    Some(error) => format!("{} (identity: {error})", row.reason),
+                    None => row.reason.clone(),
+                },
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(|h| h.chars().count());
+    for line in &cells {
+        for (width, cell) in widths.iter_mut().zip(line) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    let lines = std::iter::once(header.map(str::to_string)).chain(cells);
+    for line in lines {
+        let padded: Vec<String> = line
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        table.push_str(padded.join("  ").trim_end());
+        table.push('\n');
+    }
+
+    table
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::default_window_rules;
+    use crate::rules::WindowFacts;
+    use windows::Win32::Foundation::HWND;
+
+    fn row(title: &str, shown: bool, reason: &str) -> DiagnosticRow {
+        DiagnosticRow {
+            hwnd: "0x00010203".to_string(),
+            pid: 42,
+            class: "Notepad".to_string(),
+            title: title.to_string(),
+            path: Some(r"C:\Windows\notepad.exe".to_string()),
+            aumid: None,
+            identity_error: None,
+            dock_item: None,
+            icon_source: "WM_GETICON",
+            shown,
+            reason: reason.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_format_defaults_to_table() {
+        assert_eq!(parse_format(&[]), Ok(OutputFormat::Table));
+    }
+
+    #[test]
+    fn parse_format_accepts_json_spellings() {
+        for args in [vec!["--json"], vec!["--format", "json"], vec!["--format=json"]] {
+            let args: Vec<String> = args.into_iter().map(String::from).collect();
+            assert_eq!(parse_format(&args), Ok(OutputFormat::Json));
+        }
+    }
+
+    #[test]
+    fn parse_format_rejects_unknown_arguments() {
+        assert!(parse_format(&["--yaml".to_string()]).is_err());
+        assert!(parse_format(&["--format".to_string()]).is_err());
+    }
+
+    #[test]
+    fn describe_verdict_quotes_the_deciding_rule() {
+        let rules = default_window_rules();
+
+        assert_eq!(
+            describe_verdict(&Verdict::ExcludedByRule(2), &rules),
+            "excluded by rule #2 (exclude where cloaked = true)"
+        );
+        assert_eq!(
+            describe_verdict(&Verdict::Shown(None), &rules),
+            "included: no include/exclude rule matched"
+        );
+    }
+
+    #[test]
+    fn rule_excluded_windows_report_their_resolved_path() {
+        let rules = default_window_rules();
+        let inspection = |exe_path: &str| WindowInspection {
+            hwnd: HWND(0),
+            process_id: 42,
+            identity_error: None,
+            facts: Some(WindowFacts {
+                exe_path: exe_path.to_string(),
+                cloaked: true,
+                ..WindowFacts::default()
+            }),
+            icon_source: IconSource::None,
+            verdict: Verdict::ExcludedByRule(2),
+            icon: None,
+        };
+
+        let row = diagnostic_row(&inspection(r"C:\Windows\explorer.exe"), &rules);
+        assert_eq!(row.path.as_deref(), Some(r"C:\Windows\explorer.exe"));
+        assert_eq!(row.reason, "excluded by rule #2 (exclude where cloaked = true)");
+        assert!(!row.shown);
+
+        // A window rejected before its process was opened has no path to report
+        assert_eq!(diagnostic_row(&inspection(""), &rules).path, None);
+    }
+
+    #[test]
+    fn format_table_aligns_columns_and_truncates_titles() {
+        let long_title = "x".repeat(60);
+        let table = format_table(&[
+            row("notes.txt - Notepad", true, "included"),
+            row(&long_title, false, "excluded"),
+        ]);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("HWND"));
+        assert_eq!(lines[1].find("yes"), lines[0].find("SHOWN"));
+        assert!(lines[2].contains(&format!("{}…", "x".repeat(MAX_TITLE_CHARS - 1))));
+        assert!(!lines[2].contains(&long_title));
+    }
+}
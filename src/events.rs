@@ -5,33 +5,60 @@ use windows::Win32::{
     Foundation::{HWND, LPARAM, LRESULT, WPARAM},
     UI::WindowsAndMessaging::{
         CS_HREDRAW, CS_VREDRAW, CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW,
-        MSG, RegisterClassW, RegisterShellHookWindow, RegisterWindowMessageW, TranslateMessage,
-        WINDOW_EX_STYLE, WINDOW_STYLE, WNDCLASSW,
+        MSG, RegisterClassW, RegisterShellHookWindow, RegisterWindowMessageW,
+        SPI_SETCLIENTAREAANIMATION, SPI_SETHIGHCONTRAST, SPI_SETLOGICALDPIOVERRIDE,
+        SPI_SETWORKAREA, TranslateMessage, WINDOW_EX_STYLE, WINDOW_STYLE, WM_DISPLAYCHANGE,
+        WM_DPICHANGED, WM_DWMCOLORIZATIONCOLORCHANGED, WM_HOTKEY, WM_SETTINGCHANGE,
+        WM_SYSCOLORCHANGE, WNDCLASSW,
     },
 };
 
-use windows::core::w;
+use windows::Win32::UI::Input::KeyboardAndMouse::{MOD_NOREPEAT, RegisterHotKey};
+use windows::core::{PCWSTR, w};
+
+use crate::hotkeys::{Hotkey, HotkeyAction};
 
 const HSHELL_WINDOWCREATED: usize = 0x0001;
 const HSHELL_WINDOWDESTROYED: usize = 0x0002;
 const HSHELL_WINDOWACTIVATED: usize = 0x0004;
+const HSHELL_REDRAW: usize = 0x0006;
 const HSHELL_RUDEAPPACTIVATED: usize = 0x8004;
+const HSHELL_FLASH: usize = 0x8006;
 
 #[derive(Debug, Clone)]
 pub enum WindowEvent {
     WindowCreated,
     WindowDestroyed,
-    WindowActivated,
+    /// The window that became the foreground window. `HWND(0)` when no window
+    /// (e.g. the desktop) was activated.
+    WindowActivated(HWND),
+    /// The window called `FlashWindowEx` to request the user's attention.
+    WindowFlashed(HWND),
+    /// The window's title or icon changed.
+    WindowRedrawn(HWND),
+    /// The work area, resolution, monitor layout or display scaling changed.
+    DisplayChanged,
+    /// The animation, high contrast or light/dark mode setting, or the system or accent
+    /// colours, changed.
+    PreferencesChanged,
+    /// A registered global hotkey was pressed.
+    Hotkey(HotkeyAction),
 }
 
 static EVENT_SENDER: OnceLock<Sender<WindowEvent>> = OnceLock::new();
 static SHELL_HOOK_MSG: OnceLock<u32> = OnceLock::new();
-
-pub fn start_event_listener() -> Receiver<WindowEvent> {
+/// The action of each registered hotkey, indexed by its hotkey id.
+static HOTKEY_ACTIONS: OnceLock<Vec<HotkeyAction>> = OnceLock::new();
+
+/// Starts the thread that forwards shell, display and hotkey notifications.
+///
+/// `hotkeys` are registered on that thread; a combination already taken by another
+/// application is reported and skipped.
+pub fn start_event_listener(hotkeys: Vec<(HotkeyAction, Hotkey)>) -> Receiver<WindowEvent> {
     let (sender, receiver) = mpsc::channel();
     let _ = EVENT_SENDER.set(sender);
 
-    std::thread::spawn(|| unsafe {
+    std::thread::spawn(move || unsafe {
         let shell_msg = RegisterWindowMessageW(w!("SHELLHOOK"));
         SHELL_HOOK_MSG.set(shell_msg).ok();
 
@@ -73,6 +100,13 @@ pub fn start_event_listener() -> Receiver<WindowEvent> {
 
         println!("Shell hook window created");
 
+        for (id, (_, hotkey)) in hotkeys.iter().enumerate() {
+            if let Err(err) = RegisterHotKey(hwnd, id as i32, hotkey.modifiers | MOD_NOREPEAT, hotkey.key.0 as u32) {
+                eprintln!("Failed to register hotkey {hotkey:?}: {err}");
+            }
+        }
+        HOTKEY_ACTIONS.set(hotkeys.into_iter().map(|(action, _)| action).collect()).ok();
+
         let mut msg = MSG::default();
         while GetMessageW(&mut msg, None, 0, 0).as_bool() {
             let _ = TranslateMessage(&msg);
@@ -92,14 +126,54 @@ extern "system" fn shell_hook_proc(
     unsafe {
         let shell_msg = SHELL_HOOK_MSG.get().copied().unwrap_or(0);
 
+        // The hook window is top-level, so it also receives display broadcasts
+        let display_changed = match msg {
+            WM_DISPLAYCHANGE | WM_DPICHANGED => true,
+            WM_SETTINGCHANGE => {
+                wparam.0 == SPI_SETWORKAREA.0 as usize
+                    || wparam.0 == SPI_SETLOGICALDPIOVERRIDE.0 as usize
+            }
+            _ => false,
+        };
+
+        if display_changed && let Some(sender) = EVENT_SENDER.get() {
+            let _ = sender.send(WindowEvent::DisplayChanged);
+        }
+
+        let preferences_changed = match msg {
+            WM_SYSCOLORCHANGE | WM_DWMCOLORIZATIONCOLORCHANGED => true,
+            WM_SETTINGCHANGE => {
+                wparam.0 == SPI_SETHIGHCONTRAST.0 as usize
+                    || wparam.0 == SPI_SETCLIENTAREAANIMATION.0 as usize
+                    // Switching between light and dark mode names this area
+                    || (lparam.0 != 0
+                        && PCWSTR(lparam.0 as *const u16).to_string().is_ok_and(|area| area == "ImmersiveColorSet"))
+            }
+            _ => false,
+        };
+
+        if preferences_changed && let Some(sender) = EVENT_SENDER.get() {
+            let _ = sender.send(WindowEvent::PreferencesChanged);
+        }
+
+        if msg == WM_HOTKEY
+            && let Some(action) = HOTKEY_ACTIONS.get().and_then(|actions| actions.get(wparam.0))
+            && let Some(sender) = EVENT_SENDER.get()
+        {
+            let _ = sender.send(WindowEvent::Hotkey(*action));
+            return LRESULT(0);
+        }
+
         if msg == shell_msg {
             if let Some(sender) = EVENT_SENDER.get() {
                 let event = match wparam.0 {
                     HSHELL_WINDOWCREATED => Some(WindowEvent::WindowCreated),
                     HSHELL_WINDOWDESTROYED => Some(WindowEvent::WindowDestroyed),
                     HSHELL_WINDOWACTIVATED | HSHELL_RUDEAPPACTIVATED => {
-                        Some(WindowEvent::WindowActivated)
+                        Some(WindowEvent::WindowActivated(HWND(lparam.0)))
                     }
+                    HSHELL_FLASH => Some(WindowEvent::WindowFlashed(HWND(lparam.0))),
+                    HSHELL_REDRAW => Some(WindowEvent::WindowRedrawn(HWND(lparam.0))),
                     _ => return LRESULT(0),
                 };
 
@@ -0,0 +1,124 @@
+use eframe::egui;
+
+use crate::config::PinnedItem;
+
+/// Offered on the first start with nothing pinned: picks which of the applications pinned to
+/// the taskbar, and optionally the Start menu, to pin to the dock.
+pub struct FirstRunWizard {
+    taskbar: Vec<Choice>,
+    start_menu: Vec<Choice>,
+    include_start_menu: bool,
+}
+
+struct Choice {
+    pin: PinnedItem,
+    selected: bool,
+}
+
+/// How the user closed the wizard.
+#[derive(Debug, PartialEq)]
+pub enum WizardOutcome {
+    Pin(Vec<PinnedItem>),
+    Skip,
+}
+
+impl FirstRunWizard {
+    /// Every taskbar pin starts selected; Start menu pins are only added on request.
+    pub fn new(taskbar: Vec<PinnedItem>, start_menu: Vec<PinnedItem>) -> Self {
+        let choices = |pins: Vec<PinnedItem>| pins.into_iter().map(|pin| Choice { pin, selected: true }).collect();
+        Self {
+            taskbar: choices(taskbar),
+            start_menu: choices(start_menu),
+            include_start_menu: false,
+        }
+    }
+
+    /// Whether there is nothing to offer.
+    pub fn is_empty(&self) -> bool {
+        self.taskbar.is_empty() && self.start_menu.is_empty()
+    }
+
+    /// The selected pins, taskbar first, each application once.
+    pub fn selected(&self) -> Vec<PinnedItem> {
+        let start_menu = self.start_menu.iter().filter(|_| self.include_start_menu);
+        let mut pins: Vec<PinnedItem> = Vec::new();
+        for choice in self.taskbar.iter().chain(start_menu).filter(|choice| choice.selected) {
+            if !pins.iter().any(|pin| pin.path.eq_ignore_ascii_case(&choice.pin.path)) {
+                pins.push(choice.pin.clone());
+            }
+        }
+        pins
+    }
+
+    /// Draws the wizard, returning how it was closed once it is.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<WizardOutcome> {
+        ui.heading("Welcome to FerroDock");
+        ui.label("Pin the apps you already use to the dock, so they stay there when they are not running.");
+        ui.add_space(8.0);
+
+        egui::ScrollArea::vertical().max_height(ui.available_height() - 64.0).show(ui, |ui| {
+            if !self.taskbar.is_empty() {
+                ui.strong("Pinned to the taskbar");
+                choice_list(ui, &mut self.taskbar);
+            }
+            ui.add_space(8.0);
+            if self.start_menu.is_empty() {
+                ui.weak("Apps pinned to the Start menu of Windows 10 and later cannot be read, so they are not listed.");
+            } else {
+                ui.checkbox(&mut self.include_start_menu, "Also pin apps from the Start menu");
+                ui.add_enabled_ui(self.include_start_menu, |ui| choice_list(ui, &mut self.start_menu));
+            }
+        });
+
+        ui.add_space(8.0);
+        let count = self.selected().len();
+        let mut outcome = None;
+        ui.horizontal(|ui| {
+            let pin = ui.add_enabled(count > 0, egui::Button::new(format!("Pin {count} apps")));
+            if pin.clicked() {
+                outcome = Some(WizardOutcome::Pin(self.selected()));
+            }
+            if ui.button("Not now").clicked() {
+                outcome = Some(WizardOutcome::Skip);
+            }
+        });
+        outcome
+    }
+}
+
+fn choice_list(ui: &mut egui::Ui, choices: &mut [Choice]) {
+    for choice in choices {
+        let name = choice.pin.name.as_deref().unwrap_or(&choice.pin.path);
+        ui.checkbox(&mut choice.selected, name).on_hover_text(&choice.pin.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(path: &str) -> PinnedItem {
+        PinnedItem {
+            path: path.to_string(),
+            ..PinnedItem::default()
+        }
+    }
+
+    #[test]
+    fn start_menu_pins_are_opt_in() {
+        let mut wizard = FirstRunWizard::new(vec![pin("C:\\a.exe")], vec![pin("C:\\b.exe"), pin("c:\\A.EXE")]);
+        assert_eq!(wizard.selected(), [pin("C:\\a.exe")]);
+
+        wizard.include_start_menu = true;
+        assert_eq!(wizard.selected(), [pin("C:\\a.exe"), pin("C:\\b.exe")]);
+
+        wizard.taskbar[0].selected = false;
+        assert_eq!(wizard.selected(), [pin("C:\\b.exe"), pin("c:\\A.EXE")]);
+    }
+
+    #[test]
+    fn nothing_to_offer_without_pins() {
+        assert!(FirstRunWizard::new(Vec::new(), Vec::new()).is_empty());
+        assert!(!FirstRunWizard::new(Vec::new(), vec![pin("C:\\b.exe")]).is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use windows::Win32::Foundation::HWND;
+
+use crate::windows::DockIcon;
+
+/// Keeps track of the foreground window and the most-recently-used order of
+/// applications and their windows.
+///
+/// Applications are keyed by the same path used for `DockIcon::path`. The
+/// front of the window list is the most recently activated window, so the
+/// application order follows from the first window seen for each path.
+#[derive(Default)]
+pub struct FocusTracker {
+    foreground: Option<HWND>,
+    windows: Vec<(HWND, String)>,
+}
+
+impl FocusTracker {
+    /// Records `hwnd`, owned by the application at `path`, as the foreground window
+    /// and moves both the window and the application to the front of the MRU order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut focus = FocusTracker::default();
+    /// focus.activate(HWND(1), "C:\\a.exe");
+    /// assert!(focus.is_foreground_app("C:\\a.exe"));
+    /// ```
+    pub fn activate(&mut self, hwnd: HWND, path: &str) {
+        self.foreground = Some(hwnd);
+        self.windows.retain(|(h, _)| *h != hwnd);
+        self.windows.insert(0, (hwnd, path.to_string()));
+    }
+
+    /// Clears the foreground window, e.g. when the desktop or a window that is not
+    /// shown in the dock gains focus. The MRU order is left untouched.
+    pub fn clear_foreground(&mut self) {
+        self.foreground = None;
+    }
+
+    pub fn foreground_window(&self) -> Option<HWND> {
+        self.foreground
+    }
+
+    /// Returns the path of the application owning the foreground window, if any.
+    pub fn foreground_app(&self) -> Option<&str> {
+        let hwnd = self.foreground?;
+        self.windows
+            .iter()
+            .find(|(h, _)| *h == hwnd)
+            .map(|(_, path)| path.as_str())
+    }
+
+    pub fn is_foreground_app(&self, path: &str) -> bool {
+        self.foreground_app() == Some(path)
+    }
+
+    /// Returns the windows of `item` with the most recently activated first.
+    ///
+    /// Windows that have never been activated keep their enumeration order and
+    /// follow the ones with activation history.
+    pub fn windows_for(&self, item: &DockIcon) -> Vec<HWND> {
+        let mut ordered: Vec<HWND> = self
+            .windows
+            .iter()
+            .filter(|(h, path)| *path == item.path && item.windows.contains(h))
+            .map(|(h, _)| *h)
+            .collect();
+
+        for hwnd in &item.windows {
+            if !ordered.contains(hwnd) {
+                ordered.push(*hwnd);
+            }
+        }
+
+        ordered
+    }
+
+    /// Returns the window of `item` that should receive focus when the item is clicked.
+    pub fn most_recent_window(&self, item: &DockIcon) -> HWND {
+        self.windows_for(item).first().copied().unwrap_or(item.hwnd)
+    }
+
+    /// Returns the window of `item` after the foreground one, in the item's stable window
+    /// order, so repeated activation cycles through all of them. `None` unless the item owns
+    /// the foreground window and has more than one window.
+    pub fn next_window(&self, item: &DockIcon) -> Option<HWND> {
+        let foreground = self.foreground?;
+        let position = item.windows.iter().position(|h| *h == foreground)?;
+
+        (item.windows.len() > 1).then(|| item.windows[(position + 1) % item.windows.len()])
+    }
+
+    /// Drops history for windows and applications that are no longer in the dock.
+    pub fn retain_items(&mut self, items: &[DockIcon]) {
+        self.windows.retain(|(hwnd, path)| {
+            items
+                .iter()
+                .any(|i| i.path == *path && i.windows.contains(hwnd))
+        });
+
+        if let Some(hwnd) = self.foreground
+            && !self.windows.iter().any(|(h, _)| *h == hwnd)
+        {
+            self.foreground = None;
+        }
+    }
+
+    /// Applies a window activation using the current dock items to resolve the owning
+    /// application. Activations of windows outside the dock clear the foreground.
+    pub fn apply_activation(&mut self, hwnd: HWND, items: &[DockIcon]) {
+        match items.iter().find(|i| i.windows.contains(&hwnd)) {
+            Some(item) => self.activate(hwnd, &item.path),
+            None => self.clear_foreground(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::UI::WindowsAndMessaging::HICON;
+
+    fn item(path: &str, windows: &[isize]) -> DockIcon {
+        let windows: Vec<HWND> = windows.iter().map(|h| HWND(*h)).collect();
+        DockIcon {
+            path: path.to_string(),
+            name: None,
+            icon: None,
+            aumid: None,
+            class: None,
+            hicon: HICON(0),
+            hwnd: windows.first().copied().unwrap_or_default(),
+            windows,
+        }
+    }
+
+    fn app_order(focus: &FocusTracker) -> Vec<&str> {
+        let mut order: Vec<&str> = Vec::new();
+        for (_, path) in &focus.windows {
+            if !order.contains(&path.as_str()) {
+                order.push(path);
+            }
+        }
+        order
+    }
+
+    #[test]
+    fn activation_moves_app_and_window_to_front() {
+        let mut focus = FocusTracker::default();
+        focus.activate(HWND(1), "a.exe");
+        focus.activate(HWND(2), "b.exe");
+        focus.activate(HWND(3), "a.exe");
+
+        assert_eq!(app_order(&focus), vec!["a.exe", "b.exe"]);
+        assert_eq!(focus.foreground_window(), Some(HWND(3)));
+        assert_eq!(focus.foreground_app(), Some("a.exe"));
+        assert!(!focus.is_foreground_app("b.exe"));
+    }
+
+    #[test]
+    fn windows_for_orders_by_recency_then_enumeration() {
+        let a = item("a.exe", &[1, 2, 3]);
+        let mut focus = FocusTracker::default();
+        focus.activate(HWND(2), "a.exe");
+        focus.activate(HWND(3), "a.exe");
+
+        assert_eq!(focus.windows_for(&a), vec![HWND(3), HWND(2), HWND(1)]);
+        assert_eq!(focus.most_recent_window(&a), HWND(3));
+    }
+
+    #[test]
+    fn most_recent_window_falls_back_to_item_hwnd_without_history() {
+        let a = item("a.exe", &[7]);
+        let focus = FocusTracker::default();
+        assert_eq!(focus.most_recent_window(&a), HWND(7));
+    }
+
+    #[test]
+    fn apply_activation_clears_foreground_for_unknown_windows() {
+        let items = vec![item("a.exe", &[1])];
+        let mut focus = FocusTracker::default();
+
+        focus.apply_activation(HWND(1), &items);
+        assert!(focus.is_foreground_app("a.exe"));
+
+        focus.apply_activation(HWND(99), &items);
+        assert_eq!(focus.foreground_app(), None);
+        assert_eq!(app_order(&focus), vec!["a.exe"]);
+    }
+
+    #[test]
+    fn retain_items_prunes_closed_windows_and_apps() {
+        let mut focus = FocusTracker::default();
+        focus.activate(HWND(1), "a.exe");
+        focus.activate(HWND(2), "b.exe");
+
+        focus.retain_items(&[item("a.exe", &[1])]);
+
+        assert_eq!(app_order(&focus), vec!["a.exe"]);
+        assert_eq!(focus.foreground_window(), None);
+    }
+
+    #[test]
+    fn next_window_cycles_through_the_foreground_apps_windows() {
+        let items = [item("a.exe", &[1, 2, 3]), item("b.exe", &[4])];
+        let mut focus = FocusTracker::default();
+
+        assert_eq!(focus.next_window(&items[0]), None);
+
+        focus.apply_activation(HWND(2), &items);
+        assert_eq!(focus.next_window(&items[0]), Some(HWND(3)));
+
+        focus.apply_activation(HWND(3), &items);
+        assert_eq!(focus.next_window(&items[0]), Some(HWND(1)));
+        assert_eq!(focus.next_window(&items[1]), None);
+
+        focus.apply_activation(HWND(4), &items);
+        assert_eq!(focus.next_window(&items[1]), None);
+    }
+}
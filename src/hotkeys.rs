@@ -0,0 +1,196 @@
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOD_WIN, VIRTUAL_KEY, VK_DOWN, VK_END,
+    VK_ESCAPE, VK_F1, VK_HOME, VK_LEFT, VK_RETURN, VK_RIGHT, VK_SPACE, VK_TAB, VK_UP,
+};
+
+use crate::config::HotkeyConfig;
+
+/// A global key combination as passed to `RegisterHotKey`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hotkey {
+    pub modifiers: HOT_KEY_MODIFIERS,
+    pub key: VIRTUAL_KEY,
+}
+
+impl Hotkey {
+    /// Parses a combination such as `"ctrl+alt+d"` or `"win+shift+f5"`.
+    ///
+    /// Modifiers are `ctrl`, `alt`, `shift` and `win`, in any order and case. The key is a
+    /// letter, a digit, `f1`–`f24` or one of `space`, `enter`, `tab`, `escape`, `home`, `end`
+    /// and the arrow keys. At least one modifier is required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let hotkey = Hotkey::parse("ctrl+alt+d").unwrap();
+    /// assert_eq!(hotkey.modifiers, MOD_CONTROL | MOD_ALT);
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = parts.pop().filter(|key| !key.is_empty()).ok_or("missing key")?;
+
+        let modifiers = parse_modifiers(&parts.join("+"))?;
+        if modifiers.0 == 0 {
+            return Err(format!("`{text}` needs at least one modifier"));
+        }
+
+        Ok(Self {
+            modifiers,
+            key: parse_key(key).ok_or_else(|| format!("unknown key `{key}`"))?,
+        })
+    }
+}
+
+/// What a registered hotkey does when pressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HotkeyAction {
+    /// Activates, cycles or launches the item at this position in the dock (0-based).
+    ActivateItem(usize),
+    /// Brings the dock to the foreground for keyboard navigation.
+    FocusDock,
+}
+
+/// Builds the hotkeys described by `config`. Invalid combinations are reported and skipped.
+pub fn bindings(config: &HotkeyConfig) -> Vec<(HotkeyAction, Hotkey)> {
+    let mut bindings = Vec::new();
+
+    if !config.item_modifiers.trim().is_empty() {
+        match parse_modifiers(&config.item_modifiers) {
+            Ok(modifiers) if modifiers.0 != 0 => {
+                for n in 0..9u16 {
+                    let key = VIRTUAL_KEY(u16::from(b'1') + n);
+                    bindings.push((HotkeyAction::ActivateItem(n as usize), Hotkey { modifiers, key }));
+                }
+            }
+            Ok(_) => eprintln!("Ignoring item hotkeys: at least one modifier is required"),
+            Err(err) => eprintln!("Ignoring item hotkeys: {err}"),
+        }
+    }
+
+    if !config.focus_dock.trim().is_empty() {
+        match Hotkey::parse(&config.focus_dock) {
+            Ok(hotkey) => bindings.push((HotkeyAction::FocusDock, hotkey)),
+            Err(err) => eprintln!("Ignoring focus_dock hotkey: {err}"),
+        }
+    }
+
+    bindings
+}
+
+fn parse_modifiers(text: &str) -> Result<HOT_KEY_MODIFIERS, String> {
+    text.split('+')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .try_fold(HOT_KEY_MODIFIERS(0), |modifiers, part| {
+            let modifier = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => MOD_CONTROL,
+                "alt" => MOD_ALT,
+                "shift" => MOD_SHIFT,
+                "win" | "super" => MOD_WIN,
+                _ => return Err(format!("unknown modifier `{part}`")),
+            };
+            Ok(modifiers | modifier)
+        })
+}
+
+fn parse_key(text: &str) -> Option<VIRTUAL_KEY> {
+    let lower = text.to_ascii_lowercase();
+
+    if let [c] = lower.as_bytes()
+        && c.is_ascii_alphanumeric()
+    {
+        return Some(VIRTUAL_KEY(u16::from(c.to_ascii_uppercase())));
+    }
+
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u16>().ok())
+        && (1..=24).contains(&n)
+    {
+        return Some(VIRTUAL_KEY(VK_F1.0 + n - 1));
+    }
+
+    Some(match lower.as_str() {
+        "space" => VK_SPACE,
+        "enter" | "return" => VK_RETURN,
+        "tab" => VK_TAB,
+        "escape" | "esc" => VK_ESCAPE,
+        "home" => VK_HOME,
+        "end" => VK_END,
+        "left" => VK_LEFT,
+        "right" => VK_RIGHT,
+        "up" => VK_UP,
+        "down" => VK_DOWN,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{VK_D, VK_F12};
+
+    #[test]
+    fn parses_modifiers_in_any_order_and_case() {
+        let hotkey = Hotkey::parse("Alt + CTRL + d").unwrap();
+
+        assert_eq!(hotkey.modifiers, MOD_CONTROL | MOD_ALT);
+        assert_eq!(hotkey.key, VK_D);
+    }
+
+    #[test]
+    fn parses_function_and_named_keys() {
+        assert_eq!(Hotkey::parse("win+f12").unwrap().key, VK_F12);
+        assert_eq!(Hotkey::parse("shift+space").unwrap().key, VK_SPACE);
+        assert_eq!(Hotkey::parse("ctrl+5").unwrap().key, VIRTUAL_KEY(u16::from(b'5')));
+    }
+
+    #[test]
+    fn rejects_invalid_combinations() {
+        assert!(Hotkey::parse("d").is_err());
+        assert!(Hotkey::parse("ctrl+").is_err());
+        assert!(Hotkey::parse("hyper+d").is_err());
+        assert!(Hotkey::parse("ctrl+f25").is_err());
+        assert!(Hotkey::parse("ctrl+pause").is_err());
+    }
+
+    #[test]
+    fn item_modifiers_bind_the_digits_one_to_nine() {
+        let config = HotkeyConfig {
+            item_modifiers: "win+alt".to_string(),
+            focus_dock: String::new(),
+        };
+        let bindings = bindings(&config);
+
+        assert_eq!(bindings.len(), 9);
+        assert_eq!(
+            bindings[0],
+            (
+                HotkeyAction::ActivateItem(0),
+                Hotkey {
+                    modifiers: MOD_WIN | MOD_ALT,
+                    key: VIRTUAL_KEY(u16::from(b'1')),
+                }
+            )
+        );
+        assert_eq!(bindings[8].0, HotkeyAction::ActivateItem(8));
+    }
+
+    #[test]
+    fn empty_settings_disable_hotkeys() {
+        let config = HotkeyConfig {
+            item_modifiers: String::new(),
+            focus_dock: " ".to_string(),
+        };
+
+        assert!(bindings(&config).is_empty());
+    }
+
+    #[test]
+    fn invalid_settings_are_skipped() {
+        let config = HotkeyConfig {
+            item_modifiers: "hyper".to_string(),
+            focus_dock: "ctrl+alt+d".to_string(),
+        };
+
+        assert_eq!(bindings(&config).len(), 1);
+    }
+}
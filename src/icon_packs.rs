@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{ColorImage, Context, TextureHandle, TextureOptions};
+
+use crate::svg::render_svg;
+use crate::windows::{get_resource_icon, load_png_as_color_image};
+
+/// Directory searched for icon packs, next to the executable or in the working directory.
+const ICON_PACKS_DIR_NAME: &str = "icon_packs";
+
+/// File in an icon pack's directory mapping applications to its images.
+const MANIFEST_FILE_NAME: &str = "icons.toml";
+
+/// Where an icon drawn instead of an application's own comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IconRef {
+    /// A PNG, ICO or SVG file.
+    Image(PathBuf),
+    /// An icon inside an executable or DLL; negative indices are resource IDs.
+    Resource { file: PathBuf, index: i32 },
+}
+
+impl IconRef {
+    /// Reads `path` or `path,index`, relative to `dir`. Executables and DLLs without an index
+    /// name their first icon.
+    pub fn parse(text: &str, dir: &Path) -> Self {
+        let (file, index) = match text.rsplit_once(',') {
+            Some((file, index)) => match index.trim().parse() {
+                Ok(index) => (file, Some(index)),
+                Err(_) => (text, None),
+            },
+            None => (text, None),
+        };
+        let file = dir.join(file.trim());
+
+        let is_binary = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe") || ext.eq_ignore_ascii_case("dll"));
+        match index {
+            Some(index) => IconRef::Resource { file, index },
+            None if is_binary => IconRef::Resource { file, index: 0 },
+            None => IconRef::Image(file),
+        }
+    }
+
+    /// Whether the icon can be drawn at any size: an SVG, or an icon in an executable, which
+    /// Windows scales from its largest image.
+    pub fn is_scalable(&self) -> bool {
+        match self {
+            IconRef::Resource { .. } => true,
+            IconRef::Image(path) => is_svg(path),
+        }
+    }
+
+    /// Reads the icon, rasterizing scalable icons `size` pixels wide. Bitmaps keep their own
+    /// size, up to 128 pixels.
+    pub fn load(&self, size: u32) -> Option<ColorImage> {
+        match self {
+            IconRef::Resource { file, index } => get_resource_icon(file, *index, size),
+            IconRef::Image(path) if is_svg(path) => {
+                let data = std::fs::read(path).ok()?;
+                render_svg(&data, size)
+                    .map_err(|err| eprintln!("Failed to render {}: {err}", path.display()))
+                    .ok()
+            }
+            IconRef::Image(path) => load_png_as_color_image(path),
+        }
+    }
+}
+
+/// Textures of scalable icons, rasterized once per size bucket.
+#[derive(Default)]
+pub struct IconRasters {
+    textures: HashMap<(IconRef, u32), Option<TextureHandle>>,
+}
+
+impl IconRasters {
+    /// The icon rasterized for `bucket` and passed through `process`, or `None` if it failed
+    /// to load.
+    pub fn texture(
+        &mut self,
+        ctx: &Context,
+        icon: &IconRef,
+        bucket: u32,
+        process: impl FnOnce(ColorImage) -> ColorImage,
+    ) -> Option<TextureHandle> {
+        self.textures
+            .entry((icon.clone(), bucket))
+            .or_insert_with(|| {
+                let image = process(icon.load(bucket)?);
+                Some(ctx.load_texture(format!("{icon:?}@{bucket}"), image, TextureOptions::LINEAR))
+            })
+            .clone()
+    }
+
+    /// Forgets the rasters of other buckets, e.g. after moving to a display with another scale.
+    pub fn retain_bucket(&mut self, bucket: u32) {
+        self.textures.retain(|(_, size), _| *size == bucket);
+    }
+
+    /// Forgets the rasters of icons `keep` rejects, e.g. those of apps no longer in the dock.
+    pub fn retain_icons(&mut self, keep: impl Fn(&IconRef) -> bool) {
+        self.textures.retain(|(icon, _), _| keep(icon));
+    }
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+}
+
+/// The `icons.toml` of an icon pack: images by executable name, AUMID and window class,
+/// relative to the pack's directory.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Manifest {
+    exe: HashMap<String, String>,
+    aumid: HashMap<String, String>,
+    class: HashMap<String, String>,
+    /// Directories searched for an SVG or PNG named like the executable, e.g. `firefox.svg`
+    /// for `firefox.exe`, such as the `scalable/apps` directory of a Linux icon theme.
+    directories: Vec<PathBuf>,
+}
+
+/// An icon pack with its lookups keyed as they are matched.
+#[derive(Debug, Default)]
+pub struct IconPack {
+    dir: PathBuf,
+    /// By lower-case executable file name.
+    exe: HashMap<String, String>,
+    /// By lower-case AUMID.
+    aumid: HashMap<String, String>,
+    /// By exact window class.
+    class: HashMap<String, String>,
+    directories: Vec<PathBuf>,
+}
+
+impl IconPack {
+    pub fn parse(text: &str, dir: &Path) -> Result<Self, String> {
+        let manifest: Manifest = toml::from_str(text).map_err(|err| err.to_string())?;
+        let lower = |map: HashMap<String, String>| map.into_iter().map(|(key, image)| (key.to_lowercase(), image)).collect();
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            exe: lower(manifest.exe),
+            aumid: lower(manifest.aumid),
+            class: manifest.class,
+            directories: manifest.directories.iter().map(|directory| dir.join(directory)).collect(),
+        })
+    }
+
+    /// The pack's image for an application, matching its AUMID, then its executable's file
+    /// name, then its window class, then an image named like the executable in its directories.
+    pub fn lookup(&self, exe_path: &str, aumid: Option<&str>, class: Option<&str>) -> Option<IconRef> {
+        let exe_name = exe_path.rsplit(['\\', '/']).next().unwrap_or(exe_path).to_lowercase();
+        let image = aumid
+            .and_then(|aumid| self.aumid.get(&aumid.to_lowercase()))
+            .or_else(|| self.exe.get(&exe_name))
+            .or_else(|| class.and_then(|class| self.class.get(class)));
+        if let Some(image) = image {
+            return Some(IconRef::parse(image, &self.dir));
+        }
+
+        let stem = exe_name.strip_suffix(".exe").unwrap_or(&exe_name);
+        self.directories
+            .iter()
+            .flat_map(|directory| ["svg", "png"].map(|ext| directory.join(format!("{stem}.{ext}"))))
+            .find(|path| path.is_file())
+            .map(IconRef::Image)
+    }
+}
+
+/// The configured icon packs, in priority order.
+#[derive(Debug, Default)]
+pub struct IconPacks {
+    packs: Vec<IconPack>,
+}
+
+impl IconPacks {
+    /// Loads the named packs from the `icon_packs` directory, reporting and skipping packs
+    /// that fail to load.
+    pub fn load(names: &[String]) -> Self {
+        let Some(root) = icon_packs_dir() else {
+            if !names.is_empty() {
+                eprintln!("No `{ICON_PACKS_DIR_NAME}` directory for the configured icon packs");
+            }
+            return Self::default();
+        };
+
+        let packs = names
+            .iter()
+            .filter_map(|name| {
+                let dir = root.join(name);
+                let manifest = dir.join(MANIFEST_FILE_NAME);
+                let pack = std::fs::read_to_string(&manifest)
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| IconPack::parse(&text, &dir));
+                pack.map_err(|err| eprintln!("Ignoring icon pack `{name}`: {}: {err}", manifest.display()))
+                    .ok()
+            })
+            .collect();
+
+        Self { packs }
+    }
+
+    /// The first pack's image for an application.
+    pub fn lookup(&self, exe_path: &str, aumid: Option<&str>, class: Option<&str>) -> Option<IconRef> {
+        self.packs.iter().find_map(|pack| pack.lookup(exe_path, aumid, class))
+    }
+}
+
+fn icon_packs_dir() -> Option<PathBuf> {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(ICON_PACKS_DIR_NAME)));
+
+    beside_exe
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(ICON_PACKS_DIR_NAME)))
+        .find(|path| path.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::unique_temp_dir;
+
+    #[test]
+    fn parses_icon_references() {
+        let dir = Path::new("pack");
+
+        assert_eq!(IconRef::parse("web.svg", dir), IconRef::Image(dir.join("web.svg")));
+        assert_eq!(
+            IconRef::parse("C:/Windows/System32/shell32.dll, -16", Path::new("")),
+            IconRef::Resource {
+                file: PathBuf::from("C:/Windows/System32/shell32.dll"),
+                index: -16
+            }
+        );
+        assert_eq!(
+            IconRef::parse("tools/app.EXE", dir),
+            IconRef::Resource {
+                file: dir.join("tools/app.EXE"),
+                index: 0
+            }
+        );
+        // Commas in file names are not indices
+        assert_eq!(IconRef::parse("a,b.png", dir), IconRef::Image(dir.join("a,b.png")));
+    }
+
+    #[test]
+    fn packs_match_aumid_then_exe_then_class() {
+        let dir = Path::new("icon_packs").join("flat");
+        let pack = IconPack::parse(
+            r#"
+            [exe]
+            "Code.exe" = "code.svg"
+            [aumid]
+            "Microsoft.WindowsCalculator_8wekyb3d8bbwe!App" = "calculator.png"
+            [class]
+            Notepad = "notepad.png"
+            "#,
+            &dir,
+        )
+        .unwrap();
+
+        let image = |name: &str| Some(IconRef::Image(dir.join(name)));
+        assert_eq!(pack.lookup(r"C:\Apps\VS Code\code.exe", None, Some("Notepad")), image("code.svg"));
+        assert_eq!(
+            pack.lookup(
+                r"C:\Program Files\WindowsApps\Calc\CalculatorApp.exe",
+                Some("microsoft.windowscalculator_8wekyb3d8bbwe!app"),
+                Some("ApplicationFrameWindow"),
+            ),
+            image("calculator.png")
+        );
+        assert_eq!(pack.lookup(r"C:\Windows\notepad.exe", None, Some("Notepad")), image("notepad.png"));
+        assert_eq!(pack.lookup(r"C:\Windows\notepad.exe", None, Some("notepad")), None);
+    }
+
+    #[test]
+    fn earlier_packs_win() {
+        let first = IconPack::parse("[class]\nNotepad = \"a.png\"", Path::new("first")).unwrap();
+        let second = IconPack::parse("[exe]\n\"notepad.exe\" = \"b.png\"", Path::new("second")).unwrap();
+        let packs = IconPacks {
+            packs: vec![first, second],
+        };
+
+        assert_eq!(
+            packs.lookup("notepad.exe", None, Some("Notepad")),
+            Some(IconRef::Image(Path::new("first").join("a.png")))
+        );
+        assert!(IconPack::parse("[exe]\nx = 1", Path::new("")).is_err());
+    }
+
+    #[test]
+    fn directories_serve_images_named_like_the_executable() {
+        let dir = unique_temp_dir("icon_theme");
+        let apps = dir.join("scalable").join("apps");
+        std::fs::create_dir_all(&apps).unwrap();
+        std::fs::write(apps.join("firefox.svg"), "<svg/>").unwrap();
+        std::fs::write(apps.join("gimp.png"), "").unwrap();
+
+        let pack = IconPack::parse("directories = [\"scalable/apps\"]", &dir).unwrap();
+        let firefox = pack.lookup(r"C:\Program Files\Mozilla Firefox\Firefox.exe", None, None);
+        let gimp = pack.lookup(r"C:\Program Files\GIMP 2\bin\gimp.exe", None, None);
+        let missing = pack.lookup(r"C:\Windows\notepad.exe", None, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(firefox, Some(IconRef::Image(apps.join("firefox.svg"))));
+        assert!(firefox.is_some_and(|icon| icon.is_scalable()));
+        assert_eq!(gimp, Some(IconRef::Image(apps.join("gimp.png"))));
+        assert!(!gimp.unwrap().is_scalable());
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn rasters_are_dropped_with_their_icons() {
+        let kept = IconRef::Image(PathBuf::from("kept.svg"));
+        let dropped = IconRef::Image(PathBuf::from("dropped.svg"));
+        let mut rasters = IconRasters::default();
+        for icon in [&kept, &dropped] {
+            rasters.textures.insert((icon.clone(), 32), None);
+            rasters.textures.insert((icon.clone(), 48), None);
+        }
+
+        rasters.retain_icons(|icon| *icon == kept);
+        let mut left: Vec<_> = rasters.textures.keys().cloned().collect();
+        left.sort_by_key(|(_, bucket)| *bucket);
+        assert_eq!(left, [(kept.clone(), 32), (kept, 48)]);
+    }
+}
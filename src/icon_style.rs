@@ -0,0 +1,392 @@
+use eframe::egui::{Color32, ColorImage};
+use image::{RgbaImage, imageops};
+
+use crate::config::IconStyleConfig;
+
+/// Alpha up to which border pixels count as transparent when trimming.
+const TRIM_ALPHA: u8 = 8;
+
+/// Share of the icon's edge the plate covers, leaving room for the shadow.
+const PLATE_SCALE: f32 = 0.9;
+
+/// Share of the plate's edge the icon on it is scaled to.
+const PLATE_CONTENT_SCALE: f32 = 0.62;
+
+/// Exponent of the superellipse outlining the plate: 2 is a circle, higher is squarer.
+const SQUIRCLE_EXPONENT: f32 = 5.0;
+
+/// Samples per pixel along each axis when anti-aliasing the plate's outline.
+const PLATE_SUPERSAMPLING: usize = 4;
+
+/// How far a plate coloured from an icon's dominant colour is lightened, so the icon stands out.
+const PLATE_TINT: f32 = 0.7;
+
+/// Shadow blur radius and downward offset as shares of the icon's edge, and its opacity.
+const SHADOW_BLUR: f32 = 0.04;
+const SHADOW_OFFSET: f32 = 0.025;
+const SHADOW_OPACITY: f32 = 0.45;
+
+/// Applies the enabled steps of `style` to `image`, making it a square `size` pixels wide.
+/// `background` is the plate colour the app asks for, if any. Without any step enabled the
+/// image is returned unchanged.
+pub fn style_icon(image: ColorImage, style: &IconStyleConfig, size: usize, background: Option<Color32>) -> ColorImage {
+    if !(style.normalize || style.plate || style.shadow) {
+        return image;
+    }
+
+    let content = if style.normalize || style.plate { trim_transparent(&image) } else { image };
+    let mut icon = if style.plate {
+        let color = background
+            .or_else(|| dominant_color(&content).map(|color| lerp_to_white(color, PLATE_TINT)))
+            .unwrap_or(Color32::WHITE);
+        let content_scale = if style.normalize { style.content_scale.clamp(0.1, 1.0) } else { 1.0 };
+        let content = fit(&content, size, PLATE_SCALE * PLATE_CONTENT_SCALE * content_scale);
+        over(&content, &squircle_plate(size, PLATE_SCALE, color))
+    } else if style.normalize {
+        fit(&content, size, style.content_scale.clamp(0.1, 1.0))
+    } else {
+        fit(&content, size, 1.0)
+    };
+
+    if style.shadow {
+        icon = with_shadow(&icon);
+    }
+    icon
+}
+
+/// Crops the rows and columns around the image that are all but transparent. A fully
+/// transparent image is returned as it is.
+pub fn trim_transparent(image: &ColorImage) -> ColorImage {
+    let [width, height] = image.size;
+    let opaque = |x: usize, y: usize| image.pixels[y * width + x].a() > TRIM_ALPHA;
+
+    let rows: Vec<usize> = (0..height).filter(|&y| (0..width).any(|x| opaque(x, y))).collect();
+    let columns: Vec<usize> = (0..width).filter(|&x| (0..height).any(|y| opaque(x, y))).collect();
+    let (Some(&top), Some(&bottom), Some(&left), Some(&right)) =
+        (rows.first(), rows.last(), columns.first(), columns.last())
+    else {
+        return image.clone();
+    };
+
+    let pixels = (top..=bottom)
+        .flat_map(|y| image.pixels[y * width + left..=y * width + right].iter().copied())
+        .collect();
+    ColorImage {
+        size: [right - left + 1, bottom - top + 1],
+        pixels,
+    }
+}
+
+/// Scales the image, keeping its aspect ratio, so its longer edge is `scale` of `size`, and
+/// centres it on a transparent square `size` pixels wide.
+pub fn fit(image: &ColorImage, size: usize, scale: f32) -> ColorImage {
+    let [width, height] = image.size;
+    let mut canvas = ColorImage::new([size, size], Color32::TRANSPARENT);
+    if width == 0 || height == 0 || size == 0 {
+        return canvas;
+    }
+
+    let longest = (size as f32 * scale).round().max(1.0);
+    let ratio = longest / width.max(height) as f32;
+    let new_width = ((width as f32 * ratio).round() as u32).max(1);
+    let new_height = ((height as f32 * ratio).round() as u32).max(1);
+
+    // Resampled premultiplied, so transparent pixels do not bleed their colour into edges;
+    // a triangle filter does not ring, which would push colours past their alpha
+    let source = RgbaImage::from_raw(width as u32, height as u32, image.as_raw().to_vec()).expect("sized buffer");
+    let resized = imageops::resize(&source, new_width, new_height, imageops::FilterType::Triangle);
+
+    let left = (size - new_width as usize) / 2;
+    let top = (size - new_height as usize) / 2;
+    for (x, y, pixel) in resized.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        canvas.pixels[(top + y as usize) * size + left + x as usize] = Color32::from_rgba_premultiplied(r, g, b, a);
+    }
+    canvas
+}
+
+/// The most common colour among the image's opaque pixels, favouring saturated colours over
+/// greys, white and black. `None` for images without opaque pixels.
+pub fn dominant_color(image: &ColorImage) -> Option<Color32> {
+    // Sums of red, green and blue, pixel count and score per colour bin of 4 bits per channel
+    let mut bins = vec![([0u64; 3], 0u64, 0f32); 16 * 16 * 16];
+    for pixel in image.pixels.iter().filter(|pixel| pixel.a() >= 192) {
+        let [r, g, b, _] = pixel.to_srgba_unmultiplied();
+        let bin = &mut bins[(r as usize >> 4) << 8 | (g as usize >> 4) << 4 | b as usize >> 4];
+
+        let max = r.max(g).max(b) as f32;
+        let min = r.min(g).min(b) as f32;
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        bin.0[0] += r as u64;
+        bin.0[1] += g as u64;
+        bin.0[2] += b as u64;
+        bin.1 += 1;
+        bin.2 += 1.0 + 4.0 * saturation;
+    }
+
+    let (sums, count, _) = bins
+        .into_iter()
+        .filter(|(_, count, _)| *count > 0)
+        .max_by(|a, b| a.2.total_cmp(&b.2))?;
+    let [r, g, b] = sums.map(|sum| (sum / count) as u8);
+    Some(Color32::from_rgb(r, g, b))
+}
+
+/// A transparent square `size` pixels wide with a squircle of `color` centred on it, its edge
+/// `scale` of the square's.
+///
+/// Each pixel's coverage is the exact width of the squircle within it, averaged over a few
+/// rows per pixel, so the outline is computed once per row rather than per sample.
+pub fn squircle_plate(size: usize, scale: f32, color: Color32) -> ColorImage {
+    let half = size as f32 * scale / 2.0;
+    let centre = size as f32 / 2.0;
+    let samples = PLATE_SUPERSAMPLING;
+
+    let mut plate = ColorImage::new([size, size], Color32::TRANSPARENT);
+    let mut coverage = vec![0.0f32; size];
+    for y in 0..size {
+        coverage.fill(0.0);
+        for sample in 0..samples {
+            let sy = y as f32 + (sample as f32 + 0.5) / samples as f32;
+            let v = ((sy - centre) / half).abs();
+            if v >= 1.0 {
+                continue;
+            }
+
+            let extent = half * (1.0 - v.powf(SQUIRCLE_EXPONENT)).powf(1.0 / SQUIRCLE_EXPONENT);
+            let (left, right) = (centre - extent, centre + extent);
+            let first = left.floor().max(0.0) as usize;
+            let last = (right.ceil().max(0.0) as usize).min(size);
+            for (x, covered) in coverage.iter_mut().enumerate().take(last).skip(first) {
+                let overlap = right.min(x as f32 + 1.0) - left.max(x as f32);
+                *covered += overlap.max(0.0) / samples as f32;
+            }
+        }
+
+        for (x, covered) in coverage.iter().enumerate() {
+            if *covered > 0.0 {
+                plate.pixels[y * size + x] = color.gamma_multiply(covered.min(1.0));
+            }
+        }
+    }
+    plate
+}
+
+/// Draws `top` over `bottom`, which must be the same size.
+pub fn over(top: &ColorImage, bottom: &ColorImage) -> ColorImage {
+    debug_assert_eq!(top.size, bottom.size);
+    let pixels = top
+        .pixels
+        .iter()
+        .zip(&bottom.pixels)
+        .map(|(top, bottom)| {
+            let below = 255 - top.a() as u16;
+            let blend = |t: u8, b: u8| (t as u16 + (b as u16 * below + 127) / 255).min(255) as u8;
+            Color32::from_rgba_premultiplied(
+                blend(top.r(), bottom.r()),
+                blend(top.g(), bottom.g()),
+                blend(top.b(), bottom.b()),
+                blend(top.a(), bottom.a()),
+            )
+        })
+        .collect();
+    ColorImage {
+        size: top.size,
+        pixels,
+    }
+}
+
+/// Draws the image over a blurred black copy of its outline, shifted down.
+pub fn with_shadow(image: &ColorImage) -> ColorImage {
+    let [width, height] = image.size;
+    let edge = width.max(height) as f32;
+    let offset = (edge * SHADOW_OFFSET).round() as usize;
+    let radius = (edge * SHADOW_BLUR).round() as usize;
+
+    let mut alpha = vec![0.0; width * height];
+    for y in offset..height {
+        for x in 0..width {
+            alpha[y * width + x] = image.pixels[(y - offset) * width + x].a() as f32;
+        }
+    }
+
+    // Two box blurs in each direction approximate a Gaussian
+    let mut line = Vec::new();
+    for _ in 0..2 {
+        for y in 0..height {
+            line.clear();
+            line.extend_from_slice(&alpha[y * width..(y + 1) * width]);
+            blur_line(&line, radius, |x, value| alpha[y * width + x] = value);
+        }
+        for x in 0..width {
+            line.clear();
+            line.extend((0..height).map(|y| alpha[y * width + x]));
+            blur_line(&line, radius, |y, value| alpha[y * width + x] = value);
+        }
+    }
+
+    let shadow = ColorImage {
+        size: image.size,
+        pixels: alpha
+            .iter()
+            .map(|a| Color32::from_rgba_premultiplied(0, 0, 0, (a * SHADOW_OPACITY).round() as u8))
+            .collect(),
+    };
+    over(image, &shadow)
+}
+
+/// Averages each value with its `radius` neighbours on either side, counting values past the
+/// ends as zero.
+fn blur_line(values: &[f32], radius: usize, mut write: impl FnMut(usize, f32)) {
+    let window = (2 * radius + 1) as f32;
+    let mut sum: f32 = values.iter().take(radius).sum();
+    for i in 0..values.len() {
+        if let Some(entering) = values.get(i + radius) {
+            sum += entering;
+        }
+        if i > radius {
+            sum -= values[i - radius - 1];
+        }
+        write(i, sum / window);
+    }
+}
+
+fn lerp_to_white(color: Color32, amount: f32) -> Color32 {
+    let lerp = |channel: u8| (channel as f32 + (255.0 - channel as f32) * amount).round() as u8;
+    Color32::from_rgb(lerp(color.r()), lerp(color.g()), lerp(color.b()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A transparent square `size` wide with an opaque `color` rectangle at `[x, y, w, h]`.
+    fn image_with_rect(size: usize, [x, y, w, h]: [usize; 4], color: Color32) -> ColorImage {
+        let mut image = ColorImage::new([size, size], Color32::TRANSPARENT);
+        for row in y..y + h {
+            for column in x..x + w {
+                image.pixels[row * size + column] = color;
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn trims_transparent_borders() {
+        let image = image_with_rect(32, [4, 10, 20, 6], Color32::RED);
+
+        let trimmed = trim_transparent(&image);
+
+        assert_eq!(trimmed.size, [20, 6]);
+        assert!(trimmed.pixels.iter().all(|pixel| *pixel == Color32::RED));
+        let empty = ColorImage::new([8, 8], Color32::TRANSPARENT);
+        assert_eq!(trim_transparent(&empty).size, [8, 8]);
+    }
+
+    #[test]
+    fn fits_content_centred_at_the_requested_scale() {
+        // A 2:1 rectangle fitted to half of 64 pixels becomes 32 by 16, centred
+        let fitted = fit(&ColorImage::new([20, 10], Color32::BLUE), 64, 0.5);
+
+        assert_eq!(fitted.size, [64, 64]);
+        assert_eq!(trim_transparent(&fitted).size, [32, 16]);
+        assert_eq!(fitted[(32, 32)], Color32::BLUE);
+        assert_eq!(fitted[(32, 23)], Color32::TRANSPARENT);
+        assert_eq!(fitted[(15, 32)], Color32::TRANSPARENT);
+    }
+
+    #[test]
+    fn normalizing_evens_out_padding() {
+        let style = IconStyleConfig {
+            normalize: true,
+            content_scale: 0.75,
+            ..IconStyleConfig::default()
+        };
+        let padded = image_with_rect(32, [12, 12, 8, 8], Color32::GREEN);
+        let full = ColorImage::new([48, 48], Color32::GREEN);
+
+        let padded = style_icon(padded, &style, 64, None);
+        let full = style_icon(full, &style, 64, None);
+
+        assert_eq!(trim_transparent(&padded).size, [48, 48]);
+        assert_eq!(trim_transparent(&full).size, [48, 48]);
+    }
+
+    #[test]
+    fn dominant_colour_favours_saturated_pixels() {
+        // Mostly white, with a smaller orange glyph and transparent surroundings
+        let mut image = image_with_rect(20, [0, 0, 20, 12], Color32::WHITE);
+        for pixel in &mut image.pixels[12 * 20..16 * 20] {
+            *pixel = Color32::from_rgb(240, 120, 10);
+        }
+
+        assert_eq!(dominant_color(&image), Some(Color32::from_rgb(240, 120, 10)));
+        assert_eq!(dominant_color(&ColorImage::new([4, 4], Color32::TRANSPARENT)), None);
+    }
+
+    #[test]
+    fn squircle_plates_have_rounded_corners() {
+        let plate = squircle_plate(64, 1.0, Color32::RED);
+
+        assert_eq!(plate[(32, 32)], Color32::RED);
+        assert_eq!(plate[(32, 1)], Color32::RED);
+        assert_eq!(plate[(0, 0)], Color32::TRANSPARENT);
+        assert_eq!(plate[(3, 3)], Color32::TRANSPARENT);
+        // Anti-aliased along the outline
+        assert!((0..32).any(|i| (1..255).contains(&plate[(i, i)].a())));
+    }
+
+    #[test]
+    fn squircle_plates_cover_the_squircle_area() {
+        let plate = squircle_plate(64, 0.5, Color32::WHITE);
+        let covered: f32 = plate.pixels.iter().map(|pixel| pixel.a() as f32 / 255.0).sum();
+
+        // A superellipse of exponent 5 covers about 3.8 times its squared half-width
+        let expected = 3.8006 * 16.0 * 16.0;
+        assert!((covered - expected).abs() < expected * 0.01, "{covered} vs {expected}");
+    }
+
+    #[test]
+    fn plates_use_the_requested_background() {
+        let style = IconStyleConfig {
+            plate: true,
+            ..IconStyleConfig::default()
+        };
+        let background = Color32::from_rgb(0, 120, 215);
+
+        let icon = style_icon(image_with_rect(16, [4, 4, 8, 8], Color32::WHITE), &style, 64, Some(background));
+
+        assert_eq!(icon[(32, 32)], Color32::WHITE);
+        assert_eq!(icon[(32, 8)], background);
+        assert_eq!(icon[(0, 0)], Color32::TRANSPARENT);
+    }
+
+    #[test]
+    fn composites_premultiplied_colours() {
+        let top = ColorImage::new([1, 1], Color32::from_rgba_premultiplied(100, 0, 0, 128));
+        let bottom = ColorImage::new([1, 1], Color32::BLUE);
+
+        let blended = over(&top, &bottom).pixels[0];
+
+        assert_eq!(blended, Color32::from_rgba_premultiplied(100, 0, 127, 255));
+    }
+
+    #[test]
+    fn shadows_fall_below_the_icon() {
+        let icon = image_with_rect(64, [16, 16, 32, 32], Color32::WHITE);
+
+        let shadowed = with_shadow(&icon);
+
+        assert_eq!(shadowed[(32, 32)], Color32::WHITE);
+        assert!(shadowed[(32, 49)].a() > 0);
+        assert_eq!(shadowed[(32, 49)].r(), 0);
+        assert_eq!(shadowed[(32, 10)], Color32::TRANSPARENT);
+    }
+
+    #[test]
+    fn disabled_styles_leave_icons_alone() {
+        let image = image_with_rect(16, [2, 2, 4, 4], Color32::RED);
+
+        assert_eq!(style_icon(image.clone(), &IconStyleConfig::default(), 64, None), image);
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::Path;
+
+/// The sections of an INI file or a registry export, in file order.
+#[derive(Debug, Default, PartialEq)]
+pub struct IniDocument {
+    pub sections: Vec<IniSection>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct IniSection {
+    /// The section's name; for a registry export, the last component of the key's path.
+    pub name: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl IniDocument {
+    /// Parses `key=value` lines under `[section]` headers. Registry exports (`.reg` files) are
+    /// read the same way, with their quoting removed and `dword:` values turned into decimals.
+    pub fn parse(text: &str) -> Self {
+        let mut document = Self::default();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let name = header.rsplit('\\').next().unwrap_or(header).trim();
+                document.sections.push(IniSection {
+                    name: name.to_string(),
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                // e.g. "Windows Registry Editor Version 5.00"
+                continue;
+            };
+            let key = unquote(key.trim());
+            if key == "@" {
+                continue;
+            }
+            let value = value.trim();
+            let value = match value.strip_prefix("dword:") {
+                Some(hex) => u32::from_str_radix(hex, 16).map_or_else(|_| hex.to_string(), |v| v.to_string()),
+                None => unquote(value),
+            };
+
+            if document.sections.is_empty() {
+                document.sections.push(IniSection::default());
+            }
+            let section = document.sections.last_mut().expect("a section was just added");
+            section.entries.push((key, value));
+        }
+
+        document
+    }
+
+    /// Every entry with the name of its section.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.sections.iter().flat_map(|section| {
+            section
+                .entries
+                .iter()
+                .map(|(key, value)| (section.name.as_str(), key.as_str(), value.as_str()))
+        })
+    }
+}
+
+/// Reads a settings file as text. Registry exports are UTF-16 with a byte order mark; other
+/// files are UTF-8, with anything else read lossily.
+pub fn read_text(path: &Path) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+
+    if let Some(utf16) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return Ok(String::from_utf16_lossy(&units));
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(&bytes);
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Removes a registry export's quotes and backslash escapes, leaving unquoted text as is.
+fn unquote(text: &str) -> String {
+    let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) else {
+        return text.to_string();
+    };
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl IniDocument {
+        fn get(&self, section: &str, key: &str) -> Option<&str> {
+            self.entries()
+                .find(|(s, k, _)| s.eq_ignore_ascii_case(section) && k.eq_ignore_ascii_case(key))
+                .map(|(_, _, value)| value)
+        }
+    }
+
+    #[test]
+    fn reads_ini_sections() {
+        let document = IniDocument::parse(
+            "; RocketDock\n[Settings]\nTheme=Glass\nIconMinSize = 48\n\n[Icons]\n0-FileName=C:\\Apps\\app.exe\n",
+        );
+
+        assert_eq!(document.get("settings", "theme"), Some("Glass"));
+        assert_eq!(document.get("Settings", "IconMinSize"), Some("48"));
+        assert_eq!(document.get("Icons", "0-filename"), Some("C:\\Apps\\app.exe"));
+        assert_eq!(document.get("Icons", "Theme"), None);
+    }
+
+    #[test]
+    fn reads_registry_exports() {
+        let document = IniDocument::parse(
+            "Windows Registry Editor Version 5.00\r\n\r\n\
+             [HKEY_CURRENT_USER\\Software\\RocketDock]\r\n\
+             @=\"\"\r\n\
+             \"Theme\"=\"Glass \\\"2\\\"\"\r\n\
+             \"IconMinSize\"=dword:00000030\r\n\r\n\
+             [HKEY_CURRENT_USER\\Software\\RocketDock\\Icons]\r\n\
+             \"0-FileName\"=\"C:\\\\Apps\\\\app.exe\"\r\n",
+        );
+
+        let names: Vec<_> = document.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["RocketDock", "Icons"]);
+        assert_eq!(document.get("RocketDock", "Theme"), Some("Glass \"2\""));
+        assert_eq!(document.get("RocketDock", "IconMinSize"), Some("48"));
+        assert_eq!(document.get("Icons", "0-FileName"), Some("C:\\Apps\\app.exe"));
+        assert_eq!(document.entries().count(), 3);
+    }
+}
@@ -0,0 +1,456 @@
+use std::path::{Path, PathBuf};
+
+use toml_edit::{ArrayOfTables, DocumentMut, InlineTable, Item, Table, value};
+
+use crate::config::{PinnedItem, writable_config_path};
+use crate::theme::{Insets, Skin, SkinImage, shadows_built_in_theme, writable_themes_dir};
+use crate::windows::{start_menu_pin_shortcuts, taskbar_pin_shortcuts};
+
+mod ini;
+mod nexus;
+mod rocketdock;
+pub mod taskbar;
+
+use ini::{IniDocument, read_text};
+
+const USAGE: &str = "usage: ferrodock import rocketdock|nexus <settings file> [--dry-run]\n       \
+                     ferrodock import taskbar [--start-menu] [--dry-run]";
+
+/// What another dock's settings map to in FerroDock.
+#[derive(Debug, Default, PartialEq)]
+pub struct Import {
+    /// Pinned items, in the other dock's order.
+    pub pinned: Vec<PinnedItem>,
+    pub icon_size: Option<f32>,
+    pub hover_scale: Option<f32>,
+    pub screen_edge_gap: Option<f32>,
+    pub skin: Option<ImportedSkin>,
+    /// Settings FerroDock has no equivalent for.
+    pub unmapped: Vec<Unmapped>,
+}
+
+/// A skin folder of another dock, written out as a theme of the same name.
+#[derive(Debug, PartialEq)]
+pub struct ImportedSkin {
+    pub name: String,
+    /// The folder holding the skin's images.
+    pub dir: PathBuf,
+    /// The skin, with image paths relative to `dir`.
+    pub skin: Skin,
+}
+
+/// A setting that was not imported, and why.
+#[derive(Debug, PartialEq)]
+pub struct Unmapped {
+    pub setting: String,
+    pub reason: String,
+}
+
+impl Import {
+    fn unmapped(&mut self, setting: impl Into<String>, reason: impl Into<String>) {
+        self.unmapped.push(Unmapped {
+            setting: setting.into(),
+            reason: reason.into(),
+        });
+    }
+}
+
+/// Runs `ferrodock import`: reads another dock's settings, or the taskbar's pins, prints what
+/// they map to and what could not be mapped, and writes them to `config.toml` and the
+/// `themes` directory.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut dry_run = false;
+    let mut start_menu = false;
+    let mut positional = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--start-menu" => start_menu = true,
+            other if other.starts_with("--") => return Err(format!("unexpected argument `{arg}`\n{USAGE}")),
+            other => positional.push(other),
+        }
+    }
+
+    let import = match positional[..] {
+        ["taskbar"] => {
+            let mut shortcuts = taskbar_pin_shortcuts();
+            let start_menu_pins = if start_menu { start_menu_pin_shortcuts() } else { Vec::new() };
+            let found_start_menu_pins = !start_menu_pins.is_empty();
+            shortcuts.extend(start_menu_pins);
+            let mut import = taskbar::import(&shortcuts);
+            if start_menu && !found_start_menu_pins {
+                import.unmapped("Start menu", "no pins found; only the Windows 7 Start menu's pins can be read");
+            }
+            import
+        }
+        [dock @ ("rocketdock" | "nexus"), settings] => {
+            let settings = Path::new(settings);
+            let document = IniDocument::parse(&read_text(settings)?);
+            // Relative icon and skin paths are relative to the dock's installation folder
+            let dir = settings.parent().unwrap_or(Path::new("."));
+            if dock == "rocketdock" {
+                rocketdock::import(&document, dir)
+            } else {
+                nexus::import(&document, dir)
+            }
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+
+    print!("{}", describe_import(&import));
+    if !dry_run {
+        let path = write_import(&import)?;
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Writes an import's skin to the `themes` directory and its settings to `config.toml`,
+/// returning the path of `config.toml`.
+pub fn write_import(import: &Import) -> Result<PathBuf, String> {
+    let theme = match &import.skin {
+        Some(skin) => Some(write_theme(skin)?),
+        None => None,
+    };
+
+    let path = writable_config_path();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(format!("failed to read {}: {err}", path.display())),
+    };
+    let text = merge_into_config(&text, import, theme.as_deref())?;
+    std::fs::write(&path, text).map_err(|err| format!("failed to write {}: {err}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Lists the imported settings, then the ones that were not imported.
+pub fn describe_import(import: &Import) -> String {
+    let mut text = String::new();
+
+    for pin in &import.pinned {
+        let name = pin.name.as_deref().map(|name| format!(" ({name})")).unwrap_or_default();
+        text.push_str(&format!("pinned: {}{name}\n", pin.path));
+    }
+    if let Some(size) = import.icon_size {
+        text.push_str(&format!("icon_size: {size}\n"));
+    }
+    if let Some(scale) = import.hover_scale {
+        text.push_str(&format!("hover_scale: {scale}\n"));
+    }
+    if let Some(gap) = import.screen_edge_gap {
+        text.push_str(&format!("screen_edge_gap: {gap}\n"));
+    }
+    if let Some(skin) = &import.skin {
+        let name = available_theme_name(&theme_name(&skin.name), &writable_themes_dir());
+        text.push_str(&format!("theme: {name} (from {})\n", skin.dir.display()));
+    }
+
+    if !import.unmapped.is_empty() {
+        text.push_str("\nNot imported:\n");
+        for unmapped in &import.unmapped {
+            text.push_str(&format!("  {}: {}\n", unmapped.setting, unmapped.reason));
+        }
+    }
+
+    text
+}
+
+/// Writes the imported settings into the text of `config.toml`, keeping its comments and
+/// formatting. Pins are added after the existing ones, skipping applications already pinned.
+pub fn merge_into_config(text: &str, import: &Import, theme: Option<&str>) -> Result<String, String> {
+    let mut document: DocumentMut = text.parse().map_err(|err| format!("invalid config.toml: {err}"))?;
+
+    if let Some(theme) = theme {
+        document["theme"] = value(theme);
+    }
+    if let Some(size) = import.icon_size {
+        let mut table = InlineTable::new();
+        table.insert("x", f64::from(size).into());
+        table.insert("y", f64::from(size).into());
+        document["icon_size"] = value(table);
+    }
+    if let Some(scale) = import.hover_scale {
+        document["hover_scale"] = value(f64::from(scale));
+    }
+    if let Some(gap) = import.screen_edge_gap {
+        document["screen_edge_gap"] = value(f64::from(gap));
+    }
+
+    if !import.pinned.is_empty() {
+        let pinned = document
+            .entry("pinned")
+            .or_insert_with(|| Item::ArrayOfTables(ArrayOfTables::new()))
+            .as_array_of_tables_mut()
+            .ok_or("`pinned` in config.toml is not a list of tables")?;
+
+        for pin in &import.pinned {
+            let already_pinned = pinned.iter().any(|table| {
+                table
+                    .get("path")
+                    .and_then(Item::as_str)
+                    .is_some_and(|path| path.eq_ignore_ascii_case(&pin.path))
+            });
+            if already_pinned {
+                continue;
+            }
+
+            let mut table = Table::new();
+            table["path"] = value(pin.path.as_str());
+            if let Some(name) = &pin.name {
+                table["name"] = value(name.as_str());
+            }
+            if let Some(icon) = &pin.icon {
+                table["icon"] = value(icon.to_string_lossy().as_ref());
+            }
+            pinned.push(table);
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+/// Copies a skin's images into `themes/<name>/` and writes `themes/<name>.toml` drawing
+/// them, returning the theme's name. See `available_theme_name` for the name.
+fn write_theme(imported: &ImportedSkin) -> Result<String, String> {
+    let themes = writable_themes_dir();
+    let name = available_theme_name(&theme_name(&imported.name), &themes);
+    let images = themes.join(&name);
+    std::fs::create_dir_all(&images).map_err(|err| format!("failed to create {}: {err}", images.display()))?;
+
+    for image in imported.skin.images() {
+        let from = imported.dir.join(image);
+        let to = images.join(image);
+        std::fs::copy(&from, &to).map_err(|err| format!("failed to copy {}: {err}", from.display()))?;
+    }
+
+    let mut skin = imported.skin.clone();
+    skin.resolve_paths(Path::new(&name));
+    let text = theme_file(&imported.name, &skin)?;
+    let path = themes.join(format!("{name}.toml"));
+    std::fs::write(&path, text).map_err(|err| format!("failed to write {}: {err}", path.display()))?;
+    println!("Wrote {}", path.display());
+
+    Ok(name)
+}
+
+/// The text of a theme file drawing `skin` over the default theme.
+fn theme_file(source: &str, skin: &Skin) -> Result<String, String> {
+    #[derive(serde::Serialize)]
+    struct ThemeFile<'a> {
+        skin: &'a Skin,
+    }
+
+    let mut skin = skin.clone();
+    skin.use_forward_slashes();
+    let body = toml::to_string(&ThemeFile { skin: &skin }).map_err(|err| err.to_string())?;
+    Ok(format!("# Imported from the skin `{source}`\n\n{body}"))
+}
+
+/// A theme file name for a skin: lower case, with anything but letters and digits as dashes.
+fn theme_name(skin: &str) -> String {
+    let name: String = skin
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let name = name.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-");
+    if name.is_empty() { "imported".to_string() } else { name }
+}
+
+/// `name`, or `name-2`, `name-3` and so on, whichever neither exists in `themes` nor takes
+/// the place of a built-in theme.
+fn available_theme_name(name: &str, themes: &Path) -> String {
+    let taken = |candidate: &str| {
+        shadows_built_in_theme(candidate)
+            || themes.join(format!("{candidate}.toml")).exists()
+            || themes.join(candidate).exists()
+    };
+
+    std::iter::once(name.to_string())
+        .chain((2..).map(|n| format!("{name}-{n}")))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+/// Reads a skin folder shared by RocketDock and Nexus: `background.png`, with optional
+/// `indicator.png`, `overlay.png`, `shelf.png` and `reflection.png`, and the background's
+/// margins from the folder's `.ini` file.
+fn import_skin_folder(dir: &Path, name: &str, import: &mut Import) -> Option<ImportedSkin> {
+    let setting = format!("skin `{name}`");
+    if !dir.is_dir() {
+        import.unmapped(setting, format!("skin folder {} not found", dir.display()));
+        return None;
+    }
+
+    let image = |file: &str| {
+        dir.join(file).is_file().then(|| SkinImage {
+            image: PathBuf::from(file),
+            insets: Insets::default(),
+            height: None,
+        })
+    };
+    let mut skin = Skin {
+        background: image("background.png"),
+        indicator: image("indicator.png"),
+        overlay: image("overlay.png"),
+        shelf: image("shelf.png"),
+        reflection: image("reflection.png"),
+        ..Skin::default()
+    };
+    if skin.images().next().is_none() {
+        import.unmapped(setting, format!("no images found in {}", dir.display()));
+        return None;
+    }
+
+    let ini = std::fs::read_dir(dir).ok().and_then(|entries| {
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ini")))
+    });
+    if let Some(ini) = ini.and_then(|path| read_text(&path).ok()) {
+        let mut insets = Insets::default();
+        for (section, key, text) in IniDocument::parse(&ini).entries() {
+            let margin = match key.to_ascii_lowercase().as_str() {
+                "left-margin" => &mut insets.left,
+                "top-margin" => &mut insets.top,
+                "right-margin" => &mut insets.right,
+                "bottom-margin" => &mut insets.bottom,
+                _ => {
+                    import.unmapped(format!("{setting} {section}/{key}"), "no FerroDock equivalent");
+                    continue;
+                }
+            };
+            match text.parse() {
+                Ok(pixels) => *margin = pixels,
+                Err(_) => import.unmapped(format!("{setting} {section}/{key}"), "not a number"),
+            }
+        }
+        if let Some(background) = &mut skin.background {
+            background.insets = insets;
+        }
+    }
+
+    Some(ImportedSkin {
+        name: name.to_string(),
+        dir: dir.to_path_buf(),
+        skin,
+    })
+}
+
+/// Parses a size or offset, reporting it as unmapped when it is not a number.
+fn parse_number(text: &str, setting: &str, import: &mut Import) -> Option<f32> {
+    match text.trim().parse::<f32>() {
+        Ok(number) => Some(number),
+        Err(_) => {
+            import.unmapped(setting, format!("`{text}` is not a number"));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_support::unique_temp_dir;
+
+    fn import() -> Import {
+        Import {
+            pinned: vec![
+                PinnedItem {
+                    path: "C:\\Apps\\editor.exe".to_string(),
+                    name: Some("Editor".to_string()),
+                    icon: Some(PathBuf::from("C:\\Icons\\editor.png")),
+                },
+                PinnedItem {
+                    path: "C:\\Apps\\browser.exe".to_string(),
+                    ..PinnedItem::default()
+                },
+            ],
+            icon_size: Some(48.0),
+            hover_scale: Some(2.0),
+            screen_edge_gap: Some(4.0),
+            ..Import::default()
+        }
+    }
+
+    #[test]
+    fn merges_into_config_keeping_comments() {
+        let text = "# My dock\nrounding = 8.0 # rounder\n\n[[pinned]]\npath = \"c:\\\\apps\\\\BROWSER.exe\"\n";
+
+        let merged = merge_into_config(text, &import(), Some("glass")).unwrap();
+
+        assert!(merged.starts_with("# My dock\n"));
+        assert!(merged.contains("rounding = 8.0 # rounder"));
+        let config: Config = toml::from_str(&merged).unwrap();
+        assert_eq!(config.theme.as_deref(), Some("glass"));
+        assert_eq!(config.icon_size, eframe::egui::vec2(48.0, 48.0));
+        assert_eq!(config.hover_scale, 2.0);
+        assert_eq!(config.screen_edge_gap, 4.0);
+        // The browser was already pinned
+        let paths: Vec<_> = config.pinned.iter().map(|pin| pin.path.as_str()).collect();
+        assert_eq!(paths, ["c:\\apps\\BROWSER.exe", "C:\\Apps\\editor.exe"]);
+        assert_eq!(config.pinned[1], import().pinned[0]);
+    }
+
+    #[test]
+    fn describes_unmapped_settings() {
+        let mut import = import();
+        import.unmapped("Settings/AutoHide", "no FerroDock equivalent");
+
+        let text = describe_import(&import);
+
+        assert!(text.starts_with("pinned: C:\\Apps\\editor.exe (Editor)\npinned: C:\\Apps\\browser.exe\n"));
+        assert!(text.ends_with("Not imported:\n  Settings/AutoHide: no FerroDock equivalent\n"));
+    }
+
+    #[test]
+    fn theme_names_are_file_names() {
+        assert_eq!(theme_name("Glass Blue (v2)"), "glass-blue-v2");
+        assert_eq!(theme_name("???"), "imported");
+    }
+
+    #[test]
+    fn imported_themes_get_a_name_no_theme_has() {
+        let themes = unique_temp_dir("imported_theme_names");
+        std::fs::write(themes.join("glass.toml"), "").unwrap();
+        std::fs::create_dir(themes.join("glass-2")).unwrap();
+
+        assert_eq!(available_theme_name("glass", &themes), "glass-3");
+        assert_eq!(available_theme_name("frost", &themes), "frost");
+        assert_eq!(available_theme_name("macos", &themes), "macos-2");
+        assert_eq!(available_theme_name("leopard-dark", &themes), "leopard-dark-2");
+        assert_eq!(available_theme_name("classic", &themes), "classic-2");
+
+        std::fs::remove_dir_all(&themes).unwrap();
+    }
+
+    #[test]
+    fn theme_files_read_back_as_themes() {
+        let skin = Skin {
+            background: Some(SkinImage {
+                image: Path::new("glass").join("background.png"),
+                insets: Insets {
+                    left: 12.0,
+                    ..Insets::default()
+                },
+                height: None,
+            }),
+            ..Skin::default()
+        };
+
+        // Written with the platform's separator, read back the same everywhere
+        let text = theme_file("Glass", &skin).unwrap();
+
+        assert!(text.starts_with("# Imported from the skin `Glass`\n"));
+        assert!(text.contains("image = \"glass/background.png\""));
+        assert!(!text.contains('\\'));
+        let theme = crate::theme::Theme::parse(&text, eframe::egui::Color32::BLUE).unwrap();
+        let background = theme.skin.unwrap().background.unwrap();
+        assert_eq!(background.image, Path::new("glass/background.png"));
+        assert_eq!(background.insets.left, 12.0);
+    }
+}
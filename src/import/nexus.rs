@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::PinnedItem;
+use crate::import::ini::IniDocument;
+use crate::import::{Import, import_skin_folder, parse_number};
+
+/// Nexus's `ScreenPosition` value for a dock along the bottom of the screen.
+const POSITION_BOTTOM: &str = "3";
+
+/// The fields of one dock item, from the `ItemN.Field` keys of the `Items` section.
+#[derive(Default)]
+struct Item {
+    path: Option<String>,
+    label: Option<String>,
+    icon: Option<String>,
+}
+
+/// Maps Winstep Nexus's settings, read from `NeXuS.ini` or an export of its registry key, to
+/// FerroDock. `dir` is the folder of the settings file, which the `Backgrounds` folder and
+/// relative icon paths are in.
+pub fn import(document: &IniDocument, dir: &Path) -> Import {
+    let mut import = Import::default();
+    let mut items: BTreeMap<u32, Item> = BTreeMap::new();
+    let mut zoom_size = None;
+
+    for (section, key, text) in document.entries() {
+        let setting = format!("{section}/{key}");
+
+        if section.eq_ignore_ascii_case("Items") {
+            if key.eq_ignore_ascii_case("ItemCount") {
+                continue;
+            }
+            let field = key
+                .get(..4)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("item"))
+                .and_then(|_| key[4..].split_once('.'))
+                .and_then(|(n, field)| Some((n.parse().ok()?, field)));
+            let Some((index, field)) = field else {
+                import.unmapped(setting, "not a dock item");
+                continue;
+            };
+            let item = items.entry(index).or_default();
+            match field.to_ascii_lowercase().as_str() {
+                "path" => item.path = Some(text.to_string()),
+                "label" => item.label = Some(text.to_string()),
+                "icon" => item.icon = Some(text.to_string()),
+                _ if text.is_empty() || text == "0" => {}
+                "arguments" => import.unmapped(setting, "launch arguments are not supported"),
+                _ => import.unmapped(setting, "no FerroDock equivalent"),
+            }
+            continue;
+        }
+
+        match key.to_ascii_lowercase().as_str() {
+            "iconsize" => import.icon_size = parse_number(text, &setting, &mut import),
+            "zoomiconsize" => zoom_size = parse_number(text, &setting, &mut import),
+            "edgeoffset" => import.screen_edge_gap = parse_number(text, &setting, &mut import),
+            "screenposition" if text == POSITION_BOTTOM => {}
+            "screenposition" => import.unmapped(setting, "FerroDock only docks along the bottom of the screen"),
+            "backgroundtheme" => {
+                import.skin = import_skin_folder(&dir.join("Backgrounds").join(text), text, &mut import);
+            }
+            _ => import.unmapped(setting, "no FerroDock equivalent"),
+        }
+    }
+
+    if let (Some(size), Some(zoom)) = (import.icon_size, zoom_size)
+        && size > 0.0
+    {
+        import.hover_scale = Some(zoom / size);
+    }
+
+    for (index, item) in items {
+        let Some(path) = item.path.filter(|path| !path.is_empty()) else {
+            import.unmapped(format!("Items/Item{index}"), "separators and empty items are not supported");
+            continue;
+        };
+        import.pinned.push(PinnedItem {
+            path,
+            name: item.label.filter(|label| !label.is_empty()),
+            icon: item.icon.filter(|icon| !icon.is_empty()).map(|icon| dir.join(icon)),
+        });
+    }
+
+    import
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn maps_items_and_settings() {
+        let document = IniDocument::parse(
+            "[NeXuS]\nIconSize=64\nZoomIconSize=96\nEdgeOffset=2\nScreenPosition=1\n\
+             [Items]\nItemCount=3\nItem1.Path=C:\\Apps\\mail.exe\nItem1.Label=Mail\n\
+             Item0.Path=C:\\Apps\\web.exe\nItem0.Icon=web.ico\nItem2.Path=\nItem0.Arguments=-x\n",
+        );
+
+        let import = import(&document, Path::new("Nexus"));
+
+        let paths: Vec<_> = import.pinned.iter().map(|pin| pin.path.as_str()).collect();
+        assert_eq!(paths, ["C:\\Apps\\web.exe", "C:\\Apps\\mail.exe"]);
+        assert_eq!(import.pinned[0].icon, Some(Path::new("Nexus").join("web.ico")));
+        assert_eq!(import.pinned[1].name.as_deref(), Some("Mail"));
+        assert_eq!(import.icon_size, Some(64.0));
+        assert_eq!(import.hover_scale, Some(1.5));
+        assert_eq!(import.screen_edge_gap, Some(2.0));
+        let unmapped: Vec<_> = import.unmapped.iter().map(|u| u.setting.as_str()).collect();
+        assert_eq!(unmapped, ["NeXuS/ScreenPosition", "Items/Item0.Arguments", "Items/Item2"]);
+    }
+
+    #[test]
+    fn imports_background_folders() {
+        let dir = unique_temp_dir("nexus_skin");
+        let skin = dir.join("Backgrounds").join("Frost");
+        fs::create_dir_all(&skin).unwrap();
+        fs::write(skin.join("background.png"), b"png").unwrap();
+        fs::write(skin.join("indicator.png"), b"png").unwrap();
+        fs::write(skin.join("Frost.ini"), "[Background]\nLeft-Margin=14\nRight-Margin=14\nOpacity=80\n").unwrap();
+
+        let import = import(&IniDocument::parse("[NeXuS]\nBackgroundTheme=Frost\n"), &dir);
+
+        let imported = import.skin.expect("skin imported");
+        assert_eq!(imported.name, "Frost");
+        assert_eq!(imported.dir, skin);
+        let background = imported.skin.background.unwrap();
+        assert_eq!(background.image, Path::new("background.png"));
+        assert_eq!((background.insets.left, background.insets.right), (14.0, 14.0));
+        assert_eq!(imported.skin.indicator.unwrap().image, Path::new("indicator.png"));
+        assert!(imported.skin.overlay.is_none());
+        assert_eq!(import.unmapped[0].setting, "skin `Frost` Background/Opacity");
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::PinnedItem;
+use crate::import::ini::IniDocument;
+use crate::import::{Import, import_skin_folder, parse_number};
+
+/// RocketDock's `Side` value for a dock along the bottom of the screen.
+const SIDE_BOTTOM: &str = "1";
+
+/// The fields of one dock item, from the `N-Field` keys of the `Icons` section.
+#[derive(Default)]
+struct Icon {
+    file_name: Option<String>,
+    title: Option<String>,
+    icon: Option<String>,
+}
+
+/// Maps RocketDock's settings, read from `Settings.ini` or an export of its registry key, to
+/// FerroDock. `dir` is RocketDock's folder, which relative icon paths and the `Skins` folder
+/// are in.
+pub fn import(document: &IniDocument, dir: &Path) -> Import {
+    let mut import = Import::default();
+    let mut icons: BTreeMap<u32, Icon> = BTreeMap::new();
+    let mut icon_max_size = None;
+
+    for (section, key, text) in document.entries() {
+        let setting = format!("{section}/{key}");
+
+        if section.eq_ignore_ascii_case("Icons") {
+            if key.eq_ignore_ascii_case("count") {
+                continue;
+            }
+            let Some((index, field)) = key.split_once('-').and_then(|(n, f)| Some((n.parse().ok()?, f))) else {
+                import.unmapped(setting, "not a dock item");
+                continue;
+            };
+            let icon = icons.entry(index).or_default();
+            match field.to_ascii_lowercase().as_str() {
+                "filename" => icon.file_name = Some(text.to_string()),
+                "title" => icon.title = Some(text.to_string()),
+                "iconfilename" => icon.icon = Some(text.to_string()),
+                // Empty or default values lose nothing
+                _ if text.is_empty() || text == "0" => {}
+                "arguments" => import.unmapped(setting, "launch arguments are not supported"),
+                "workingdirectory" => import.unmapped(setting, "working directories are not supported"),
+                _ => import.unmapped(setting, "no FerroDock equivalent"),
+            }
+            continue;
+        }
+
+        match key.to_ascii_lowercase().as_str() {
+            "iconminsize" => import.icon_size = parse_number(text, &setting, &mut import),
+            "iconmaxsize" => icon_max_size = parse_number(text, &setting, &mut import),
+            "voffset" => import.screen_edge_gap = parse_number(text, &setting, &mut import),
+            "side" if text == SIDE_BOTTOM => {}
+            "side" => import.unmapped(setting, "FerroDock only docks along the bottom of the screen"),
+            "offset" => import.unmapped(setting, "FerroDock always centres the dock"),
+            "theme" => import.skin = import_skin_folder(&dir.join("Skins").join(text), text, &mut import),
+            _ => import.unmapped(setting, "no FerroDock equivalent"),
+        }
+    }
+
+    // RocketDock zooms to a size; FerroDock scales by a factor
+    if let (Some(min), Some(max)) = (import.icon_size, icon_max_size)
+        && min > 0.0
+    {
+        import.hover_scale = Some(max / min);
+    }
+
+    for (index, icon) in icons {
+        let Some(file_name) = icon.file_name.filter(|name| !name.is_empty() && !name.starts_with('#')) else {
+            import.unmapped(format!("Icons/{index}"), "separators and empty items are not supported");
+            continue;
+        };
+        import.pinned.push(PinnedItem {
+            path: resolve(dir, &file_name),
+            name: icon.title.filter(|title| !title.is_empty()),
+            icon: icon
+                .icon
+                .filter(|icon| !icon.is_empty())
+                .map(|icon| PathBuf::from(resolve(dir, &icon))),
+        });
+    }
+
+    import
+}
+
+/// Makes a path RocketDock stored relative to its folder absolute.
+fn resolve(dir: &Path, path: &str) -> String {
+    let is_absolute = path.starts_with("\\\\") || path.as_bytes().get(1) == Some(&b':');
+    if is_absolute {
+        path.to_string()
+    } else {
+        dir.join(path).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: &str = r"
+[Settings]
+Theme=Missing Skin
+IconMinSize=48
+IconMaxSize=96
+vOffset=6
+Side=1
+Offset=20
+AutoHide=1
+
+[Icons]
+count=4
+0-FileName=C:\Program Files\Editor\editor.exe
+0-Title=Editor
+0-IconFilename=Icons\editor.png
+0-Arguments=--new-window
+1-FileName=#Separator#
+2-FileName=Tools\tool.exe
+2-Title=
+3-FileName=C:\Windows\explorer.exe
+3-ShowCmd=0
+";
+
+    #[test]
+    fn maps_items_in_order() {
+        let dir = Path::new("RocketDock");
+        let import = import(&IniDocument::parse(SETTINGS), dir);
+
+        let paths: Vec<_> = import.pinned.iter().map(|pin| pin.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                "C:\\Program Files\\Editor\\editor.exe".to_string(),
+                dir.join("Tools\\tool.exe").to_string_lossy().into_owned(),
+                "C:\\Windows\\explorer.exe".to_string(),
+            ]
+        );
+        assert_eq!(import.pinned[0].name.as_deref(), Some("Editor"));
+        assert_eq!(import.pinned[0].icon, Some(dir.join("Icons\\editor.png")));
+        assert_eq!(import.pinned[1].name, None);
+    }
+
+    #[test]
+    fn maps_size_and_position() {
+        let import = import(&IniDocument::parse(SETTINGS), Path::new("RocketDock"));
+
+        assert_eq!(import.icon_size, Some(48.0));
+        assert_eq!(import.hover_scale, Some(2.0));
+        assert_eq!(import.screen_edge_gap, Some(6.0));
+    }
+
+    #[test]
+    fn reports_unmapped_settings() {
+        let import = import(&IniDocument::parse(SETTINGS), Path::new("RocketDock"));
+
+        let unmapped: Vec<_> = import.unmapped.iter().map(|u| u.setting.as_str()).collect();
+        assert_eq!(
+            unmapped,
+            [
+                "skin `Missing Skin`",
+                "Settings/Offset",
+                "Settings/AutoHide",
+                "Icons/0-Arguments",
+                "Icons/1",
+            ]
+        );
+        assert!(import.skin.is_none());
+    }
+
+    #[test]
+    fn reports_other_sides() {
+        let import = import(&IniDocument::parse("[Settings]\nSide=0\n"), Path::new("."));
+
+        assert_eq!(import.unmapped[0].setting, "Settings/Side");
+    }
+}
@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::PinnedItem;
+use crate::import::Import;
+use crate::windows::{ShellLink, read_shell_link};
+
+/// Maps pinned shortcuts, in order, to pinned items named after the shortcuts.
+pub fn import(shortcuts: &[PathBuf]) -> Import {
+    let links: Vec<_> = shortcuts
+        .iter()
+        .map(|path| (path.as_path(), read_shell_link(path)))
+        .collect();
+    import_links(&links)
+}
+
+fn import_links(links: &[(&Path, Option<ShellLink>)]) -> Import {
+    let mut import = Import::default();
+
+    for (path, link) in links {
+        let setting = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let Some(link) = link else {
+            import.unmapped(setting, "not a readable shortcut");
+            continue;
+        };
+        let Some(target) = &link.target else {
+            import.unmapped(setting, "shortcuts to packaged apps and shell folders cannot be pinned");
+            continue;
+        };
+        if import.pinned.iter().any(|pin| pin.path.eq_ignore_ascii_case(target)) {
+            continue;
+        }
+        if link.arguments.is_some() {
+            import.unmapped(format!("{setting} arguments"), "launch arguments are not supported");
+        }
+
+        // Only icon files can be drawn; icons inside executables are read from the target
+        let icon = link
+            .icon_location
+            .as_deref()
+            .map(|location| location.rsplit_once(',').map_or(location, |(file, _)| file))
+            .filter(|file| !file.contains('%'))
+            .map(PathBuf::from)
+            .filter(|file| file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ico")));
+
+        import.pinned.push(PinnedItem {
+            path: target.clone(),
+            name: path.file_stem().map(|stem| stem.to_string_lossy().into_owned()),
+            icon,
+        });
+    }
+
+    import
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(target: &str) -> Option<ShellLink> {
+        Some(ShellLink {
+            target: Some(target.to_string()),
+            ..ShellLink::default()
+        })
+    }
+
+    #[test]
+    fn pins_shortcut_targets_in_order() {
+        let links = [
+            (Path::new("Terminal.lnk"), link("C:\\Apps\\terminal.exe")),
+            (
+                Path::new("Editor.lnk"),
+                Some(ShellLink {
+                    target: Some("C:\\Apps\\editor.exe".to_string()),
+                    arguments: Some("--profile work".to_string()),
+                    icon_location: Some("C:\\Icons\\editor.ico,0".to_string()),
+                }),
+            ),
+            (Path::new("Terminal (2).lnk"), link("c:\\apps\\TERMINAL.exe")),
+        ];
+
+        let import = import_links(&links);
+
+        let pins: Vec<_> = import.pinned.iter().map(|pin| (pin.path.as_str(), pin.name.as_deref())).collect();
+        assert_eq!(pins, [("C:\\Apps\\terminal.exe", Some("Terminal")), ("C:\\Apps\\editor.exe", Some("Editor"))]);
+        assert_eq!(import.pinned[1].icon, Some(PathBuf::from("C:\\Icons\\editor.ico")));
+        assert_eq!(import.unmapped[0].setting, "Editor.lnk arguments");
+    }
+
+    #[test]
+    fn reports_shortcuts_without_a_file() {
+        let links = [
+            (Path::new("Calculator.lnk"), Some(ShellLink::default())),
+            (Path::new("Broken.lnk"), None),
+            (
+                Path::new("Explorer.lnk"),
+                Some(ShellLink {
+                    target: Some("C:\\Windows\\explorer.exe".to_string()),
+                    icon_location: Some("%SystemRoot%\\explorer.exe,0".to_string()),
+                    ..ShellLink::default()
+                }),
+            ),
+        ];
+
+        let import = import_links(&links);
+
+        assert_eq!(import.pinned.len(), 1);
+        assert_eq!(import.pinned[0].icon, None);
+        let unmapped: Vec<_> = import.unmapped.iter().map(|u| u.setting.as_str()).collect();
+        assert_eq!(unmapped, ["Calculator.lnk", "Broken.lnk"]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::io;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+
+use crate::windows::{DockIcon, is_uwp_app};
+
+/// How long the reason a launch failed is shown on its item.
+pub const FAILURE_DISPLAY: Duration = Duration::from_secs(10);
+
+/// The state of an application launched from the dock.
+pub enum Launch {
+    /// The process was spawned and no window has appeared for it yet.
+    Pending {
+        started: Instant,
+        child: Option<Child>,
+    },
+    /// The launch failed; `message` is shown on the item until `at + FAILURE_DISPLAY`.
+    Failed { message: String, at: Instant },
+}
+
+/// Tracks applications launched from the dock, keyed by the same path used for `DockIcon::path`.
+#[derive(Default)]
+pub struct LaunchTracker {
+    launches: HashMap<String, Launch>,
+}
+
+impl LaunchTracker {
+    /// Spawns the application at `path` and starts tracking its launch.
+    ///
+    /// Packaged apps are started through `explorer.exe`, so the spawned process is not
+    /// the app itself and only the app's identity is used to detect its window.
+    pub fn launch(&mut self, path: &str) {
+        let result = if is_uwp_app(path) {
+            Command::new("explorer.exe").arg(path).spawn()
+        } else {
+            Command::new(path).spawn()
+        };
+
+        let child = if is_uwp_app(path) {
+            result.map(|_| None)
+        } else {
+            result.map(Some)
+        };
+
+        self.record(path, child);
+    }
+
+    fn record(&mut self, path: &str, spawned: io::Result<Option<Child>>) {
+        let launch = match spawned {
+            Ok(child) => Launch::Pending {
+                started: Instant::now(),
+                child,
+            },
+            Err(err) => Launch::Failed {
+                message: err.to_string(),
+                at: Instant::now(),
+            },
+        };
+
+        self.launches.insert(path.to_string(), launch);
+    }
+
+    /// Finishes launches whose application now has a window, turns processes that exited
+    /// before showing a window into failures and drops pending launches older than `timeout`
+    /// and failures older than `FAILURE_DISPLAY`. Returns whether any launch was dropped.
+    pub fn update(&mut self, items: &[DockIcon], timeout: Duration) -> bool {
+        let tracked = self.launches.len();
+        self.launches.retain(|path, launch| match launch {
+            Launch::Pending { started, child } => {
+                let has_window = items
+                    .iter()
+                    .any(|i| i.path == *path && !i.windows.is_empty());
+                if has_window || started.elapsed() >= timeout {
+                    return false;
+                }
+
+                if let Some(status) = child.as_mut().and_then(|c| c.try_wait().ok().flatten())
+                    && !status.success()
+                {
+                    *launch = Launch::Failed {
+                        message: format!("exited with {status}"),
+                        at: Instant::now(),
+                    };
+                }
+
+                true
+            }
+            Launch::Failed { at, .. } => at.elapsed() < FAILURE_DISPLAY,
+        });
+        self.launches.len() < tracked
+    }
+
+    /// Returns how long until the next failure stops being shown, if any are.
+    pub fn next_expiry(&self) -> Option<Duration> {
+        self.launches
+            .values()
+            .filter_map(|launch| match launch {
+                Launch::Failed { at, .. } => Some(FAILURE_DISPLAY.saturating_sub(at.elapsed())),
+                Launch::Pending { .. } => None,
+            })
+            .min()
+    }
+
+    /// Returns when the pending launch of `path` started, if it is still pending.
+    pub fn pending_since(&self, path: &str) -> Option<Instant> {
+        match self.launches.get(path) {
+            Some(Launch::Pending { started, .. }) => Some(*started),
+            _ => None,
+        }
+    }
+
+    /// Returns the reason the last launch of `path` failed.
+    pub fn error(&self, path: &str) -> Option<&str> {
+        match self.launches.get(path) {
+            Some(Launch::Failed { message, .. }) => Some(message),
+            _ => None,
+        }
+    }
+
+    pub fn is_tracked(&self, path: &str) -> bool {
+        self.launches.contains_key(path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.launches.is_empty()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.launches
+            .values()
+            .any(|l| matches!(l, Launch::Pending { .. }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::HICON;
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    fn running_item(path: &str) -> DockIcon {
+        DockIcon {
+            path: path.to_string(),
+            name: None,
+            icon: None,
+            aumid: None,
+            class: None,
+            hicon: HICON(0),
+            hwnd: HWND(1),
+            windows: vec![HWND(1)],
+        }
+    }
+
+    #[test]
+    fn failed_spawn_is_reported_as_error() {
+        let mut launches = LaunchTracker::default();
+        launches.record(
+            "missing.exe",
+            Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+        );
+
+        assert_eq!(launches.error("missing.exe"), Some("not found"));
+        assert!(launches.pending_since("missing.exe").is_none());
+        assert!(!launches.has_pending());
+    }
+
+    #[test]
+    fn pending_launch_finishes_once_a_window_appears() {
+        let mut launches = LaunchTracker::default();
+        launches.record("app.exe", Ok(None));
+        assert!(launches.pending_since("app.exe").is_some());
+
+        launches.update(&[], TIMEOUT);
+        assert!(launches.is_tracked("app.exe"));
+
+        launches.update(&[running_item("app.exe")], TIMEOUT);
+        assert!(!launches.is_tracked("app.exe"));
+    }
+
+    #[test]
+    fn pending_launch_times_out() {
+        let mut launches = LaunchTracker::default();
+        launches.record("slow.exe", Ok(None));
+
+        launches.update(&[], Duration::ZERO);
+        assert!(!launches.is_tracked("slow.exe"));
+    }
+
+    #[test]
+    fn failures_expire_on_their_own_without_pending_launches() {
+        let mut launches = LaunchTracker::default();
+        launches.record(
+            "missing.exe",
+            Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+        );
+
+        // Failures are shown for a fixed time, even when launches never time out
+        assert!(!launches.update(&[], Duration::ZERO));
+        assert!(launches.error("missing.exe").is_some());
+        assert!(launches.next_expiry().is_some_and(|expiry| expiry <= FAILURE_DISPLAY));
+
+        launches.launches.insert(
+            "missing.exe".to_string(),
+            Launch::Failed {
+                message: "not found".to_string(),
+                at: Instant::now() - FAILURE_DISPLAY,
+            },
+        );
+        assert!(!launches.has_pending());
+        assert!(launches.update(&[], Duration::MAX));
+        assert!(launches.error("missing.exe").is_none());
+        assert!(launches.is_empty());
+        assert_eq!(launches.next_expiry(), None);
+    }
+}
@@ -0,0 +1,633 @@
+use std::ops::Range;
+
+use eframe::egui::{Pos2, Rect, Vec2, pos2, vec2};
+
+use crate::config::{Config, OverflowPolicy};
+
+/// Height reserved below the icons for the running indicator.
+const INDICATOR_SPACE: f32 = 4.0;
+/// Extra room above the tallest animated icon so it is not clipped by the window edge.
+const TOP_PADDING: f32 = 6.0;
+const BASE_DPI: f32 = 96.0;
+
+/// A monitor as reported by the OS, in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MonitorGeometry {
+    pub bounds: Rect,
+    /// The part of `bounds` not covered by the taskbar and other app bars.
+    pub work_area: Rect,
+    pub dpi: f32,
+}
+
+impl Default for MonitorGeometry {
+    /// A 1920x1080 monitor at 96 DPI with a 40px taskbar, used until the real one is known.
+    fn default() -> Self {
+        Self {
+            bounds: Rect::from_min_size(Pos2::ZERO, Vec2::new(1920.0, 1080.0)),
+            work_area: Rect::from_min_size(Pos2::ZERO, Vec2::new(1920.0, 1040.0)),
+            dpi: BASE_DPI,
+        }
+    }
+}
+
+impl MonitorGeometry {
+    /// Logical-to-physical scale factor; never below 1.0, matching how the dock was always sized.
+    pub fn scale_factor(&self) -> f32 {
+        (self.dpi / BASE_DPI).max(1.0)
+    }
+
+    /// The usable area in logical points. Falls back to the full monitor when the
+    /// work area is empty or lies outside it.
+    pub fn usable_area(&self) -> Rect {
+        let work_area = self.work_area.intersect(self.bounds);
+        let area = if work_area.is_positive() {
+            work_area
+        } else {
+            self.bounds
+        };
+
+        let scale = self.scale_factor();
+        Rect::from_min_max(
+            pos2(area.min.x / scale, area.min.y / scale),
+            pos2(area.max.x / scale, area.max.y / scale),
+        )
+    }
+}
+
+/// Interaction state that feeds into the layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ViewState {
+    /// The hovered item, as an index into the items being laid out.
+    pub hovered: Option<usize>,
+    /// The first item shown while the dock is scrolled.
+    pub scroll: usize,
+    /// Whether the popup listing collapsed items is open.
+    pub overflow_open: bool,
+}
+
+/// The position of one dock item within the dock window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ItemLayout {
+    /// The index of the item among those being laid out.
+    pub index: usize,
+    /// The fixed slot the item occupies; used for hit-testing.
+    pub slot: Rect,
+    /// Where the icon is drawn, enlarged when hovered.
+    pub icon: Rect,
+}
+
+/// The grid of items collapsed into the "more" item, shown above the dock.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverflowPopup {
+    /// The popup background, relative to the window.
+    pub rect: Rect,
+    pub items: Vec<ItemLayout>,
+}
+
+/// The computed geometry of the dock, in logical points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DockLayout {
+    /// The dock window in screen coordinates.
+    pub window: Rect,
+    /// The dock background, relative to the window.
+    pub dock: Rect,
+    /// Each item shown in the dock row, relative to the window.
+    pub items: Vec<ItemLayout>,
+    /// The largest useful `ViewState::scroll`; zero unless the dock scrolls.
+    pub max_scroll: usize,
+    /// Arrows in the dock margins for scrolling back and forward, when there is more to see.
+    pub scroll_back: Option<Rect>,
+    pub scroll_forward: Option<Rect>,
+    /// The slot of the "more" item holding the items that did not fit.
+    pub more: Option<Rect>,
+    pub overflow_popup: Option<OverflowPopup>,
+}
+
+impl DockLayout {
+    /// Returns the index of the item whose slot contains `pos` (window coordinates),
+    /// looking in the dock row and the open overflow popup.
+    pub fn item_at(&self, pos: Pos2) -> Option<usize> {
+        self.items
+            .iter()
+            .chain(self.overflow_popup.iter().flat_map(|popup| &popup.items))
+            .find(|item| item.slot.contains(pos))
+            .map(|item| item.index)
+    }
+
+    /// The area that takes mouse input: the dock plus any open popup.
+    pub fn interactive_rect(&self) -> Rect {
+        match &self.overflow_popup {
+            Some(popup) => self.dock.union(popup.rect),
+            None => self.dock,
+        }
+    }
+}
+
+/// Which items make it into the dock row, and at what size.
+struct RowPlan {
+    icon: Vec2,
+    shown: Range<usize>,
+    max_scroll: usize,
+    more: bool,
+}
+
+/// Decides how `item_count` items share `max_width` according to `config.overflow`.
+fn plan_row(config: &Config, item_count: usize, max_width: f32, view: &ViewState) -> RowPlan {
+    let spacing = config.icon_spacing;
+    let chrome = config.left_margin + config.right_margin;
+
+    // How many icons of the given width fit side by side
+    let capacity = |width: f32| -> usize {
+        (((max_width - chrome + spacing) / (width + spacing)).floor() as usize).max(1)
+    };
+    let scrolled = |icon: Vec2| {
+        let visible = capacity(icon.x);
+        let max_scroll = item_count.saturating_sub(visible);
+        let first = view.scroll.min(max_scroll);
+
+        RowPlan {
+            icon,
+            shown: first..(first + visible).min(item_count),
+            max_scroll,
+            more: false,
+        }
+    };
+
+    let icon = config.icon_size;
+    if item_count <= capacity(icon.x) {
+        return RowPlan {
+            icon,
+            shown: 0..item_count,
+            max_scroll: 0,
+            more: false,
+        };
+    }
+
+    match config.overflow {
+        OverflowPolicy::Shrink => {
+            let width = (max_width - chrome - (item_count - 1) as f32 * spacing) / item_count as f32;
+            let min_width = config.min_icon_size.min(icon.x);
+
+            if width >= min_width {
+                RowPlan {
+                    icon: icon * (width / icon.x),
+                    shown: 0..item_count,
+                    max_scroll: 0,
+                    more: false,
+                }
+            } else {
+                // Even the smallest icons do not fit, so scroll through them
+                scrolled(icon * (min_width / icon.x))
+            }
+        }
+        OverflowPolicy::Scroll => scrolled(icon),
+        OverflowPolicy::Collapse => RowPlan {
+            icon,
+            shown: 0..capacity(icon.x) - 1,
+            max_scroll: 0,
+            more: true,
+        },
+    }
+}
+
+/// The menu bar strip across the top of `monitor`, in logical points.
+pub fn menu_bar_rect(monitor: &MonitorGeometry, height: f32) -> Rect {
+    let scale = monitor.scale_factor();
+    let bounds = Rect::from_min_max(
+        pos2(monitor.bounds.min.x / scale, monitor.bounds.min.y / scale),
+        pos2(monitor.bounds.max.x / scale, monitor.bounds.max.y / scale),
+    );
+
+    Rect::from_min_size(bounds.min, vec2(bounds.width(), height.clamp(0.0, bounds.height())))
+}
+
+/// Computes the dock window and item geometry.
+///
+/// The dock is sized to fit `item_count` icons, centred horizontally on the usable area of
+/// `monitor` and placed `screen_edge_gap` above its bottom edge. The window leaves room above
+/// and beside the dock for hover magnification and bounce animations. Items that do not fit
+/// on the monitor are handled according to `config.overflow`.
+///
+/// # Examples
+///
+/// ```
+/// let monitor = MonitorGeometry {
+///     bounds: Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1080.0)),
+///     work_area: Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1040.0)),
+///     dpi: 96.0,
+/// };
+/// let layout = compute_layout(&monitor, &Config::default(), 3, &ViewState::default());
+/// assert_eq!(layout.items.len(), 3);
+/// ```
+pub fn compute_layout(
+    monitor: &MonitorGeometry,
+    config: &Config,
+    item_count: usize,
+    view: &ViewState,
+) -> DockLayout {
+    let usable = monitor.usable_area();
+    let hover_scale = config.hover_scale.max(1.0);
+
+    let side_headroom = |icon: Vec2| icon.x * (hover_scale - 1.0) / 2.0;
+    let plan = plan_row(
+        config,
+        item_count,
+        usable.width() - 2.0 * side_headroom(config.icon_size),
+        view,
+    );
+    let icon = plan.icon;
+
+    let indicator_space = if config.show_active_indicators {
+        INDICATOR_SPACE
+    } else {
+        0.0
+    };
+
+    let slot_count = plan.shown.len() + usize::from(plan.more);
+    let content_width = slot_count as f32 * icon.x
+        + slot_count.saturating_sub(1) as f32 * config.icon_spacing;
+    let dock_size = vec2(
+        config.left_margin + content_width.max(icon.x) + config.right_margin,
+        config.top_margin + icon.y + config.bottom_margin + indicator_space,
+    );
+
+    // Collapsed items are listed in a roughly square grid
+    let hidden = if plan.more { plan.shown.end..item_count } else { 0..0 };
+    let popup_grid = (view.overflow_open && !hidden.is_empty()).then(|| {
+        let columns = (hidden.len() as f32).sqrt().ceil() as usize;
+        let rows = hidden.len().div_ceil(columns);
+        let cells = vec2(columns as f32, rows as f32);
+        let size = vec2(config.left_margin + config.right_margin, config.top_margin * 2.0)
+            + cells * icon
+            + (cells - Vec2::splat(1.0)) * config.icon_spacing;
+
+        (columns, size)
+    });
+    let popup_size = popup_grid.map_or(Vec2::ZERO, |(_, size)| size);
+
+    // Room for a hovered icon at either end, and for icons growing or bouncing upwards
+    let top_headroom = (icon.y * (hover_scale - 1.0) / 2.0).max(config.bounce_height) + TOP_PADDING;
+    let popup_space = if popup_grid.is_some() {
+        popup_size.y + top_headroom
+    } else {
+        0.0
+    };
+
+    let window_size = vec2(
+        ((dock_size.x + 2.0 * side_headroom(icon)).max(popup_size.x + 2.0 * side_headroom(icon)))
+            .min(usable.width()),
+        (dock_size.y + top_headroom + popup_space).min(usable.height()),
+    );
+    let window_pos = pos2(
+        usable.center().x - window_size.x / 2.0,
+        usable.max.y - config.screen_edge_gap - window_size.y,
+    );
+    let window = Rect::from_min_size(window_pos, window_size);
+
+    let dock = Rect::from_min_size(
+        pos2((window_size.x - dock_size.x) / 2.0, window_size.y - dock_size.y),
+        dock_size,
+    );
+
+    let slot_at = |origin: Pos2, column: usize, row: usize| {
+        Rect::from_min_size(
+            origin
+                + vec2(
+                    column as f32 * (icon.x + config.icon_spacing),
+                    row as f32 * (icon.y + config.icon_spacing),
+                ),
+            icon,
+        )
+    };
+    let item_layout = |index: usize, slot: Rect| {
+        let scale = if view.hovered == Some(index) { hover_scale } else { 1.0 };
+
+        ItemLayout {
+            index,
+            slot,
+            icon: Rect::from_center_size(slot.center(), icon * scale),
+        }
+    };
+
+    let row_origin = dock.min + vec2(config.left_margin, config.top_margin);
+    let items: Vec<ItemLayout> = plan
+        .shown
+        .clone()
+        .enumerate()
+        .map(|(column, index)| item_layout(index, slot_at(row_origin, column, 0)))
+        .collect();
+
+    let more = plan.more.then(|| slot_at(row_origin, plan.shown.len(), 0));
+
+    let overflow_popup = popup_grid.zip(more).map(|((columns, size), more)| {
+        let left = (more.center().x - size.x / 2.0).clamp(0.0, (window_size.x - size.x).max(0.0));
+        let rect = Rect::from_min_size(pos2(left, dock.min.y - top_headroom - size.y), size);
+        let origin = rect.min + vec2(config.left_margin, config.top_margin);
+
+        OverflowPopup {
+            rect,
+            items: hidden
+                .clone()
+                .enumerate()
+                .map(|(n, index)| item_layout(index, slot_at(origin, n % columns, n / columns)))
+                .collect(),
+        }
+    });
+
+    // The arrows fill the margin beside the first and last visible slot
+    let scroll_back = (plan.shown.start > 0).then(|| {
+        Rect::from_min_max(pos2(dock.min.x, row_origin.y), pos2(row_origin.x, row_origin.y + icon.y))
+    });
+    let scroll_forward = (plan.shown.end < item_count && !plan.more).then(|| {
+        let end = dock.max.x - config.right_margin;
+        Rect::from_min_max(pos2(end, row_origin.y), pos2(dock.max.x, row_origin.y + icon.y))
+    });
+
+    DockLayout {
+        window,
+        dock,
+        items,
+        max_scroll: plan.max_scroll,
+        scroll_back,
+        scroll_forward,
+        more,
+        overflow_popup,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(dpi: f32) -> MonitorGeometry {
+        let scale = dpi / BASE_DPI;
+        MonitorGeometry {
+            bounds: Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1080.0) * scale),
+            work_area: Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1040.0) * scale),
+            dpi,
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            icon_size: vec2(40.0, 40.0),
+            icon_spacing: 10.0,
+            left_margin: 14.0,
+            right_margin: 14.0,
+            top_margin: 8.0,
+            bottom_margin: 8.0,
+            show_active_indicators: true,
+            hover_scale: 1.5,
+            bounce_height: 14.0,
+            screen_edge_gap: 10.0,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn dock_width_fits_its_items() {
+        let layout = compute_layout(&monitor(96.0), &config(), 3, &ViewState::default());
+
+        // 14 + 3 * 40 + 2 * 10 + 14
+        assert_eq!(layout.dock.width(), 168.0);
+        // 8 + 40 + 8 + indicator
+        assert_eq!(layout.dock.height(), 60.0);
+    }
+
+    #[test]
+    fn empty_dock_keeps_room_for_one_icon() {
+        let layout = compute_layout(&monitor(96.0), &config(), 0, &ViewState::default());
+
+        assert!(layout.items.is_empty());
+        assert_eq!(layout.dock.width(), 68.0);
+    }
+
+    #[test]
+    fn window_is_centred_above_the_work_area_bottom() {
+        let layout = compute_layout(&monitor(96.0), &config(), 3, &ViewState::default());
+
+        assert_eq!(layout.window.center().x, 960.0);
+        assert_eq!(layout.window.max.y, 1040.0 - 10.0);
+    }
+
+    #[test]
+    fn window_leaves_headroom_for_magnification_and_bounce() {
+        let layout = compute_layout(&monitor(96.0), &config(), 3, &ViewState::default());
+
+        // Hovered icons grow by 10px on each side
+        assert_eq!(layout.window.width(), layout.dock.width() + 20.0);
+        // Bounce height (14) exceeds the vertical hover growth (10)
+        assert_eq!(layout.window.height(), layout.dock.height() + 14.0 + TOP_PADDING);
+        assert_eq!(layout.dock.max.y, layout.window.height());
+    }
+
+    #[test]
+    fn items_are_laid_out_left_to_right_inside_the_dock() {
+        let layout = compute_layout(&monitor(96.0), &config(), 3, &ViewState::default());
+        let slots: Vec<Rect> = layout.items.iter().map(|i| i.slot).collect();
+
+        assert_eq!(slots[0].min, layout.dock.min + vec2(14.0, 8.0));
+        assert_eq!(slots[1].min.x - slots[0].max.x, 10.0);
+        assert_eq!(layout.dock.max.x - slots[2].max.x, 14.0);
+        assert!(slots.iter().all(|s| s.size() == vec2(40.0, 40.0)));
+    }
+
+    #[test]
+    fn hovered_item_icon_is_magnified_around_its_slot() {
+        let layout = compute_layout(
+            &monitor(96.0),
+            &config(),
+            3,
+            &ViewState {
+                hovered: Some(1),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(layout.items[1].icon.size(), vec2(60.0, 60.0));
+        assert_eq!(layout.items[1].icon.center(), layout.items[1].slot.center());
+        assert_eq!(layout.items[0].icon, layout.items[0].slot);
+    }
+
+    #[test]
+    fn item_at_hit_tests_slots() {
+        let layout = compute_layout(&monitor(96.0), &config(), 3, &ViewState::default());
+
+        assert_eq!(layout.item_at(layout.items[2].slot.center()), Some(2));
+        assert_eq!(layout.item_at(layout.dock.min), None);
+    }
+
+    #[test]
+    fn high_dpi_monitors_are_converted_to_logical_points() {
+        let layout = compute_layout(&monitor(192.0), &config(), 3, &ViewState::default());
+
+        assert_eq!(layout.window.center().x, 960.0);
+        assert_eq!(layout.window.max.y, 1030.0);
+        assert_eq!(layout.dock.width(), 168.0);
+    }
+
+    #[test]
+    fn window_never_exceeds_the_usable_width() {
+        for overflow in [OverflowPolicy::Shrink, OverflowPolicy::Scroll, OverflowPolicy::Collapse] {
+            let config = Config { overflow, ..config() };
+            let layout = compute_layout(&monitor(96.0), &config, 100, &ViewState::default());
+
+            assert!(layout.window.width() <= 1920.0, "{overflow:?}");
+            assert!(layout.window.min.x >= 0.0, "{overflow:?}");
+            assert!(layout.dock.max.x <= layout.window.width(), "{overflow:?}");
+        }
+    }
+
+    /// A monitor with room for exactly ten full-size icons: 20 + 28 + 10 * 40 + 9 * 10.
+    fn narrow_monitor() -> MonitorGeometry {
+        let area = Rect::from_min_size(Pos2::ZERO, vec2(538.0, 1080.0));
+        MonitorGeometry {
+            bounds: area,
+            work_area: area,
+            dpi: 96.0,
+        }
+    }
+
+    fn overflow_layout(overflow: OverflowPolicy, item_count: usize, view: &ViewState) -> DockLayout {
+        let config = Config { overflow, ..config() };
+        compute_layout(&narrow_monitor(), &config, item_count, view)
+    }
+
+    #[test]
+    fn items_that_fit_ignore_the_overflow_policy() {
+        let layout = overflow_layout(OverflowPolicy::Collapse, 10, &ViewState::default());
+
+        assert_eq!(layout.items.len(), 10);
+        assert_eq!(layout.more, None);
+        assert_eq!(layout.max_scroll, 0);
+    }
+
+    #[test]
+    fn shrink_scales_icons_to_fit() {
+        let layout = overflow_layout(OverflowPolicy::Shrink, 12, &ViewState::default());
+
+        assert_eq!(layout.items.len(), 12);
+        // (518 - 28 - 11 * 10) / 12
+        let width = layout.items[0].slot.width();
+        assert!((width - 31.666).abs() < 0.01, "{width}");
+        assert_eq!(layout.items[0].slot.width(), layout.items[0].slot.height());
+        assert!(layout.dock.width() <= 518.0 + 0.01);
+    }
+
+    #[test]
+    fn shrink_scrolls_once_icons_reach_the_minimum_size() {
+        let layout = overflow_layout(OverflowPolicy::Shrink, 30, &ViewState::default());
+
+        assert!(layout.items.iter().all(|item| item.slot.width() == 24.0));
+        // (518 - 28 + 10) / (24 + 10)
+        assert_eq!(layout.items.len(), 14);
+        assert_eq!(layout.max_scroll, 16);
+    }
+
+    #[test]
+    fn scroll_shows_a_window_of_full_size_items() {
+        let view = ViewState {
+            scroll: 3,
+            ..Default::default()
+        };
+        let layout = overflow_layout(OverflowPolicy::Scroll, 15, &view);
+        let indices: Vec<usize> = layout.items.iter().map(|item| item.index).collect();
+
+        assert_eq!(indices, (3..13).collect::<Vec<_>>());
+        assert_eq!(layout.max_scroll, 5);
+        assert!(layout.scroll_back.is_some());
+        assert!(layout.scroll_forward.is_some());
+        assert_eq!(layout.item_at(layout.items[0].slot.center()), Some(3));
+    }
+
+    #[test]
+    fn scroll_is_clamped_to_the_last_page() {
+        let view = ViewState {
+            scroll: 99,
+            ..Default::default()
+        };
+        let layout = overflow_layout(OverflowPolicy::Scroll, 15, &view);
+
+        assert_eq!(layout.items.first().map(|item| item.index), Some(5));
+        assert_eq!(layout.items.last().map(|item| item.index), Some(14));
+        assert!(layout.scroll_forward.is_none());
+    }
+
+    #[test]
+    fn collapse_moves_the_excess_into_a_more_item() {
+        let layout = overflow_layout(OverflowPolicy::Collapse, 15, &ViewState::default());
+
+        assert_eq!(layout.items.len(), 9);
+        let more = layout.more.expect("more item");
+        assert_eq!(more.min.x - layout.items[8].slot.max.x, 10.0);
+        assert!(layout.overflow_popup.is_none());
+    }
+
+    #[test]
+    fn open_overflow_popup_lists_the_collapsed_items_above_the_dock() {
+        let view = ViewState {
+            overflow_open: true,
+            ..Default::default()
+        };
+        let layout = overflow_layout(OverflowPolicy::Collapse, 15, &view);
+        let popup = layout.overflow_popup.as_ref().expect("popup");
+        let indices: Vec<usize> = popup.items.iter().map(|item| item.index).collect();
+
+        assert_eq!(indices, (9..15).collect::<Vec<_>>());
+        // Six items in a 3x2 grid
+        assert_eq!(popup.items[3].slot.min.x, popup.items[0].slot.min.x);
+        assert!(popup.items[3].slot.min.y > popup.items[0].slot.min.y);
+        assert!(popup.rect.max.y < layout.dock.min.y);
+        assert!(popup.rect.min.y >= 0.0);
+        assert!(layout.interactive_rect().contains(popup.rect.center()));
+        assert_eq!(layout.item_at(popup.items[0].slot.center()), Some(9));
+    }
+
+    #[test]
+    fn work_area_on_a_secondary_monitor_is_respected() {
+        let secondary = MonitorGeometry {
+            bounds: Rect::from_min_size(pos2(1920.0, 0.0), vec2(1280.0, 1024.0)),
+            work_area: Rect::from_min_size(pos2(1920.0, 0.0), vec2(1280.0, 1024.0)),
+            dpi: 96.0,
+        };
+        let layout = compute_layout(&secondary, &config(), 2, &ViewState::default());
+
+        assert_eq!(layout.window.center().x, 1920.0 + 640.0);
+        assert_eq!(layout.window.max.y, 1014.0);
+    }
+
+    #[test]
+    fn empty_work_area_falls_back_to_monitor_bounds() {
+        let geometry = MonitorGeometry {
+            work_area: Rect::NOTHING,
+            ..monitor(96.0)
+        };
+
+        assert_eq!(geometry.usable_area(), Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1080.0)));
+    }
+
+    #[test]
+    fn menu_bar_spans_the_top_of_the_monitor() {
+        let secondary = MonitorGeometry {
+            bounds: Rect::from_min_size(pos2(3840.0, 0.0), vec2(2560.0, 1440.0)),
+            work_area: Rect::from_min_size(pos2(3840.0, 30.0), vec2(2560.0, 1410.0)),
+            dpi: 192.0,
+        };
+
+        assert_eq!(
+            menu_bar_rect(&secondary, 26.0),
+            Rect::from_min_size(pos2(1920.0, 0.0), vec2(1280.0, 26.0))
+        );
+    }
+
+    #[test]
+    fn indicators_off_shrinks_the_dock() {
+        let config = Config {
+            show_active_indicators: false,
+            ..config()
+        };
+        let layout = compute_layout(&monitor(96.0), &config, 1, &ViewState::default());
+
+        assert_eq!(layout.dock.height(), 56.0);
+    }
+}
@@ -1,59 +1,66 @@
 use eframe::egui::{self};
 use eframe::{self};
-use ::windows::Win32::Foundation::RECT;
-use ::windows::Win32::Graphics::Gdi::{GetDC, GetDeviceCaps, LOGPIXELSX, ReleaseDC};
-use ::windows::Win32::UI::WindowsAndMessaging::{
-    SPI_GETWORKAREA, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SystemParametersInfoW,
-};
 
 // Module(s)
+mod accessibility;
+mod animation;
 mod app;
 mod config;
+mod diagnose;
 mod events;
+mod first_run;
+mod focus;
+mod hotkeys;
+mod icon_packs;
+mod icon_style;
+mod import;
+mod launch;
+mod layout;
+mod menu_bar;
+mod menu_model;
+mod pinned;
+mod plugins;
+mod rules;
+mod svg;
+#[cfg(test)]
+mod test_support;
+mod theme;
+mod widgets;
 mod windows;
 
 use app::*;
 
 /// Starts FerroDock and positions its dock window within the usable desktop area.
 ///
+/// `ferrodock diagnose` instead prints why each top-level window is or is not shown, and
+/// `ferrodock import` reads another dock's settings into `config.toml`.
+///
 /// # Examples
 ///
 /// ```ignore
 /// main();
 /// ```
 fn main() {
-    let mut work_area = RECT::default();
-    unsafe {
-        let _ = SystemParametersInfoW(
-            SPI_GETWORKAREA,
-            0,
-            Some(&mut work_area as *mut _ as *mut _),
-            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-        );
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        let result = match command.as_str() {
+            "diagnose" => diagnose::run(&args[1..]),
+            "import" => import::run(&args[1..]),
+            _ => Err(format!("unknown command `{command}`")),
+        };
+
+        if let Err(err) = result {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+        return;
     }
 
-    let hdc = unsafe { GetDC(None) };
-    let dpi = if hdc.is_invalid() {
-        96.0
-    } else {
-        let d = unsafe { GetDeviceCaps(hdc, LOGPIXELSX) } as f32;
-        let _ = unsafe { ReleaseDC(None, hdc) };
-        d
-    };
-
-    let scale_factor = (dpi / 96.0).max(1.0);
-
-    let work_left = (work_area.left as f32) / scale_factor;
-    let work_right = (work_area.right as f32) / scale_factor;
-    let work_bottom = (work_area.bottom as f32) / scale_factor;
-
-    let work_w = work_right - work_left;
-
-    let dock_width = (work_w * 0.5).min(750.0);
-    let dock_height = 80.0;
+    windows::enable_per_monitor_dpi_awareness();
 
-    let pos_x = work_left + (work_w - dock_width) / 2.0;
-    let pos_y = work_bottom - dock_height - 2.0;
+    // The first layout sizes the window so it opens already fitted to its icons
+    let dock = FerroDock::new();
+    let window = dock.layout.window;
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -62,15 +69,19 @@ fn main() {
             .with_always_on_top()
             .with_resizable(false)
             .with_maximize_button(false)
-            .with_inner_size([dock_width, dock_height])
-            .with_position([pos_x, pos_y]),
+            .with_inner_size(window.size())
+            .with_position(window.min),
         ..Default::default()
     };
 
     let _ = eframe::run_native(
         "FerroDock",
         options,
-        Box::new(|_cc| Box::new(FerroDock::new())),
+        Box::new(|cc| {
+            let mut dock = dock;
+            dock.restore_state(cc.storage);
+            Box::new(dock)
+        }),
     );
 }
 
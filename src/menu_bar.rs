@@ -0,0 +1,114 @@
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A wall-clock time in the user's time zone.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LocalTime {
+    pub year: u16,
+    /// Day of the week, with Sunday as 0.
+    pub weekday: u16,
+    pub day: u16,
+    /// Month of the year, with January as 1.
+    pub month: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+}
+
+/// Formats the menu bar clock, e.g. `"Sat 18 Oct 14:05"` or `"Sat 18 Oct 2:05 PM"`.
+///
+/// # Examples
+///
+/// ```
+/// let time = LocalTime { year: 2025, weekday: 6, day: 18, month: 10, hour: 14, minute: 5, second: 0 };
+/// assert_eq!(format_clock(&time, true), "Sat 18 Oct 14:05");
+/// ```
+pub fn format_clock(time: &LocalTime, twenty_four_hour: bool) -> String {
+    format!("{} {}", format_date(time), format_time(time, twenty_four_hour, false))
+}
+
+/// Formats the date, e.g. `"Sat 18 Oct"`.
+pub fn format_date(time: &LocalTime) -> String {
+    let weekday = WEEKDAYS.get(usize::from(time.weekday)).copied().unwrap_or("");
+    let month = MONTHS
+        .get(usize::from(time.month).wrapping_sub(1))
+        .copied()
+        .unwrap_or("");
+
+    format!("{weekday} {} {month}", time.day)
+}
+
+/// Formats the time of day, e.g. `"14:05"`, `"2:05 PM"` or, with `seconds`, `"14:05:09"`.
+pub fn format_time(time: &LocalTime, twenty_four_hour: bool, seconds: bool) -> String {
+    let seconds = if seconds {
+        format!(":{:02}", time.second)
+    } else {
+        String::new()
+    };
+
+    if twenty_four_hour {
+        format!("{:02}:{:02}{seconds}", time.hour, time.minute)
+    } else {
+        let suffix = if time.hour < 12 { "AM" } else { "PM" };
+        let hour = match time.hour % 12 {
+            0 => 12,
+            hour => hour,
+        };
+        format!("{hour}:{:02}{seconds} {suffix}", time.minute)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u16, minute: u16) -> LocalTime {
+        LocalTime {
+            year: 2025,
+            weekday: 6,
+            day: 18,
+            month: 10,
+            hour,
+            minute,
+            second: 0,
+        }
+    }
+
+    #[test]
+    fn formats_a_24_hour_clock() {
+        assert_eq!(format_clock(&time(14, 5), true), "Sat 18 Oct 14:05");
+        assert_eq!(format_clock(&time(0, 0), true), "Sat 18 Oct 00:00");
+    }
+
+    #[test]
+    fn formats_a_12_hour_clock() {
+        assert_eq!(format_clock(&time(14, 5), false), "Sat 18 Oct 2:05 PM");
+        assert_eq!(format_clock(&time(0, 30), false), "Sat 18 Oct 12:30 AM");
+        assert_eq!(format_clock(&time(12, 0), false), "Sat 18 Oct 12:00 PM");
+    }
+
+    #[test]
+    fn formats_the_time_with_seconds() {
+        let time = LocalTime {
+            second: 9,
+            ..time(14, 5)
+        };
+
+        assert_eq!(format_time(&time, true, true), "14:05:09");
+        assert_eq!(format_time(&time, false, true), "2:05:09 PM");
+        assert_eq!(format_time(&time, false, false), "2:05 PM");
+    }
+
+    #[test]
+    fn out_of_range_fields_do_not_panic() {
+        let time = LocalTime {
+            weekday: 9,
+            month: 0,
+            ..time(9, 0)
+        };
+
+        assert_eq!(format_clock(&time, true), " 18  09:00");
+    }
+}
@@ -0,0 +1,199 @@
+/// The menus of an application, independent of where they were read from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AppMenu {
+    /// The top-level menus, shown as titles in the menu bar.
+    pub items: Vec<MenuItem>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MenuItem {
+    pub label: String,
+    /// Keyboard shortcut shown beside the label, e.g. `"Ctrl+O"`.
+    pub shortcut: Option<String>,
+    pub enabled: bool,
+    pub checked: bool,
+    pub kind: MenuItemKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MenuItemKind {
+    Command(MenuCommand),
+    Submenu(Vec<MenuItem>),
+    Separator,
+}
+
+/// How a chosen menu item is carried out on the target window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuCommand {
+    /// A `WM_COMMAND` id from the window's classic menu.
+    Command(u32),
+    /// The nth top-level item found through UI Automation; invoking it opens the app's own menu.
+    Automation(usize),
+}
+
+impl MenuItem {
+    pub fn separator() -> Self {
+        Self {
+            label: String::new(),
+            shortcut: None,
+            enabled: false,
+            checked: false,
+            kind: MenuItemKind::Separator,
+        }
+    }
+}
+
+impl AppMenu {
+    /// The top-level menus shown as titles in the menu bar, with their index in `items`.
+    pub fn titles(&self) -> Vec<(usize, &MenuItem)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.kind != MenuItemKind::Separator && !item.label.is_empty())
+            .collect()
+    }
+}
+
+/// Splits a Win32 menu string such as `"Save &As...\tCtrl+Shift+S"` into its label and
+/// shortcut, dropping the `&` mnemonic markers (`&&` is a literal ampersand).
+///
+/// # Examples
+///
+/// ```
+/// let (label, shortcut) = parse_menu_label("&Open...\tCtrl+O");
+/// assert_eq!(label, "Open...");
+/// assert_eq!(shortcut.as_deref(), Some("Ctrl+O"));
+/// ```
+pub fn parse_menu_label(raw: &str) -> (String, Option<String>) {
+    let (text, shortcut) = match raw.split_once('\t') {
+        Some((text, shortcut)) => (text, Some(shortcut.trim().to_string())),
+        None => (raw, None),
+    };
+
+    let mut label = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            if chars.peek() == Some(&'&') {
+                label.push('&');
+                chars.next();
+            }
+        } else {
+            label.push(c);
+        }
+    }
+
+    (label, shortcut.filter(|s| !s.is_empty()))
+}
+
+/// Lists the commands under `items` in depth-first order.
+///
+/// Native popup menus identify the chosen entry by its position in this list plus one,
+/// since zero means the menu was dismissed.
+pub fn flatten_commands(items: &[MenuItem]) -> Vec<MenuCommand> {
+    let mut commands = Vec::new();
+    for item in items {
+        match &item.kind {
+            MenuItemKind::Command(command) => commands.push(*command),
+            MenuItemKind::Submenu(children) => commands.extend(flatten_commands(children)),
+            MenuItemKind::Separator => {}
+        }
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(label: &str, id: u32) -> MenuItem {
+        let (label, shortcut) = parse_menu_label(label);
+        MenuItem {
+            label,
+            shortcut,
+            enabled: true,
+            checked: false,
+            kind: MenuItemKind::Command(MenuCommand::Command(id)),
+        }
+    }
+
+    fn submenu(label: &str, children: Vec<MenuItem>) -> MenuItem {
+        MenuItem {
+            label: parse_menu_label(label).0,
+            shortcut: None,
+            enabled: true,
+            checked: false,
+            kind: MenuItemKind::Submenu(children),
+        }
+    }
+
+    /// The menus of classic Notepad.
+    fn notepad() -> AppMenu {
+        AppMenu {
+            items: vec![
+                submenu(
+                    "&File",
+                    vec![
+                        command("&New\tCtrl+N", 1),
+                        command("&Open...\tCtrl+O", 2),
+                        MenuItem::separator(),
+                        command("E&xit", 28),
+                    ],
+                ),
+                submenu(
+                    "&Edit",
+                    vec![
+                        command("&Undo\tCtrl+Z", 16),
+                        submenu("&Recent", vec![command("a.txt", 100), command("b.txt", 101)]),
+                    ],
+                ),
+                submenu("&Help", vec![command("&About Notepad", 65)]),
+            ],
+        }
+    }
+
+    fn title_labels(menu: &AppMenu) -> Vec<&str> {
+        menu.titles().into_iter().map(|(_, item)| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_labels_shortcuts_and_mnemonics() {
+        assert_eq!(parse_menu_label("&File"), ("File".to_string(), None));
+        assert_eq!(
+            parse_menu_label("Save &As...\tCtrl+Shift+S"),
+            ("Save As...".to_string(), Some("Ctrl+Shift+S".to_string()))
+        );
+        assert_eq!(parse_menu_label("Tom && Jerry"), ("Tom & Jerry".to_string(), None));
+        assert_eq!(parse_menu_label("Close\t"), ("Close".to_string(), None));
+    }
+
+    #[test]
+    fn titles_are_the_top_level_labels() {
+        assert_eq!(title_labels(&notepad()), vec!["File", "Edit", "Help"]);
+    }
+
+    #[test]
+    fn titles_skip_separators_and_unnamed_items() {
+        let mut menu = notepad();
+        menu.items.insert(1, MenuItem::separator());
+        menu.items.push(submenu("", Vec::new()));
+
+        assert_eq!(title_labels(&menu), vec!["File", "Edit", "Help"]);
+        assert_eq!(menu.titles()[1].0, 2);
+    }
+
+    #[test]
+    fn commands_are_flattened_depth_first() {
+        let menu = notepad();
+        let ids: Vec<MenuCommand> = flatten_commands(&menu.items);
+
+        assert_eq!(
+            ids,
+            [1, 2, 28, 16, 100, 101, 65].map(MenuCommand::Command).to_vec()
+        );
+        assert_eq!(
+            flatten_commands(std::slice::from_ref(&menu.items[1])),
+            [16, 100, 101].map(MenuCommand::Command).to_vec()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::WindowsAndMessaging::HICON;
+
+use crate::config::PinnedItem;
+use crate::windows::DockIcon;
+
+/// Puts the pinned applications first, in their pinned order, followed by the other running
+/// applications.
+///
+/// A running application takes its pin's place, path and name. Pinned applications that are
+/// not running get an item without windows, whose icon is read from the executable.
+pub fn merge_pinned(running: Vec<DockIcon>, pinned: &[PinnedItem]) -> Vec<DockIcon> {
+    let mut running = running;
+    let mut items: Vec<DockIcon> = pinned
+        .iter()
+        .map(|pin| {
+            let item = running
+                .iter()
+                .position(|item| same_path(&item.path, &pin.path))
+                .map(|index| running.remove(index));
+
+            match item {
+                Some(item) => DockIcon {
+                    path: pin.path.clone(),
+                    name: pin.name.clone().or(item.name),
+                    ..item
+                },
+                None => DockIcon {
+                    path: pin.path.clone(),
+                    name: pin.name.clone(),
+                    icon: None,
+                    aumid: None,
+                    class: None,
+                    hicon: HICON(0),
+                    hwnd: HWND(0),
+                    windows: Vec::new(),
+                },
+            }
+        })
+        .collect();
+
+    items.extend(running);
+    items
+}
+
+/// The pin of the application at `path`, if it is pinned.
+pub fn find_pin<'a>(pinned: &'a [PinnedItem], path: &str) -> Option<&'a PinnedItem> {
+    pinned.iter().find(|pin| same_path(&pin.path, path))
+}
+
+/// Windows paths are case-insensitive.
+fn same_path(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running(path: &str, hwnd: isize) -> DockIcon {
+        DockIcon {
+            path: path.to_string(),
+            name: None,
+            icon: None,
+            aumid: None,
+            class: None,
+            hicon: HICON(0),
+            hwnd: HWND(hwnd),
+            windows: vec![HWND(hwnd)],
+        }
+    }
+
+    fn pin(path: &str) -> PinnedItem {
+        PinnedItem {
+            path: path.to_string(),
+            ..PinnedItem::default()
+        }
+    }
+
+    #[test]
+    fn pins_come_first_in_their_order() {
+        let items = merge_pinned(
+            vec![running("C:\\a.exe", 1), running("C:\\b.exe", 2)],
+            &[pin("C:\\c.exe"), pin("C:\\b.exe")],
+        );
+
+        let paths: Vec<_> = items.iter().map(|item| item.path.as_str()).collect();
+        assert_eq!(paths, ["C:\\c.exe", "C:\\b.exe", "C:\\a.exe"]);
+        assert!(items[0].windows.is_empty());
+        assert_eq!(items[1].hwnd, HWND(2));
+    }
+
+    #[test]
+    fn running_apps_take_their_pins_path_and_name() {
+        let pinned = [PinnedItem {
+            name: Some("Editor".to_string()),
+            ..pin("C:\\Apps\\Editor.exe")
+        }];
+        let items = merge_pinned(vec![running("c:\\apps\\editor.exe", 5)], &pinned);
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, "C:\\Apps\\Editor.exe");
+        assert_eq!(items[0].name.as_deref(), Some("Editor"));
+        assert_eq!(items[0].windows, [HWND(5)]);
+        assert!(find_pin(&pinned, "C:\\APPS\\EDITOR.EXE").is_some());
+    }
+}
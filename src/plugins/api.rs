@@ -0,0 +1,362 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use wasmi::{Caller, Extern, Linker, StoreLimits};
+
+use crate::config::{PluginPermission, WidgetPlacement};
+
+/// The module name plugins import the host functions from.
+pub const API_MODULE: &str = "ferrodock";
+
+pub const OK: i32 = 0;
+pub const DENIED: i32 = -1;
+pub const INVALID: i32 = -2;
+
+/// The largest value a plugin may pass to the host in one call.
+const MAX_PAYLOAD: usize = 64 * 1024;
+
+/// The most of each kind of contribution one plugin may make.
+const MAX_CONTRIBUTIONS: usize = 32;
+
+/// An item a plugin added to the dock.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct PluginDockItem {
+    pub id: String,
+    pub label: String,
+    /// A PNG relative to the plugin's directory, which it must not leave. Items without one show
+    /// their label's initial.
+    #[serde(default)]
+    pub icon: Option<PathBuf>,
+}
+
+/// An entry a plugin added to every dock item's context menu.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct PluginMenuEntry {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Badge {
+    /// The executable path of the dock item.
+    item: String,
+    /// Up to a few characters; empty clears the badge.
+    text: String,
+}
+
+/// What a plugin widget shows. The plugin replaces it whenever it has something new to show.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct PluginWidgetContent {
+    pub id: String,
+    #[serde(default)]
+    pub placement: WidgetPlacement,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub lines: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct Removal {
+    id: String,
+}
+
+/// Everything a plugin has added through the API.
+#[derive(Debug, Default)]
+pub struct Contributions {
+    pub dock_items: Vec<PluginDockItem>,
+    pub menu_entries: Vec<PluginMenuEntry>,
+    /// Badge text by dock item path.
+    pub badges: BTreeMap<String, String>,
+    pub widgets: BTreeMap<String, PluginWidgetContent>,
+    /// The events the plugin subscribed to, by name.
+    pub subscriptions: BTreeSet<String>,
+}
+
+/// The state a plugin's store holds: what it may do and what it has done.
+pub struct HostState {
+    pub name: String,
+    pub permissions: Vec<PluginPermission>,
+    /// The configuration as JSON, read through `read_config`.
+    pub config: String,
+    pub contributions: Contributions,
+    pub limits: StoreLimits,
+}
+
+/// A host function that changes the plugin's contributions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ApiCall {
+    SetDockItem,
+    RemoveDockItem,
+    AddMenuEntry,
+    SetBadge,
+    SetWidget,
+    Subscribe,
+}
+
+impl ApiCall {
+    const ALL: [ApiCall; 6] = [
+        ApiCall::SetDockItem,
+        ApiCall::RemoveDockItem,
+        ApiCall::AddMenuEntry,
+        ApiCall::SetBadge,
+        ApiCall::SetWidget,
+        ApiCall::Subscribe,
+    ];
+
+    /// The name the plugin imports the function under.
+    fn name(self) -> &'static str {
+        match self {
+            ApiCall::SetDockItem => "set_dock_item",
+            ApiCall::RemoveDockItem => "remove_dock_item",
+            ApiCall::AddMenuEntry => "add_menu_entry",
+            ApiCall::SetBadge => "set_badge",
+            ApiCall::SetWidget => "set_widget",
+            ApiCall::Subscribe => "subscribe",
+        }
+    }
+
+    fn permission(self) -> PluginPermission {
+        match self {
+            ApiCall::SetDockItem | ApiCall::RemoveDockItem => PluginPermission::DockItems,
+            ApiCall::AddMenuEntry => PluginPermission::ContextMenu,
+            ApiCall::SetBadge => PluginPermission::Badges,
+            ApiCall::SetWidget => PluginPermission::Widgets,
+            ApiCall::Subscribe => PluginPermission::Events,
+        }
+    }
+}
+
+impl HostState {
+    /// Applies `call` with its JSON `payload` if the plugin holds the permission it needs,
+    /// returning the status code the plugin sees.
+    pub fn handle(&mut self, call: ApiCall, payload: &[u8]) -> i32 {
+        if !self.permissions.contains(&call.permission()) {
+            return DENIED;
+        }
+
+        let contributions = &mut self.contributions;
+        let applied = match call {
+            ApiCall::SetDockItem => serde_json::from_slice(payload).ok().and_then(|item: PluginDockItem| {
+                // The icon must stay inside the plugin's own directory
+                let outside = item.icon.as_ref().is_some_and(|icon| {
+                    icon.components()
+                        .any(|component| !matches!(component, std::path::Component::Normal(_)))
+                });
+                if outside {
+                    return None;
+                }
+                upsert(&mut contributions.dock_items, item, |item| &item.id)
+            }),
+            ApiCall::RemoveDockItem => serde_json::from_slice(payload).ok().map(|removal: Removal| {
+                contributions.dock_items.retain(|item| item.id != removal.id);
+            }),
+            ApiCall::AddMenuEntry => serde_json::from_slice(payload).ok().and_then(|entry: PluginMenuEntry| {
+                upsert(&mut contributions.menu_entries, entry, |entry| &entry.id)
+            }),
+            ApiCall::SetBadge => serde_json::from_slice(payload).ok().and_then(|badge: Badge| {
+                if badge.text.is_empty() {
+                    contributions.badges.remove(&badge.item);
+                    return Some(());
+                }
+                let text: String = badge.text.chars().take(4).collect();
+                let room = contributions.badges.len() < MAX_CONTRIBUTIONS
+                    || contributions.badges.contains_key(&badge.item);
+                room.then(|| {
+                    contributions.badges.insert(badge.item, text);
+                })
+            }),
+            ApiCall::SetWidget => serde_json::from_slice(payload).ok().and_then(|widget: PluginWidgetContent| {
+                let room = contributions.widgets.len() < MAX_CONTRIBUTIONS
+                    || contributions.widgets.contains_key(&widget.id);
+                room.then(|| {
+                    contributions.widgets.insert(widget.id.clone(), widget);
+                })
+            }),
+            ApiCall::Subscribe => serde_json::from_slice(payload).ok().map(|events: Vec<String>| {
+                contributions.subscriptions.extend(events);
+            }),
+        };
+
+        if applied.is_some() { OK } else { INVALID }
+    }
+}
+
+/// Replaces the element with the same id as `value`, or appends it if there is room.
+fn upsert<T>(list: &mut Vec<T>, value: T, id: impl Fn(&T) -> &String) -> Option<()> {
+    match list.iter().position(|existing| id(existing) == id(&value)) {
+        Some(index) => list[index] = value,
+        None if list.len() < MAX_CONTRIBUTIONS => list.push(value),
+        None => return None,
+    }
+    Some(())
+}
+
+/// Defines the functions plugins import from the `ferrodock` module.
+///
+/// Values cross the boundary as UTF-8 JSON in the plugin's memory, passed as a pointer and a
+/// length. Every function returns `OK`, `DENIED` when the plugin lacks the permission it
+/// needs, or `INVALID` when its input is malformed.
+pub fn define(linker: &mut Linker<HostState>) -> Result<(), wasmi::Error> {
+    for call in ApiCall::ALL {
+        linker.func_wrap(
+            API_MODULE,
+            call.name(),
+            move |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> i32 {
+                match read_guest(&caller, ptr, len) {
+                    Some(payload) => caller.data_mut().handle(call, &payload),
+                    None => INVALID,
+                }
+            },
+        )?;
+    }
+
+    linker.func_wrap(
+        API_MODULE,
+        "log",
+        |caller: Caller<'_, HostState>, ptr: i32, len: i32| -> i32 {
+            let Some(message) = read_guest(&caller, ptr, len) else {
+                return INVALID;
+            };
+            eprintln!("[{}] {}", caller.data().name, String::from_utf8_lossy(&message));
+            OK
+        },
+    )?;
+
+    // Returns the length of the config, or `DENIED`; the plugin then allocates that much
+    // and calls `read_config`
+    linker.func_wrap(API_MODULE, "config_len", |caller: Caller<'_, HostState>| -> i32 {
+        let state = caller.data();
+        if !state.permissions.contains(&PluginPermission::Config) {
+            return DENIED;
+        }
+        state.config.len() as i32
+    })?;
+
+    linker.func_wrap(
+        API_MODULE,
+        "read_config",
+        |mut caller: Caller<'_, HostState>, ptr: i32, capacity: i32| -> i32 {
+            if !caller.data().permissions.contains(&PluginPermission::Config) {
+                return DENIED;
+            }
+            let config = caller.data().config.clone();
+            if capacity < 0 || (capacity as usize) < config.len() {
+                return INVALID;
+            }
+            match write_guest(&mut caller, ptr, config.as_bytes()) {
+                true => config.len() as i32,
+                false => INVALID,
+            }
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Copies `len` bytes at `ptr` out of the plugin's memory.
+fn read_guest(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let len = usize::try_from(len).ok().filter(|len| *len <= MAX_PAYLOAD)?;
+    let memory = caller.get_export("memory").and_then(Extern::into_memory)?;
+
+    let mut buffer = vec![0; len];
+    memory.read(caller, usize::try_from(ptr).ok()?, &mut buffer).ok()?;
+    Some(buffer)
+}
+
+fn write_guest(caller: &mut Caller<'_, HostState>, ptr: i32, bytes: &[u8]) -> bool {
+    let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) else {
+        return false;
+    };
+    let Ok(ptr) = usize::try_from(ptr) else {
+        return false;
+    };
+    memory.write(caller, ptr, bytes).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(permissions: &[PluginPermission]) -> HostState {
+        HostState {
+            name: "test".to_string(),
+            permissions: permissions.to_vec(),
+            config: String::new(),
+            contributions: Contributions::default(),
+            limits: StoreLimits::default(),
+        }
+    }
+
+    #[test]
+    fn calls_need_their_permission() {
+        let mut state = state(&[PluginPermission::Badges]);
+
+        let badge = br#"{"item": "C:\\Windows\\notepad.exe", "text": "3"}"#;
+        assert_eq!(state.handle(ApiCall::SetBadge, badge), OK);
+        assert_eq!(state.handle(ApiCall::SetDockItem, br#"{"id": "a", "label": "A"}"#), DENIED);
+
+        assert_eq!(state.contributions.badges["C:\\Windows\\notepad.exe"], "3");
+        assert!(state.contributions.dock_items.is_empty());
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let mut state = state(&[PluginPermission::DockItems]);
+
+        assert_eq!(state.handle(ApiCall::SetDockItem, b"not json"), INVALID);
+        assert_eq!(state.handle(ApiCall::SetDockItem, br#"{"id": "a"}"#), INVALID);
+        assert!(state.contributions.dock_items.is_empty());
+    }
+
+    #[test]
+    fn dock_item_icons_must_stay_in_the_plugin_directory() {
+        let mut state = state(&[PluginPermission::DockItems]);
+
+        for icon in [r#""../secret.png""#, r#""/icon.png""#, r#""icons/../../secret.png""#] {
+            let payload = format!(r#"{{"id": "a", "label": "A", "icon": {icon}}}"#);
+            assert_eq!(state.handle(ApiCall::SetDockItem, payload.as_bytes()), INVALID, "{icon}");
+        }
+        assert!(state.contributions.dock_items.is_empty());
+
+        let payload = br#"{"id": "a", "label": "A", "icon": "icons/a.png"}"#;
+        assert_eq!(state.handle(ApiCall::SetDockItem, payload), OK);
+    }
+
+    #[test]
+    fn dock_items_are_replaced_by_id_and_removed() {
+        let mut state = state(&[PluginPermission::DockItems]);
+
+        state.handle(ApiCall::SetDockItem, br#"{"id": "timer", "label": "25:00"}"#);
+        state.handle(ApiCall::SetDockItem, br#"{"id": "timer", "label": "24:59"}"#);
+        assert_eq!(state.contributions.dock_items.len(), 1);
+        assert_eq!(state.contributions.dock_items[0].label, "24:59");
+
+        assert_eq!(state.handle(ApiCall::RemoveDockItem, br#"{"id": "timer"}"#), OK);
+        assert!(state.contributions.dock_items.is_empty());
+    }
+
+    #[test]
+    fn contributions_are_capped() {
+        let mut state = state(&[PluginPermission::ContextMenu]);
+
+        for i in 0..MAX_CONTRIBUTIONS {
+            let entry = format!(r#"{{"id": "{i}", "label": "Entry"}}"#);
+            assert_eq!(state.handle(ApiCall::AddMenuEntry, entry.as_bytes()), OK);
+        }
+
+        assert_eq!(state.handle(ApiCall::AddMenuEntry, br#"{"id": "more", "label": "More"}"#), INVALID);
+        assert_eq!(state.handle(ApiCall::AddMenuEntry, br#"{"id": "0", "label": "First"}"#), OK);
+    }
+
+    #[test]
+    fn empty_badge_text_clears_the_badge() {
+        let mut state = state(&[PluginPermission::Badges]);
+
+        state.handle(ApiCall::SetBadge, br#"{"item": "a.exe", "text": "123456"}"#);
+        assert_eq!(state.contributions.badges["a.exe"], "1234");
+
+        state.handle(ApiCall::SetBadge, br#"{"item": "a.exe", "text": ""}"#);
+        assert!(state.contributions.badges.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::PluginPermission;
+
+const PLUGINS_DIR_NAME: &str = "plugins";
+pub const MANIFEST_FILE_NAME: &str = "plugin.toml";
+
+/// A plugin's `plugin.toml`, which names it and declares what it needs.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The name the plugin is approved under in the config. Letters, digits, `-` and `_`.
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// The WebAssembly module, relative to the plugin's directory.
+    #[serde(default = "default_module")]
+    pub module: PathBuf,
+    #[serde(default)]
+    pub permissions: Vec<PluginPermission>,
+}
+
+fn default_module() -> PathBuf {
+    PathBuf::from("plugin.wasm")
+}
+
+impl Manifest {
+    /// Parses and checks a `plugin.toml`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let manifest = Manifest::parse("name = \"pomodoro\"\npermissions = [\"badges\"]").unwrap();
+    /// assert_eq!(manifest.module, PathBuf::from("plugin.wasm"));
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let manifest: Manifest = toml::from_str(text).map_err(|err| err.message().to_string())?;
+
+        let valid_name = !manifest.name.is_empty()
+            && manifest
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(format!(
+                "`{}` is not a valid plugin name; use letters, digits, `-` and `_`",
+                manifest.name
+            ));
+        }
+
+        // The module must stay inside the plugin's own directory
+        if manifest.module.is_absolute()
+            || manifest
+                .module
+                .components()
+                .any(|component| !matches!(component, std::path::Component::Normal(_)))
+        {
+            return Err(format!("module `{}` must be inside the plugin directory", manifest.module.display()));
+        }
+
+        Ok(manifest)
+    }
+
+    /// The declared permissions that `approved` does not grant this plugin, in declaration
+    /// order.
+    pub fn unapproved_permissions(&self, approved: &BTreeMap<String, Vec<PluginPermission>>) -> Vec<PluginPermission> {
+        let granted = approved.get(&self.name).map(Vec::as_slice).unwrap_or_default();
+        self.permissions
+            .iter()
+            .filter(|permission| !granted.contains(permission))
+            .copied()
+            .collect()
+    }
+
+    /// The line to add under `[plugins.approved]` to grant every declared permission, e.g.
+    /// `pomodoro = ["badges", "events"]`.
+    pub fn approval_line(&self) -> String {
+        let permissions: Vec<String> = self
+            .permissions
+            .iter()
+            .map(|permission| format!("\"{}\"", permission_name(*permission)))
+            .collect();
+        format!("{} = [{}]", self.name, permissions.join(", "))
+    }
+}
+
+/// The name of `permission` as written in the config and manifests.
+pub fn permission_name(permission: PluginPermission) -> &'static str {
+    match permission {
+        PluginPermission::DockItems => "dock_items",
+        PluginPermission::ContextMenu => "context_menu",
+        PluginPermission::Badges => "badges",
+        PluginPermission::Widgets => "widgets",
+        PluginPermission::Events => "events",
+        PluginPermission::Config => "config",
+    }
+}
+
+/// The directory plugins are loaded from: `plugins` next to the executable, falling back to
+/// the working directory, like `config.toml`.
+pub fn plugins_dir() -> Option<PathBuf> {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(PLUGINS_DIR_NAME)));
+
+    beside_exe
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(PLUGINS_DIR_NAME)))
+        .find(|path| path.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_declares_name_module_and_permissions() {
+        let manifest = Manifest::parse(
+            r#"
+            name = "pomodoro"
+            version = "1.2.0"
+            module = "build/pomodoro.wasm"
+            permissions = ["badges", "events"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.module, PathBuf::from("build/pomodoro.wasm"));
+        assert_eq!(manifest.permissions, [PluginPermission::Badges, PluginPermission::Events]);
+        assert_eq!(manifest.approval_line(), r#"pomodoro = ["badges", "events"]"#);
+    }
+
+    #[test]
+    fn invalid_names_and_escaping_modules_are_rejected() {
+        assert!(Manifest::parse("name = \"\"").is_err());
+        assert!(Manifest::parse("name = \"my plugin\"").is_err());
+        assert!(Manifest::parse("name = \"a\"\nmodule = \"../other/plugin.wasm\"").is_err());
+        assert!(Manifest::parse("name = \"a\"\npermissions = [\"files\"]").is_err());
+    }
+
+    #[test]
+    fn every_declared_permission_needs_approval() {
+        let manifest = Manifest::parse("name = \"pomodoro\"\npermissions = [\"badges\", \"events\"]").unwrap();
+        let mut approved = BTreeMap::new();
+
+        assert_eq!(
+            manifest.unapproved_permissions(&approved),
+            [PluginPermission::Badges, PluginPermission::Events]
+        );
+
+        approved.insert("pomodoro".to_string(), vec![PluginPermission::Events]);
+        assert_eq!(manifest.unapproved_permissions(&approved), [PluginPermission::Badges]);
+
+        approved.insert(
+            "pomodoro".to_string(),
+            vec![PluginPermission::Badges, PluginPermission::Events],
+        );
+        assert!(manifest.unapproved_permissions(&approved).is_empty());
+    }
+}
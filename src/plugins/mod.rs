@@ -0,0 +1,496 @@
+mod api;
+mod manifest;
+mod widget;
+
+pub use api::{PluginDockItem, PluginMenuEntry};
+pub use manifest::Manifest;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use wasmi::{Engine, Instance, Linker, Module, Store, StoreLimitsBuilder};
+
+use crate::config::{Config, PluginPermission, WidgetPlacement};
+use crate::widgets::Widget;
+use api::{Contributions, HostState};
+use manifest::{MANIFEST_FILE_NAME, plugins_dir};
+use widget::{PluginWidget, WidgetView};
+
+/// Fuel for one call into a plugin. Each instruction costs about one unit, so a plugin stuck
+/// in a loop is stopped after a few milliseconds instead of freezing the dock.
+const FUEL_PER_CALL: u64 = 5_000_000;
+
+/// The most linear memory a plugin may grow to, in bytes.
+const MEMORY_LIMIT: usize = 16 << 20;
+
+/// Something that happened in the dock, delivered to plugins as JSON through `on_event`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginEvent {
+    /// A window came to the foreground. `item` is the path of its dock item, if it has one.
+    WindowActivated { item: Option<String> },
+    WindowCreated,
+    WindowDestroyed,
+    /// The dock's items changed. `items` lists their paths in dock order.
+    DockChanged { items: Vec<String> },
+    /// Sent about once a second.
+    Tick,
+    /// One of the plugin's own dock items was clicked.
+    DockItemClicked { id: String },
+    /// One of the plugin's context-menu entries was chosen on the dock item at `item`.
+    MenuEntryChosen { id: String, item: String },
+}
+
+impl PluginEvent {
+    /// The name plugins subscribe to the event by. Events about a plugin's own contributions
+    /// have none; they go to that plugin alone, whether it subscribed or not.
+    fn subscription(&self) -> Option<&'static str> {
+        match self {
+            PluginEvent::WindowActivated { .. } => Some("window_activated"),
+            PluginEvent::WindowCreated => Some("window_created"),
+            PluginEvent::WindowDestroyed => Some("window_destroyed"),
+            PluginEvent::DockChanged { .. } => Some("dock_changed"),
+            PluginEvent::Tick => Some("tick"),
+            PluginEvent::DockItemClicked { .. } | PluginEvent::MenuEntryChosen { .. } => None,
+        }
+    }
+}
+
+/// A loaded plugin and its sandbox.
+pub struct Plugin {
+    pub manifest: Manifest,
+    /// The plugin's directory, which the icons it names are relative to.
+    pub dir: PathBuf,
+    /// Why the plugin was stopped. A stopped plugin is never called again.
+    pub error: Option<String>,
+    store: Store<HostState>,
+    instance: Instance,
+    /// The views of the widgets the plugin created, by the plugin's id for them.
+    widget_views: HashMap<String, Rc<RefCell<WidgetView>>>,
+}
+
+impl Plugin {
+    /// Loads the plugin in `dir` if the config approves every permission it declares.
+    fn load(dir: &Path, config: &Config) -> Result<Self, String> {
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+        let text = std::fs::read_to_string(&manifest_path)
+            .map_err(|err| format!("failed to read {}: {err}", manifest_path.display()))?;
+        let manifest = Manifest::parse(&text)?;
+
+        if !manifest.unapproved_permissions(&config.plugins.approved).is_empty() {
+            return Err(format!(
+                "`{}` needs approval; add `{}` under [plugins.approved] in config.toml",
+                manifest.name,
+                manifest.approval_line()
+            ));
+        }
+
+        let module_path = dir.join(&manifest.module);
+        let wasm = std::fs::read(&module_path)
+            .map_err(|err| format!("failed to read {}: {err}", module_path.display()))?;
+
+        Self::instantiate(manifest, dir.to_path_buf(), &wasm, config)
+    }
+
+    /// Compiles and starts `wasm` with the permissions the manifest declares, then calls its
+    /// `on_load` export if it has one.
+    fn instantiate(manifest: Manifest, dir: PathBuf, wasm: &[u8], config: &Config) -> Result<Self, String> {
+        let mut engine_config = wasmi::Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, wasm).map_err(|err| err.to_string())?;
+
+        // Plugins get a copy, so they cannot change the dock's config
+        let config = if manifest.permissions.contains(&PluginPermission::Config) {
+            serde_json::to_string(config).map_err(|err| err.to_string())?
+        } else {
+            String::new()
+        };
+        let state = HostState {
+            name: manifest.name.clone(),
+            permissions: manifest.permissions.clone(),
+            config,
+            contributions: Contributions::default(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(MEMORY_LIMIT)
+                .instances(1)
+                .memories(1)
+                .build(),
+        };
+
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL).map_err(|err| err.to_string())?;
+
+        let mut linker = Linker::new(&engine);
+        api::define(&mut linker).map_err(|err| err.to_string())?;
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|err| err.to_string())?;
+
+        let mut plugin = Self {
+            manifest,
+            dir,
+            error: None,
+            store,
+            instance,
+            widget_views: HashMap::new(),
+        };
+        if let Ok(on_load) = plugin.instance.get_typed_func::<(), ()>(&plugin.store, "on_load") {
+            plugin.store.set_fuel(FUEL_PER_CALL).map_err(|err| err.to_string())?;
+            on_load.call(&mut plugin.store, ()).map_err(|err| err.to_string())?;
+        }
+
+        Ok(plugin)
+    }
+
+    pub fn contributions(&self) -> &Contributions {
+        &self.store.data().contributions
+    }
+
+    fn is_subscribed(&self, event: &PluginEvent) -> bool {
+        match event.subscription() {
+            Some(name) => self.contributions().subscriptions.contains(name),
+            None => true,
+        }
+    }
+
+    /// Passes `event` to the plugin's `on_event` export, stopping the plugin if it fails.
+    fn deliver(&mut self, event: &PluginEvent) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(err) = self.try_deliver(event) {
+            self.stop(err);
+        }
+    }
+
+    fn try_deliver(&mut self, event: &PluginEvent) -> Result<(), String> {
+        let Ok(on_event) = self.instance.get_typed_func::<(i32, i32), ()>(&self.store, "on_event") else {
+            return Ok(());
+        };
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&self.store, "alloc")
+            .map_err(|_| "`on_event` needs an `alloc` export".to_string())?;
+        let memory = self
+            .instance
+            .get_memory(&self.store, "memory")
+            .ok_or_else(|| "`on_event` needs a `memory` export".to_string())?;
+
+        let json = serde_json::to_vec(event).map_err(|err| err.to_string())?;
+        let len = json.len() as i32;
+
+        self.store.set_fuel(FUEL_PER_CALL).map_err(|err| err.to_string())?;
+        let ptr = alloc.call(&mut self.store, len).map_err(|err| err.to_string())?;
+        let offset = usize::try_from(ptr).map_err(|_| format!("`alloc` returned {ptr}"))?;
+        memory
+            .write(&mut self.store, offset, &json)
+            .map_err(|err| err.to_string())?;
+        on_event
+            .call(&mut self.store, (ptr, len))
+            .map_err(|err| err.to_string())
+    }
+
+    /// Disables the plugin and takes back everything it added.
+    fn stop(&mut self, error: String) {
+        eprintln!("Stopped plugin `{}`: {error}", self.manifest.name);
+
+        let contributions = &mut self.store.data_mut().contributions;
+        contributions.dock_items.clear();
+        contributions.menu_entries.clear();
+        contributions.badges.clear();
+        contributions.subscriptions.clear();
+
+        for view in self.widget_views.values() {
+            view.borrow_mut().stopped = Some(error.clone());
+        }
+        self.error = Some(error);
+    }
+
+    /// Copies the plugin's widget content into its views, returning widgets for content the
+    /// plugin has not shown before.
+    fn sync_widgets(&mut self) -> Vec<(String, WidgetPlacement, Box<dyn Widget>)> {
+        let mut created = Vec::new();
+
+        for (id, content) in &self.store.data().contributions.widgets {
+            match self.widget_views.get(id) {
+                Some(view) => {
+                    if view.borrow().content != *content {
+                        view.borrow_mut().content = content.clone();
+                    }
+                }
+                None => {
+                    let view = Rc::new(RefCell::new(WidgetView {
+                        content: content.clone(),
+                        stopped: None,
+                    }));
+                    self.widget_views.insert(id.clone(), view.clone());
+                    let widget_id = format!("{}/{id}", self.manifest.name);
+                    created.push((widget_id, content.placement, Box::new(PluginWidget { view }) as Box<dyn Widget>));
+                }
+            }
+        }
+
+        created
+    }
+}
+
+/// The loaded plugins, and the widgets they created that the dock has yet to show.
+#[derive(Default)]
+pub struct PluginHost {
+    pub plugins: Vec<Plugin>,
+    new_widgets: Vec<(String, WidgetPlacement, Box<dyn Widget>)>,
+}
+
+impl PluginHost {
+    /// Loads every approved plugin from the plugins directory. Plugins that fail to load are
+    /// reported and skipped.
+    pub fn load(config: &Config) -> Self {
+        let mut host = Self::default();
+        if !config.plugins.enabled {
+            return host;
+        }
+        let Some(dir) = plugins_dir() else {
+            return host;
+        };
+
+        let mut plugin_dirs: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| path.join(MANIFEST_FILE_NAME).is_file())
+                .collect(),
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", dir.display());
+                return host;
+            }
+        };
+        plugin_dirs.sort();
+
+        for plugin_dir in plugin_dirs {
+            match Plugin::load(&plugin_dir, config) {
+                Ok(plugin) if host.plugins.iter().any(|p| p.manifest.name == plugin.manifest.name) => {
+                    eprintln!("Ignoring {}: another plugin is named `{}`", plugin_dir.display(), plugin.manifest.name);
+                }
+                Ok(plugin) => host.plugins.push(plugin),
+                Err(err) => eprintln!("Not loading plugin {}: {err}", plugin_dir.display()),
+            }
+        }
+
+        host.sync_widgets();
+        host
+    }
+
+    /// Delivers `event` to every plugin subscribed to it.
+    pub fn dispatch(&mut self, event: &PluginEvent) {
+        for plugin in &mut self.plugins {
+            if plugin.is_subscribed(event) {
+                plugin.deliver(event);
+            }
+        }
+        self.sync_widgets();
+    }
+
+    /// Tells the plugin at `index` that its dock item `id` was clicked.
+    pub fn click_dock_item(&mut self, index: usize, id: &str) {
+        self.deliver_to(index, &PluginEvent::DockItemClicked { id: id.to_string() });
+    }
+
+    /// Tells the plugin at `index` that its menu entry `id` was chosen for the item at `path`.
+    pub fn choose_menu_entry(&mut self, index: usize, id: &str, path: &str) {
+        let event = PluginEvent::MenuEntryChosen {
+            id: id.to_string(),
+            item: path.to_string(),
+        };
+        self.deliver_to(index, &event);
+    }
+
+    fn deliver_to(&mut self, index: usize, event: &PluginEvent) {
+        if let Some(plugin) = self.plugins.get_mut(index) {
+            plugin.deliver(event);
+        }
+        self.sync_widgets();
+    }
+
+    /// Whether any plugin is subscribed to the event named `name`.
+    pub fn wants(&self, name: &str) -> bool {
+        self.plugins
+            .iter()
+            .any(|plugin| plugin.contributions().subscriptions.contains(name))
+    }
+
+    /// The dock items of every plugin, with the index of the plugin that added each.
+    pub fn dock_items(&self) -> impl Iterator<Item = (usize, &PluginDockItem)> {
+        self.plugins.iter().enumerate().flat_map(|(index, plugin)| {
+            plugin.contributions().dock_items.iter().map(move |item| (index, item))
+        })
+    }
+
+    /// The context-menu entries of every plugin, with the index of the plugin that added each.
+    pub fn menu_entries(&self) -> impl Iterator<Item = (usize, &PluginMenuEntry)> {
+        self.plugins.iter().enumerate().flat_map(|(index, plugin)| {
+            plugin.contributions().menu_entries.iter().map(move |entry| (index, entry))
+        })
+    }
+
+    /// The badge shown on the dock item at `path`, from the first plugin that set one.
+    pub fn badge(&self, path: &str) -> Option<&str> {
+        self.plugins
+            .iter()
+            .find_map(|plugin| plugin.contributions().badges.get(path))
+            .map(String::as_str)
+    }
+
+    /// Takes the widgets plugins created since the last call, as ids, placements and widgets
+    /// to add to the widget host.
+    pub fn take_new_widgets(&mut self) -> Vec<(String, WidgetPlacement, Box<dyn Widget>)> {
+        std::mem::take(&mut self.new_widgets)
+    }
+
+    fn sync_widgets(&mut self) {
+        for plugin in &mut self.plugins {
+            self.new_widgets.extend(plugin.sync_widgets());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Imports the API, exports memory and a bump allocator, and stores `data` at offset 0.
+    fn module(data: &str, body: &str) -> String {
+        format!(
+            r#"(module
+                (import "ferrodock" "subscribe" (func $subscribe (param i32 i32) (result i32)))
+                (import "ferrodock" "set_badge" (func $set_badge (param i32 i32) (result i32)))
+                (import "ferrodock" "set_dock_item" (func $set_dock_item (param i32 i32) (result i32)))
+                (import "ferrodock" "set_widget" (func $set_widget (param i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{data}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 4096))
+                {body}
+            )"#
+        )
+    }
+
+    fn plugin(permissions: &[PluginPermission], wat: &str) -> Result<Plugin, String> {
+        let manifest = Manifest {
+            name: "test".to_string(),
+            version: String::new(),
+            description: String::new(),
+            module: PathBuf::from("plugin.wasm"),
+            permissions: permissions.to_vec(),
+        };
+        let wasm = wat::parse_str(wat).unwrap();
+        Plugin::instantiate(manifest, PathBuf::new(), &wasm, &Config::default())
+    }
+
+    fn host(plugins: Vec<Plugin>) -> PluginHost {
+        PluginHost {
+            plugins,
+            new_widgets: Vec::new(),
+        }
+    }
+
+    // [\"tick\"] at 0 (8 bytes), {\"id\":\"t\",\"label\":\"T\"} at 8 (22 bytes)
+    const TICK_AND_ITEM: &str = r#"[\"tick\"]{\"id\":\"t\",\"label\":\"T\"}"#;
+
+    #[test]
+    fn events_reach_subscribed_plugins_only() {
+        let wat = module(
+            TICK_AND_ITEM,
+            r#"(func (export "on_load") (drop (call $subscribe (i32.const 0) (i32.const 8))))
+               (func (export "on_event") (param i32 i32)
+                 (drop (call $set_dock_item (i32.const 8) (i32.const 22))))"#,
+        );
+        let mut host = host(vec![plugin(&[PluginPermission::Events, PluginPermission::DockItems], &wat).unwrap()]);
+
+        host.dispatch(&PluginEvent::WindowCreated);
+        assert_eq!(host.dock_items().count(), 0);
+
+        assert!(host.wants("tick"));
+        host.dispatch(&PluginEvent::Tick);
+        let items: Vec<_> = host.dock_items().collect();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].1.label, "T");
+    }
+
+    #[test]
+    fn undeclared_permissions_are_denied() {
+        // Traps unless setting a badge is refused
+        let wat = module(
+            r#"{\"item\":\"a.exe\",\"text\":\"1\"}"#,
+            r#"(func (export "on_load")
+                 (if (i32.ne (call $set_badge (i32.const 0) (i32.const 27)) (i32.const -1))
+                   (then unreachable)))"#,
+        );
+
+        let plugin = plugin(&[PluginPermission::Events], &wat).unwrap();
+        assert!(plugin.contributions().badges.is_empty());
+    }
+
+    #[test]
+    fn runaway_plugins_are_stopped_and_lose_their_contributions() {
+        let wat = module(
+            r#"{\"item\":\"a.exe\",\"text\":\"1\"}"#,
+            r#"(func (export "on_load") (drop (call $set_badge (i32.const 0) (i32.const 27))))
+               (func (export "on_event") (param i32 i32) (loop $forever (br $forever)))"#,
+        );
+        let mut host = host(vec![plugin(&[PluginPermission::Badges], &wat).unwrap()]);
+        assert_eq!(host.badge("a.exe"), Some("1"));
+
+        host.click_dock_item(0, "anything");
+
+        assert!(host.plugins[0].error.is_some());
+        assert_eq!(host.badge("a.exe"), None);
+
+        // Stopped plugins are not called again
+        host.click_dock_item(0, "anything");
+    }
+
+    #[test]
+    fn plugins_that_fail_to_start_are_not_loaded() {
+        let trapping = module("", r#"(func (export "on_load") unreachable)"#);
+        let greedy = module("", r#"(func (export "on_load") (drop (memory.grow (i32.const 1000))) (if (i32.gt_u (memory.size) (i32.const 256)) (then unreachable)))"#);
+
+        assert!(plugin(&[], &trapping).is_err());
+        assert!(plugin(&[], "(module (import \"env\" \"open\" (func)))").is_err());
+        assert!(plugin(&[], &greedy).is_ok(), "memory beyond the limit must not be granted");
+    }
+
+    #[test]
+    fn new_widgets_are_handed_to_the_dock_once() {
+        let wat = module(
+            r#"{\"id\":\"w\",\"title\":\"Focus\",\"lines\":[\"25:00\"]}"#,
+            r#"(func (export "on_load") (drop (call $set_widget (i32.const 0) (i32.const 44))))"#,
+        );
+        let mut host = host(vec![plugin(&[PluginPermission::Widgets], &wat).unwrap()]);
+        host.sync_widgets();
+
+        let widgets = host.take_new_widgets();
+        assert_eq!(widgets.len(), 1);
+        assert_eq!(widgets[0].0, "test/w");
+        assert_eq!(widgets[0].1, WidgetPlacement::Desktop);
+
+        host.dispatch(&PluginEvent::Tick);
+        assert!(host.take_new_widgets().is_empty());
+    }
+
+    #[test]
+    fn events_are_tagged_by_type() {
+        let event = PluginEvent::MenuEntryChosen {
+            id: "pin".to_string(),
+            item: "a.exe".to_string(),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"type":"menu_entry_chosen","id":"pin","item":"a.exe"}"#
+        );
+        assert_eq!(serde_json::to_string(&PluginEvent::Tick).unwrap(), r#"{"type":"tick"}"#);
+    }
+}
@@ -0,0 +1,66 @@
+use eframe::egui::{self, Vec2, vec2};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::config::WidgetPlacement;
+use crate::plugins::api::PluginWidgetContent;
+use crate::widgets::{Widget, WidgetStyle};
+
+/// What a plugin widget draws, shared between the plugin that fills it and the widget host.
+#[derive(Debug, Default)]
+pub struct WidgetView {
+    pub content: PluginWidgetContent,
+    /// Why the plugin that owns the widget was stopped.
+    pub stopped: Option<String>,
+}
+
+/// A widget whose content a plugin pushes through `set_widget`.
+pub struct PluginWidget {
+    pub view: Rc<RefCell<WidgetView>>,
+}
+
+impl Widget for PluginWidget {
+    fn default_size(&self) -> Vec2 {
+        let lines = self.view.borrow().content.lines.len().max(1);
+        vec2(200.0, 34.0 + 18.0 * lines as f32)
+    }
+
+    fn update_interval(&self) -> Duration {
+        // The plugin pushes new content instead of being polled
+        Duration::from_secs(3600)
+    }
+
+    fn update(&mut self) {}
+
+    fn ui(&self, ui: &mut egui::Ui, style: &WidgetStyle) {
+        let view = self.view.borrow();
+        let content = &view.content;
+
+        if style.placement != WidgetPlacement::Desktop {
+            let text = match &view.stopped {
+                Some(_) => "Stopped",
+                None => content.lines.first().unwrap_or(&content.title),
+            };
+            ui.centered_and_justified(|ui| {
+                ui.label(egui::RichText::new(text).small().color(style.text_color));
+            });
+            return;
+        }
+
+        if !content.title.is_empty() {
+            ui.label(egui::RichText::new(&content.title).strong().color(style.text_color));
+        }
+        match &view.stopped {
+            Some(error) => {
+                ui.label(egui::RichText::new("The plugin was stopped").color(style.warning_color))
+                    .on_hover_text(error);
+            }
+            None => {
+                for line in &content.lines {
+                    ui.label(egui::RichText::new(line).color(style.text_color));
+                }
+            }
+        }
+    }
+}
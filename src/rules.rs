@@ -0,0 +1,432 @@
+use std::fmt;
+
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+
+use crate::config::{RuleAction, WindowRule};
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// What is known about a top-level window when rules are evaluated.
+#[derive(Clone, Debug, Default)]
+pub struct WindowFacts {
+    pub exe_path: String,
+    pub class: String,
+    pub title: String,
+    pub aumid: Option<String>,
+    pub tool_window: bool,
+    pub app_window: bool,
+    pub owned: bool,
+    pub cloaked: bool,
+}
+
+/// The outcome of evaluating a `RuleSet` against a window.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RuleDecision {
+    pub included: bool,
+    /// Index of the include/exclude rule that decided `included`, or `None` when no rule
+    /// matched and the window was included by default.
+    pub deciding_rule: Option<usize>,
+    pub group_under: Option<String>,
+    pub rename: Option<String>,
+    pub icon: Option<String>,
+}
+
+struct CompiledRule {
+    rule: WindowRule,
+    exe: Option<Pattern>,
+    title: Option<Regex>,
+}
+
+/// Window rules from the configuration with their patterns compiled once.
+pub struct RuleSet {
+    rules: Vec<Option<CompiledRule>>,
+}
+
+impl RuleSet {
+    /// Compiles `rules`. Rules with an invalid glob or regex are reported and never match.
+    pub fn new(rules: &[WindowRule]) -> Self {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| match compile(rule) {
+                Ok(compiled) => Some(compiled),
+                Err(err) => {
+                    eprintln!("Ignoring window rule #{index}: {err}");
+                    None
+                }
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    /// Evaluates the rules in order.
+    ///
+    /// The first matching `include` or `exclude` rule decides whether the window is shown, and
+    /// the first matching `group_under`, `rename` and `icon` rules set the respective overrides.
+    /// Windows no include/exclude rule matches are shown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let rules = RuleSet::new(&default_window_rules());
+    /// let facts = WindowFacts { tool_window: true, ..Default::default() };
+    /// assert!(!rules.evaluate(&facts).included);
+    /// ```
+    pub fn evaluate(&self, facts: &WindowFacts) -> RuleDecision {
+        let mut decision = RuleDecision {
+            included: true,
+            ..Default::default()
+        };
+
+        for (index, compiled) in self.rules.iter().enumerate() {
+            let Some(compiled) = compiled else {
+                continue;
+            };
+            if !compiled.matches(facts) {
+                continue;
+            }
+
+            match &compiled.rule.action {
+                RuleAction::Include | RuleAction::Exclude if decision.deciding_rule.is_none() => {
+                    decision.included = compiled.rule.action == RuleAction::Include;
+                    decision.deciding_rule = Some(index);
+                }
+                RuleAction::GroupUnder(path) if decision.group_under.is_none() => {
+                    decision.group_under = Some(path.clone());
+                }
+                RuleAction::Rename(name) if decision.rename.is_none() => {
+                    decision.rename = Some(name.clone());
+                }
+                RuleAction::Icon(icon) if decision.icon.is_none() => {
+                    decision.icon = Some(icon.clone());
+                }
+                _ => {}
+            }
+        }
+
+        decision
+    }
+
+    /// Returns the exclude rule that hides the window regardless of its process, if any.
+    ///
+    /// `facts` need not have `exe_path` and `aumid` filled in. Rules are walked in the same order
+    /// as by `evaluate`, stopping at the first include/exclude rule whose outcome depends on the
+    /// process, so a window this returns `Some` for is also excluded by `evaluate`.
+    pub fn excluded_without_process(&self, facts: &WindowFacts) -> Option<usize> {
+        for (index, compiled) in self.rules.iter().enumerate() {
+            let Some(compiled) = compiled else {
+                continue;
+            };
+            if !matches!(compiled.rule.action, RuleAction::Include | RuleAction::Exclude)
+                || !compiled.matches_window(facts)
+            {
+                continue;
+            }
+            if compiled.needs_process() {
+                return None;
+            }
+            return (compiled.rule.action == RuleAction::Exclude).then_some(index);
+        }
+
+        None
+    }
+}
+
+fn compile(rule: &WindowRule) -> Result<CompiledRule, String> {
+    let exe = rule
+        .exe
+        .as_deref()
+        .map(Pattern::new)
+        .transpose()
+        .map_err(|err| format!("invalid exe glob: {err}"))?;
+    let title = rule
+        .title
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|err| format!("invalid title regex: {err}"))?;
+
+    Ok(CompiledRule {
+        rule: rule.clone(),
+        exe,
+        title,
+    })
+}
+
+impl CompiledRule {
+    fn matches(&self, facts: &WindowFacts) -> bool {
+        self.matches_window(facts)
+            && self
+                .exe
+                .as_ref()
+                .is_none_or(|p| p.matches_with(&facts.exe_path, GLOB_OPTIONS))
+            && self.rule.aumid.as_ref().is_none_or(|a| {
+                facts
+                    .aumid
+                    .as_ref()
+                    .is_some_and(|actual| actual.eq_ignore_ascii_case(a))
+            })
+    }
+
+    /// Matches the conditions that can be checked without knowing the window's process.
+    fn matches_window(&self, facts: &WindowFacts) -> bool {
+        let rule = &self.rule;
+        let flag = |expected: Option<bool>, actual: bool| expected.is_none_or(|e| e == actual);
+
+        rule.class.as_ref().is_none_or(|c| *c == facts.class)
+            && self.title.as_ref().is_none_or(|r| r.is_match(&facts.title))
+            && flag(rule.tool_window, facts.tool_window)
+            && flag(rule.app_window, facts.app_window)
+            && flag(rule.owned, facts.owned)
+            && flag(rule.cloaked, facts.cloaked)
+    }
+
+    fn needs_process(&self) -> bool {
+        self.exe.is_some() || self.rule.aumid.is_some()
+    }
+}
+
+impl fmt::Display for WindowRule {
+    /// Formats the rule as e.g. `exclude where tool_window = true`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.action {
+            RuleAction::Include => write!(f, "include")?,
+            RuleAction::Exclude => write!(f, "exclude")?,
+            RuleAction::GroupUnder(path) => write!(f, "group under {path:?}")?,
+            RuleAction::Rename(name) => write!(f, "rename to {name:?}")?,
+            RuleAction::Icon(icon) => write!(f, "draw with icon {icon:?}")?,
+        }
+
+        let mut conditions = Vec::new();
+        if let Some(exe) = &self.exe {
+            conditions.push(format!("exe ~ {exe:?}"));
+        }
+        if let Some(class) = &self.class {
+            conditions.push(format!("class = {class:?}"));
+        }
+        if let Some(title) = &self.title {
+            conditions.push(format!("title ~ /{title}/"));
+        }
+        if let Some(aumid) = &self.aumid {
+            conditions.push(format!("aumid = {aumid:?}"));
+        }
+        for (name, flag) in [
+            ("tool_window", self.tool_window),
+            ("app_window", self.app_window),
+            ("owned", self.owned),
+            ("cloaked", self.cloaked),
+        ] {
+            if let Some(flag) = flag {
+                conditions.push(format!("{name} = {flag}"));
+            }
+        }
+
+        if conditions.is_empty() {
+            write!(f, " always")
+        } else {
+            write!(f, " where {}", conditions.join(" and "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::default_window_rules;
+
+    fn facts(exe_path: &str) -> WindowFacts {
+        WindowFacts {
+            exe_path: exe_path.to_string(),
+            class: "Notepad".to_string(),
+            title: "notes.txt - Notepad".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_rules_include_ordinary_windows() {
+        let rules = RuleSet::new(&default_window_rules());
+        let decision = rules.evaluate(&facts(r"C:\Windows\notepad.exe"));
+
+        assert!(decision.included);
+        assert_eq!(decision.deciding_rule, None);
+    }
+
+    #[test]
+    fn default_rules_exclude_tool_owned_and_cloaked_windows() {
+        let rules = RuleSet::new(&default_window_rules());
+
+        let tool = WindowFacts {
+            tool_window: true,
+            ..facts("a.exe")
+        };
+        let owned = WindowFacts {
+            owned: true,
+            ..facts("a.exe")
+        };
+        let owned_app_window = WindowFacts {
+            owned: true,
+            app_window: true,
+            ..facts("a.exe")
+        };
+        let cloaked = WindowFacts {
+            cloaked: true,
+            ..facts("a.exe")
+        };
+
+        assert_eq!(rules.evaluate(&tool).deciding_rule, Some(0));
+        assert!(!rules.evaluate(&owned).included);
+        assert!(rules.evaluate(&owned_app_window).included);
+        assert!(!rules.evaluate(&cloaked).included);
+    }
+
+    #[test]
+    fn default_rules_exclude_background_system_apps_case_insensitively() {
+        let rules = RuleSet::new(&default_window_rules());
+        let decision = rules.evaluate(&facts(r"C:\Windows\SystemApps\x\searchhost.EXE"));
+
+        assert!(!decision.included);
+    }
+
+    #[test]
+    fn first_include_or_exclude_rule_wins() {
+        let rules = RuleSet::new(&[
+            WindowRule {
+                class: Some("Notepad".to_string()),
+                action: RuleAction::Include,
+                ..Default::default()
+            },
+            WindowRule {
+                exe: Some("*notepad.exe".to_string()),
+                action: RuleAction::Exclude,
+                ..Default::default()
+            },
+        ]);
+
+        let decision = rules.evaluate(&facts(r"C:\Windows\notepad.exe"));
+        assert!(decision.included);
+        assert_eq!(decision.deciding_rule, Some(0));
+    }
+
+    #[test]
+    fn overrides_apply_alongside_visibility() {
+        let rules = RuleSet::new(&[
+            WindowRule {
+                title: Some(r"\.txt\b".to_string()),
+                action: RuleAction::Rename("Text".to_string()),
+                ..Default::default()
+            },
+            WindowRule {
+                exe: Some("*notepad.exe".to_string()),
+                action: RuleAction::Icon(r"C:\Icons\notes.svg".to_string()),
+                ..Default::default()
+            },
+            WindowRule {
+                aumid: Some("microsoft.notepad".to_string()),
+                action: RuleAction::GroupUnder(r"C:\Tools\editor.exe".to_string()),
+                ..Default::default()
+            },
+        ]);
+
+        let decision = rules.evaluate(&WindowFacts {
+            aumid: Some("Microsoft.Notepad".to_string()),
+            ..facts("notepad.exe")
+        });
+
+        assert!(decision.included);
+        assert_eq!(decision.rename.as_deref(), Some("Text"));
+        assert_eq!(decision.icon.as_deref(), Some(r"C:\Icons\notes.svg"));
+        assert_eq!(decision.group_under.as_deref(), Some(r"C:\Tools\editor.exe"));
+    }
+
+    #[test]
+    fn aumid_rule_does_not_match_unpackaged_apps() {
+        let rules = RuleSet::new(&[WindowRule {
+            aumid: Some("Microsoft.Notepad".to_string()),
+            action: RuleAction::Exclude,
+            ..Default::default()
+        }]);
+
+        assert!(rules.evaluate(&facts("notepad.exe")).included);
+    }
+
+    #[test]
+    fn rules_display_their_action_and_conditions() {
+        let rules = default_window_rules();
+
+        assert_eq!(rules[0].to_string(), "exclude where tool_window = true");
+        assert_eq!(
+            rules[1].to_string(),
+            "exclude where app_window = false and owned = true"
+        );
+        assert_eq!(WindowRule::default().to_string(), "include always");
+        assert_eq!(
+            WindowRule {
+                title: Some("^Friends".to_string()),
+                action: RuleAction::Rename("Chat".to_string()),
+                ..Default::default()
+            }
+            .to_string(),
+            r#"rename to "Chat" where title ~ /^Friends/"#
+        );
+    }
+
+    #[test]
+    fn style_only_exclusions_are_decided_without_the_process() {
+        let rules = RuleSet::new(&default_window_rules());
+        let tool = WindowFacts {
+            tool_window: true,
+            ..Default::default()
+        };
+
+        assert_eq!(rules.excluded_without_process(&tool), Some(0));
+        assert_eq!(rules.excluded_without_process(&WindowFacts::default()), None);
+    }
+
+    #[test]
+    fn process_rules_defer_the_decision_to_evaluate() {
+        let rules = RuleSet::new(&[
+            WindowRule {
+                exe: Some("*tool.exe".to_string()),
+                action: RuleAction::Include,
+                ..Default::default()
+            },
+            WindowRule {
+                tool_window: Some(true),
+                action: RuleAction::Exclude,
+                ..Default::default()
+            },
+        ]);
+        let tool = WindowFacts {
+            tool_window: true,
+            ..Default::default()
+        };
+
+        assert_eq!(rules.excluded_without_process(&tool), None);
+        assert!(rules.evaluate(&WindowFacts { exe_path: "tool.exe".to_string(), ..tool }).included);
+    }
+
+    #[test]
+    fn invalid_patterns_disable_only_their_rule() {
+        let rules = RuleSet::new(&[
+            WindowRule {
+                title: Some("(".to_string()),
+                action: RuleAction::Exclude,
+                ..Default::default()
+            },
+            WindowRule {
+                class: Some("Notepad".to_string()),
+                action: RuleAction::Exclude,
+                ..Default::default()
+            },
+        ]);
+
+        assert_eq!(rules.evaluate(&facts("a.exe")).deciding_rule, Some(1));
+    }
+}
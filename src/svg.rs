@@ -0,0 +1,70 @@
+use eframe::egui::ColorImage;
+use resvg::{tiny_skia, usvg};
+
+/// Step between the sizes scalable icons are rasterized at, in physical pixels.
+const SIZE_BUCKET_STEP: u32 = 16;
+
+/// Largest size a scalable icon is rasterized at, in physical pixels.
+const MAX_RASTER_SIZE: u32 = 1024;
+
+/// Rounds the physical pixel size an icon is drawn at up to its bucket, so that one raster
+/// serves nearby sizes and small changes do not render it again.
+pub fn size_bucket(pixels: f32) -> u32 {
+    let pixels = pixels.ceil().clamp(1.0, MAX_RASTER_SIZE as f32) as u32;
+    pixels.div_ceil(SIZE_BUCKET_STEP) * SIZE_BUCKET_STEP
+}
+
+/// Renders an SVG document into a square image `size` pixels wide, scaled to fit and centred.
+pub fn render_svg(data: &[u8], size: u32) -> Result<ColorImage, String> {
+    let tree = usvg::Tree::from_data(data, &usvg::Options::default()).map_err(|err| err.to_string())?;
+    let mut pixmap = tiny_skia::Pixmap::new(size, size).ok_or("cannot render at zero size")?;
+
+    let document = tree.size();
+    let scale = size as f32 / document.width().max(document.height());
+    let offset_x = (size as f32 - document.width() * scale) / 2.0;
+    let offset_y = (size as f32 - document.height() * scale) / 2.0;
+    let transform = tiny_skia::Transform::from_scale(scale, scale).post_translate(offset_x, offset_y);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    let size = [size as usize, size as usize];
+    Ok(ColorImage::from_rgba_premultiplied(size, pixmap.data()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::Color32;
+
+    #[test]
+    fn renders_at_the_requested_size() {
+        // A wide document: red on the left half, blue on the right
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+            <rect width="10" height="10" fill="red"/>
+            <rect x="10" width="10" height="10" fill="blue"/>
+        </svg>"#;
+
+        let image = render_svg(svg, 64).unwrap();
+
+        assert_eq!(image.size, [64, 64]);
+        // Letterboxed: transparent above and below the document
+        assert_eq!(image[(32, 4)], Color32::TRANSPARENT);
+        assert_eq!(image[(8, 32)], Color32::RED);
+        assert_eq!(image[(56, 32)], Color32::BLUE);
+    }
+
+    #[test]
+    fn sizes_round_up_to_their_bucket() {
+        // A 48 point icon magnified by 1.25 at 150% scaling
+        assert_eq!(size_bucket(48.0 * 1.25 * 1.5), 96);
+        assert_eq!(size_bucket(96.0), 96);
+        assert_eq!(size_bucket(96.1), 112);
+        assert_eq!(size_bucket(0.0), SIZE_BUCKET_STEP);
+        assert_eq!(size_bucket(5000.0), MAX_RASTER_SIZE);
+    }
+
+    #[test]
+    fn reports_invalid_documents() {
+        assert!(render_svg(b"<svg", 32).is_err());
+        assert!(render_svg(br#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1"/>"#, 0).is_err());
+    }
+}
@@ -0,0 +1,15 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Creates a fresh, uniquely-named temporary directory for a test so that assertions on the
+/// files in it are deterministic and independent from other tests running in parallel.
+pub fn unique_temp_dir(label: &str) -> PathBuf {
+    let pid = std::process::id();
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("ferro_dock_test_{label}_{pid}_{nanos}"));
+    fs::create_dir_all(&dir).expect("failed to create temp test dir");
+    dir
+}
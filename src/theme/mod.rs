@@ -0,0 +1,843 @@
+mod paint;
+mod skin;
+
+pub use paint::{apply_tooltip_style, paint_fill, paint_frame, paint_item_background, paint_reflection, paint_shelf};
+pub use skin::{Insets, Skin, SkinImage, SkinLayer, load_skin_image};
+
+use eframe::egui::{self, Color32, Vec2};
+use std::path::PathBuf;
+
+use crate::accessibility::ContrastPalette;
+use crate::config::{Appearance, Config};
+
+const THEMES_DIR_NAME: &str = "themes";
+
+/// The string a theme writes in place of a colour to use the system accent colour.
+const ACCENT_TOKEN: &str = "accent";
+
+/// The theme built from the colours in `config.toml`, used when no theme is chosen.
+pub const CLASSIC_THEME: &str = "classic";
+
+/// Themes that ship with FerroDock, by name. A file with the same name in the themes
+/// directory replaces the built-in one.
+pub const BUILT_IN_THEMES: [(&str, &str); 6] = [
+    ("classic-light", include_str!("../../themes/classic-light.toml")),
+    ("macos-light", include_str!("../../themes/macos-light.toml")),
+    ("macos-dark", include_str!("../../themes/macos-dark.toml")),
+    ("windows-11", include_str!("../../themes/windows-11.toml")),
+    ("windows-11-light", include_str!("../../themes/windows-11-light.toml")),
+    ("leopard", include_str!("../../themes/leopard.toml")),
+];
+
+/// The mode and accent colour Windows is set to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SystemAppearance {
+    /// Whether the taskbar and Start menu are dark.
+    pub dark: bool,
+    /// The accent colour, opaque.
+    pub accent: Color32,
+}
+
+impl Default for SystemAppearance {
+    /// Dark mode with the default blue accent.
+    fn default() -> Self {
+        Self {
+            dark: true,
+            accent: Color32::from_rgb(0, 120, 215),
+        }
+    }
+}
+
+impl SystemAppearance {
+    /// Whether `appearance` comes out dark on this system.
+    pub fn is_dark(&self, appearance: Appearance) -> bool {
+        match appearance {
+            Appearance::Light => false,
+            Appearance::Dark => true,
+            Appearance::Auto => self.dark,
+        }
+    }
+}
+
+/// How the dock, its items, tooltips and the menu bar are drawn.
+///
+/// Colours are `[r, g, b, a]` arrays with premultiplied alpha, as in `config.toml`, or
+/// `"accent"` for the system accent colour. Every section may be left out of a theme file,
+/// falling back to the classic look.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub frame: FrameStyle,
+    pub items: ItemStyles,
+    pub indicators: IndicatorStyle,
+    pub tooltip: TooltipStyle,
+    pub menu_bar: BarStyle,
+    /// Images drawn in place of, or over, the frame.
+    pub skin: Option<Skin>,
+    /// A shelf in perspective that the items stand on, drawn in place of the dock's frame.
+    pub shelf: Option<ShelfStyle>,
+    /// Mirrors the items in the floor below them.
+    pub reflection: Option<ReflectionStyle>,
+}
+
+/// A solid colour or a two-stop gradient.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub enum Fill {
+    Solid(Color32),
+    Gradient(Gradient),
+}
+
+/// A linear gradient across a shape, from its top (or left) edge to its bottom (or right).
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Gradient {
+    pub from: Color32,
+    pub to: Color32,
+    #[serde(default)]
+    pub direction: GradientDirection,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradientDirection {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Border {
+    pub width: f32,
+    pub color: Color32,
+}
+
+impl From<Border> for egui::Stroke {
+    fn from(border: Border) -> Self {
+        egui::Stroke::new(border.width, border.color)
+    }
+}
+
+/// A blurred shadow, cast outside a shape or, as an inner shadow, inside its edges.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Shadow {
+    pub offset: Vec2,
+    /// Width of the soft edge, in points.
+    pub blur: f32,
+    /// Grows the shadow beyond the shape in every direction.
+    pub spread: f32,
+    pub color: Color32,
+}
+
+impl Default for Shadow {
+    fn default() -> Self {
+        Self {
+            offset: egui::vec2(0.0, 4.0),
+            blur: 12.0,
+            spread: 0.0,
+            color: Color32::from_black_alpha(80),
+        }
+    }
+}
+
+impl From<Shadow> for egui::epaint::Shadow {
+    fn from(shadow: Shadow) -> Self {
+        egui::epaint::Shadow {
+            offset: shadow.offset,
+            blur: shadow.blur,
+            spread: shadow.spread,
+            color: shadow.color,
+        }
+    }
+}
+
+/// The panel behind the dock items, also used for the overflow popup and desktop widgets.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrameStyle {
+    pub fill: Fill,
+    pub border: Border,
+    pub rounding: f32,
+    pub shadow: Option<Shadow>,
+    pub inner_shadow: Option<Shadow>,
+}
+
+/// A 3D shelf along the bottom of the dock: a top surface whose back edge is drawn in at
+/// both ends, and a front face below it.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShelfStyle {
+    /// Height of the top surface, in points.
+    pub depth: f32,
+    /// How far the back edge is drawn in from each end, in points.
+    pub slant: f32,
+    /// The top surface; gradients run from its back edge to its front.
+    pub surface: Fill,
+    pub front_height: f32,
+    pub front: Color32,
+    /// A highlight along the surface's front edge.
+    pub edge: Option<Border>,
+}
+
+impl Default for ShelfStyle {
+    fn default() -> Self {
+        Self {
+            depth: 22.0,
+            slant: 28.0,
+            surface: Fill::Gradient(Gradient {
+                from: Color32::from_rgba_premultiplied(120, 120, 125, 150),
+                to: Color32::from_rgba_premultiplied(200, 200, 205, 210),
+                direction: GradientDirection::Vertical,
+            }),
+            front_height: 3.0,
+            front: Color32::from_rgba_premultiplied(60, 60, 65, 220),
+            edge: Some(Border {
+                width: 1.0,
+                color: Color32::from_white_alpha(120),
+            }),
+        }
+    }
+}
+
+/// Upside-down copies of the icons below the floor, fading out downwards. They follow the
+/// icons as they are magnified and bounce.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReflectionStyle {
+    /// Opacity where the reflection meets the floor, from 0 to 1.
+    pub opacity: f32,
+    /// How much of the icon is mirrored, as a share of its height.
+    pub length: f32,
+}
+
+impl Default for ReflectionStyle {
+    fn default() -> Self {
+        Self {
+            opacity: 0.35,
+            length: 0.5,
+        }
+    }
+}
+
+/// How an item is drawn in one state.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ItemStyle {
+    /// A plate drawn behind the icon.
+    pub background: Option<Fill>,
+    pub border: Option<Border>,
+    pub rounding: f32,
+    /// Multiplied with the icon's colours; white leaves the icon unchanged.
+    pub tint: Color32,
+    pub shadow: Option<Shadow>,
+}
+
+impl Default for ItemStyle {
+    fn default() -> Self {
+        Self {
+            background: None,
+            border: None,
+            rounding: 10.0,
+            tint: Color32::WHITE,
+            shadow: None,
+        }
+    }
+}
+
+/// Changes to an item's normal style in one state. Unset values keep the normal style.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ItemPatch {
+    pub background: Option<Fill>,
+    pub border: Option<Border>,
+    pub rounding: Option<f32>,
+    pub tint: Option<Color32>,
+    pub shadow: Option<Shadow>,
+}
+
+impl ItemPatch {
+    fn apply(&self, style: ItemStyle) -> ItemStyle {
+        ItemStyle {
+            background: self.background.or(style.background),
+            border: self.border.or(style.border),
+            rounding: self.rounding.unwrap_or(style.rounding),
+            tint: self.tint.unwrap_or(style.tint),
+            shadow: self.shadow.or(style.shadow),
+        }
+    }
+}
+
+/// Item styles by state. The states an item is in are layered over `normal` in the order
+/// active, attention, hovered, pressed, so a pressed item looks pressed even while active.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ItemStyles {
+    pub normal: ItemStyle,
+    pub active: ItemPatch,
+    pub attention: ItemPatch,
+    pub hovered: ItemPatch,
+    pub pressed: ItemPatch,
+}
+
+/// The states an item can be drawn in; several can hold at once.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ItemState {
+    /// The item's application is in the foreground.
+    pub active: bool,
+    pub attention: bool,
+    pub hovered: bool,
+    pub pressed: bool,
+}
+
+impl ItemStyles {
+    /// The style of an item in `state`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let style = theme.items.resolve(ItemState { hovered: true, ..Default::default() });
+    /// ```
+    pub fn resolve(&self, state: ItemState) -> ItemStyle {
+        [
+            (state.active, &self.active),
+            (state.attention, &self.attention),
+            (state.hovered, &self.hovered),
+            (state.pressed, &self.pressed),
+        ]
+        .into_iter()
+        .filter(|(applies, _)| *applies)
+        .fold(self.normal, |style, (_, patch)| patch.apply(style))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndicatorStyle {
+    /// The dot under applications with a window.
+    pub running: Color32,
+    /// The foreground application's indicator, and the keyboard selection.
+    pub foreground: Color32,
+    /// Items requesting attention, and plugin badges.
+    pub attention: Color32,
+    /// The badge on items that failed to launch.
+    pub launch_error: Color32,
+}
+
+impl Default for IndicatorStyle {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            running: Color32::from_rgba_unmultiplied(240, 240, 245, 220),
+            foreground: config.foreground_indicator_color,
+            attention: config.attention_indicator_color,
+            launch_error: config.launch_error_color,
+        }
+    }
+}
+
+/// Tooltips, drawn by egui as small popup windows. Gradients are not supported here.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TooltipStyle {
+    pub fill: Color32,
+    pub text_color: Color32,
+    pub border: Border,
+    pub rounding: f32,
+    pub shadow: Option<Shadow>,
+}
+
+impl Default for TooltipStyle {
+    fn default() -> Self {
+        Self {
+            fill: Color32::from_rgb(32, 32, 36),
+            text_color: Color32::from_rgb(240, 240, 245),
+            border: Border {
+                width: 1.0,
+                color: Color32::from_white_alpha(30),
+            },
+            rounding: 6.0,
+            shadow: Some(Shadow::default()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BarStyle {
+    pub fill: Fill,
+    pub text_color: Color32,
+    /// A line along the bottom edge.
+    pub separator: Option<Border>,
+}
+
+impl Default for BarStyle {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            fill: Fill::Solid(config.menu_bar.background_color),
+            text_color: config.menu_bar.text_color,
+            separator: None,
+        }
+    }
+}
+
+impl Default for FrameStyle {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            fill: Fill::Solid(config.background_color),
+            border: Border {
+                width: config.border_width,
+                color: config.border_color,
+            },
+            rounding: config.rounding,
+            shadow: None,
+            inner_shadow: None,
+        }
+    }
+}
+
+impl Default for Theme {
+    /// The classic look with the default config.
+    fn default() -> Self {
+        Self {
+            frame: FrameStyle::default(),
+            items: ItemStyles::default(),
+            indicators: IndicatorStyle::default(),
+            tooltip: TooltipStyle::default(),
+            menu_bar: BarStyle::default(),
+            skin: None,
+            shelf: None,
+            reflection: None,
+        }
+    }
+}
+
+impl Theme {
+    /// The classic look, from the colours in `config`.
+    pub fn from_config(config: &Config) -> Self {
+        let defaults = Self::default();
+        Self {
+            frame: FrameStyle {
+                fill: Fill::Solid(config.background_color),
+                border: Border {
+                    width: config.border_width,
+                    color: config.border_color,
+                },
+                rounding: config.rounding,
+                ..defaults.frame
+            },
+            indicators: IndicatorStyle {
+                foreground: config.foreground_indicator_color,
+                attention: config.attention_indicator_color,
+                launch_error: config.launch_error_color,
+                ..defaults.indicators
+            },
+            menu_bar: BarStyle {
+                fill: Fill::Solid(config.menu_bar.background_color),
+                text_color: config.menu_bar.text_color,
+                ..defaults.menu_bar
+            },
+            ..defaults
+        }
+    }
+
+    /// Parses a theme file, drawing its `"accent"` colours in `accent`.
+    pub fn parse(text: &str, accent: Color32) -> Result<Self, String> {
+        let mut value: toml::Value = toml::from_str(text).map_err(|err: toml::de::Error| err.message().to_string())?;
+        resolve_accent(&mut value, accent)?;
+        value.try_into().map_err(|err: toml::de::Error| err.message().to_string())
+    }
+
+    /// This theme in solid system contrast colours, keeping its shapes. Gradients, shadows,
+    /// tints, skins, shelves and reflections are dropped so nothing reduces contrast.
+    pub fn with_contrast(&self, palette: &ContrastPalette) -> Self {
+        let border = Border {
+            width: self.frame.border.width.max(2.0),
+            color: palette.foreground,
+        };
+        // States the theme marks with a plate or border are outlined instead
+        let item = |patch: &ItemPatch| ItemPatch {
+            border: (patch.background.is_some() || patch.border.is_some()).then_some(Border {
+                width: 2.0,
+                color: palette.highlight,
+            }),
+            rounding: patch.rounding,
+            ..ItemPatch::default()
+        };
+
+        Self {
+            frame: FrameStyle {
+                fill: Fill::Solid(palette.background),
+                border,
+                rounding: self.frame.rounding,
+                shadow: None,
+                inner_shadow: None,
+            },
+            items: ItemStyles {
+                normal: ItemStyle {
+                    rounding: self.items.normal.rounding,
+                    ..ItemStyle::default()
+                },
+                active: item(&self.items.active),
+                attention: item(&self.items.attention),
+                hovered: item(&self.items.hovered),
+                pressed: item(&self.items.pressed),
+            },
+            indicators: IndicatorStyle {
+                running: palette.foreground,
+                foreground: palette.highlight,
+                attention: palette.hot,
+                launch_error: self.indicators.launch_error,
+            },
+            tooltip: TooltipStyle {
+                fill: palette.background,
+                text_color: palette.foreground,
+                border,
+                rounding: self.tooltip.rounding,
+                shadow: None,
+            },
+            menu_bar: BarStyle {
+                fill: Fill::Solid(palette.background),
+                text_color: palette.foreground,
+                separator: Some(border),
+            },
+            skin: None,
+            shelf: None,
+            reflection: None,
+        }
+    }
+}
+
+/// The themes that can be chosen, by name: the classic look, the built-in themes and the
+/// files in the themes directory, sorted and without duplicates. Themes with light and dark
+/// variants are listed once.
+pub fn theme_names() -> Vec<String> {
+    let files = themes_dir()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            (path.extension()? == "toml").then(|| path.file_stem()?.to_str().map(str::to_string))?
+        });
+
+    let mut names: Vec<String> = BUILT_IN_THEMES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(files)
+        .chain([CLASSIC_THEME.to_string()])
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let mut names = group_variants(names);
+    names.retain(|name| name != CLASSIC_THEME);
+    names.insert(0, CLASSIC_THEME.to_string());
+    names
+}
+
+/// Loads the theme called `name`, or its `-light` or `-dark` variant for the appearance in
+/// `config` if there is one: the classic look from `config`, a file from the themes
+/// directory, or a built-in theme.
+pub fn load_theme(name: &str, config: &Config, system: &SystemAppearance) -> Result<Theme, String> {
+    let suffix = if system.is_dark(config.appearance) { "dark" } else { "light" };
+    let variant = format!("{name}-{suffix}");
+    let name = if theme_exists(&variant) { variant.as_str() } else { name };
+
+    if name == CLASSIC_THEME {
+        return Ok(Theme::from_config(config));
+    }
+
+    if let Some(path) = theme_file(name) {
+        let text = std::fs::read_to_string(&path).map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        let mut theme =
+            Theme::parse(&text, system.accent).map_err(|err| format!("invalid {}: {err}", path.display()))?;
+        if let (Some(skin), Some(dir)) = (&mut theme.skin, path.parent()) {
+            skin.resolve_paths(dir);
+        }
+        return Ok(theme);
+    }
+
+    let (_, text) = BUILT_IN_THEMES
+        .iter()
+        .find(|(built_in, _)| *built_in == name)
+        .ok_or_else(|| format!("unknown theme `{name}`"))?;
+    Theme::parse(text, system.accent)
+}
+
+/// Whether a theme file called `name` would replace a built-in theme, or a light or dark
+/// variant of one, when loaded by its name or its family's.
+pub fn shadows_built_in_theme(name: &str) -> bool {
+    let family = |name: &str| {
+        let family = name.strip_suffix("-light").or_else(|| name.strip_suffix("-dark"));
+        family.unwrap_or(name).to_string()
+    };
+
+    BUILT_IN_THEMES
+        .iter()
+        .map(|(built_in, _)| *built_in)
+        .chain([CLASSIC_THEME])
+        .any(|built_in| family(built_in) == family(name))
+}
+
+fn theme_exists(name: &str) -> bool {
+    name == CLASSIC_THEME || theme_file(name).is_some() || BUILT_IN_THEMES.iter().any(|(built_in, _)| *built_in == name)
+}
+
+fn theme_file(name: &str) -> Option<PathBuf> {
+    themes_dir().map(|dir| dir.join(format!("{name}.toml"))).filter(|path| path.is_file())
+}
+
+/// Lists the `-light` and `-dark` variants in sorted `names` under their family's name when
+/// both exist, and hides the variants of a theme that also exists on its own.
+fn group_variants(names: Vec<String>) -> Vec<String> {
+    let has = |name: &str| names.iter().any(|other| other == name);
+
+    let mut grouped: Vec<String> = names
+        .iter()
+        .filter_map(|name| {
+            let family = name.strip_suffix("-light").or_else(|| name.strip_suffix("-dark"));
+            match family {
+                Some(family) if has(family) => None,
+                Some(family) if has(&format!("{family}-light")) && has(&format!("{family}-dark")) => {
+                    Some(family.to_string())
+                }
+                _ => Some(name.clone()),
+            }
+        })
+        .collect();
+    grouped.dedup();
+    grouped
+}
+
+/// Replaces the `"accent"` strings in a theme with the accent colour, and `"accent/40"` with
+/// the accent colour at 40% opacity.
+fn resolve_accent(value: &mut toml::Value, accent: Color32) -> Result<(), String> {
+    match value {
+        toml::Value::String(token) => {
+            let Some(rest) = token.strip_prefix(ACCENT_TOKEN) else {
+                return Ok(());
+            };
+            let color = match rest.strip_prefix('/') {
+                None if rest.is_empty() => accent,
+                Some(percent) => {
+                    let percent = percent
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|percent| *percent <= 100)
+                        .ok_or_else(|| format!("invalid accent opacity in `{token}`"))?;
+                    accent.gamma_multiply(percent as f32 / 100.0)
+                }
+                None => return Err(format!("unknown colour `{token}`")),
+            };
+            *value = toml::Value::Array(color.to_array().map(|c| toml::Value::Integer(c.into())).to_vec());
+        }
+        toml::Value::Array(items) => {
+            for item in items {
+                resolve_accent(item, accent)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, item) in table.iter_mut() {
+                resolve_accent(item, accent)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The directory new theme files are written to: the `themes` directory that is read, or a
+/// new one next to the executable when there is none yet.
+pub fn writable_themes_dir() -> PathBuf {
+    themes_dir()
+        .or_else(|| {
+            let exe = std::env::current_exe().ok()?;
+            Some(exe.parent()?.join(THEMES_DIR_NAME))
+        })
+        .unwrap_or_else(|| PathBuf::from(THEMES_DIR_NAME))
+}
+
+/// The directory theme files are read from: `themes` next to the executable, falling back
+/// to the working directory, like `config.toml`.
+fn themes_dir() -> Option<PathBuf> {
+    let beside_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(THEMES_DIR_NAME)));
+
+    beside_exe
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(THEMES_DIR_NAME)))
+        .find(|path| path.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_themes_parse() {
+        for (name, text) in BUILT_IN_THEMES {
+            assert!(Theme::parse(text, Color32::BLUE).is_ok(), "{name}: {:?}", Theme::parse(text, Color32::BLUE).err());
+        }
+    }
+
+    #[test]
+    fn classic_theme_follows_the_config() {
+        let config: Config = toml::from_str("rounding = 4.0\nbackground_color = [10, 20, 30, 255]").unwrap();
+        let theme = Theme::from_config(&config);
+
+        assert_eq!(theme.frame.rounding, 4.0);
+        assert_eq!(theme.frame.fill, Fill::Solid(Color32::from_rgb(10, 20, 30)));
+        assert_eq!(Theme::from_config(&Config::default()), Theme::default());
+    }
+
+    #[test]
+    fn fills_are_colours_or_gradients() {
+        let theme = Theme::parse(
+            r#"
+            [frame]
+            fill = { from = [255, 255, 255, 255], to = [0, 0, 0, 255], direction = "horizontal" }
+
+            [menu_bar]
+            fill = [20, 20, 20, 255]
+            "#,
+            Color32::BLUE,
+        )
+        .unwrap();
+
+        assert_eq!(
+            theme.frame.fill,
+            Fill::Gradient(Gradient {
+                from: Color32::WHITE,
+                to: Color32::BLACK,
+                direction: GradientDirection::Horizontal,
+            })
+        );
+        assert_eq!(theme.menu_bar.fill, Fill::Solid(Color32::from_gray(20)));
+        assert!(Theme::parse("[frame]\nfil = [0, 0, 0, 255]", Color32::BLUE).is_err());
+    }
+
+    #[test]
+    fn item_states_layer_over_the_normal_style() {
+        let theme = Theme::parse(
+            r#"
+            [items.normal]
+            rounding = 8.0
+            [items.active]
+            background = [40, 40, 40, 40]
+            [items.hovered]
+            background = [60, 60, 60, 60]
+            [items.pressed]
+            tint = [200, 200, 200, 255]
+            "#,
+            Color32::BLUE,
+        )
+        .unwrap();
+
+        let normal = theme.items.resolve(ItemState::default());
+        let active_pressed = theme.items.resolve(ItemState {
+            active: true,
+            pressed: true,
+            ..Default::default()
+        });
+        let active_hovered = theme.items.resolve(ItemState {
+            active: true,
+            hovered: true,
+            ..Default::default()
+        });
+
+        assert_eq!(normal.background, None);
+        assert_eq!(active_pressed.background, Some(Fill::Solid(Color32::from_rgba_premultiplied(40, 40, 40, 40))));
+        assert_eq!(active_pressed.tint, Color32::from_gray(200));
+        assert_eq!(active_pressed.rounding, 8.0);
+        assert_eq!(active_hovered.background, Some(Fill::Solid(Color32::from_rgba_premultiplied(60, 60, 60, 60))));
+    }
+
+    #[test]
+    fn shelves_and_reflections_are_opt_in() {
+        let theme = Theme::parse("[shelf]\ndepth = 30.0\n[reflection]\nopacity = 0.5", Color32::BLUE).unwrap();
+
+        assert_eq!(theme.shelf.map(|shelf| shelf.depth), Some(30.0));
+        assert_eq!(theme.shelf.map(|shelf| shelf.slant), Some(ShelfStyle::default().slant));
+        assert_eq!(theme.reflection.map(|reflection| reflection.opacity), Some(0.5));
+        assert_eq!(Theme::default().shelf, None);
+        assert_eq!(theme.with_contrast(&ContrastPalette::default()).reflection, None);
+    }
+
+    #[test]
+    fn contrast_drops_gradients_and_shadows() {
+        let (_, text) = BUILT_IN_THEMES[2];
+        let theme = Theme::parse(text, Color32::BLUE).unwrap().with_contrast(&ContrastPalette::default());
+
+        assert_eq!(theme.frame.fill, Fill::Solid(Color32::BLACK));
+        assert_eq!(theme.frame.shadow, None);
+        assert_eq!(theme.indicators.attention, ContrastPalette::default().hot);
+        assert!(theme.frame.border.width >= 2.0);
+    }
+
+    #[test]
+    fn classic_theme_is_listed_first() {
+        let names = theme_names();
+
+        assert_eq!(names[0], CLASSIC_THEME);
+        assert!(names.iter().any(|name| name == "windows-11"));
+        assert!(load_theme("no-such-theme", &Config::default(), &SystemAppearance::default()).is_err());
+    }
+
+    #[test]
+    fn variants_are_listed_under_their_family() {
+        let names = ["classic", "classic-light", "macos-dark", "macos-light", "solo-dark"];
+        let grouped = group_variants(names.map(String::from).to_vec());
+
+        assert_eq!(grouped, ["classic", "macos", "solo-dark"]);
+    }
+
+    #[test]
+    fn appearance_picks_the_variant() {
+        let light = Config {
+            appearance: Appearance::Auto,
+            ..Config::default()
+        };
+        let system = SystemAppearance {
+            dark: false,
+            ..SystemAppearance::default()
+        };
+        let parse = |text| Theme::parse(text, system.accent).unwrap();
+
+        assert_eq!(load_theme("macos", &light, &system), Ok(parse(BUILT_IN_THEMES[1].1)));
+        assert_eq!(load_theme("macos", &Config::default(), &system), Ok(parse(BUILT_IN_THEMES[2].1)));
+        assert_eq!(load_theme("classic", &light, &system), Ok(parse(BUILT_IN_THEMES[0].1)));
+        assert_eq!(load_theme("classic", &Config::default(), &system), Ok(Theme::default()));
+    }
+
+    #[test]
+    fn accent_tokens_become_the_accent_colour() {
+        let accent = Color32::from_rgb(0, 120, 200);
+        let theme = Theme::parse(
+            r#"
+            [frame]
+            fill = { from = "accent", to = "accent/50", direction = "horizontal" }
+            [indicators]
+            foreground = "accent"
+            "#,
+            accent,
+        )
+        .unwrap();
+
+        assert_eq!(theme.indicators.foreground, accent);
+        assert_eq!(
+            theme.frame.fill,
+            Fill::Gradient(Gradient {
+                from: accent,
+                to: accent.gamma_multiply(0.5),
+                direction: GradientDirection::Horizontal,
+            })
+        );
+        assert!(Theme::parse("[indicators]\nforeground = \"accent/200\"", accent).is_err());
+        assert!(Theme::parse("[indicators]\nforeground = \"accents\"", accent).is_err());
+    }
+}
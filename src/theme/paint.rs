@@ -0,0 +1,294 @@
+use eframe::egui::epaint::{Mesh, RectShape, TessellationOptions, Tessellator, Vertex};
+use eframe::egui::{self, Color32, Painter, Pos2, Rect, Rounding, Shape, Stroke, TextureId, pos2, vec2};
+
+use crate::theme::{
+    Fill, FrameStyle, Gradient, GradientDirection, ItemStyle, ReflectionStyle, Shadow, ShelfStyle, TooltipStyle,
+};
+
+/// Rings an inner shadow's soft edge is drawn with.
+const INNER_SHADOW_STEPS: usize = 6;
+
+/// Fills `rect` with a colour or gradient.
+pub fn paint_fill(painter: &Painter, rect: Rect, rounding: impl Into<Rounding>, fill: Fill) {
+    match fill {
+        Fill::Solid(color) => {
+            painter.rect_filled(rect, rounding, color);
+        }
+        Fill::Gradient(gradient) => {
+            let pixels_per_point = painter.ctx().pixels_per_point();
+            painter.add(Shape::mesh(gradient_mesh(rect, rounding.into(), &gradient, pixels_per_point)));
+        }
+    }
+}
+
+/// Draws a panel: its drop shadow, fill, inner shadow and border.
+pub fn paint_frame(painter: &Painter, rect: Rect, style: &FrameStyle) {
+    if let Some(shadow) = style.shadow {
+        paint_drop_shadow(painter, rect, style.rounding, shadow);
+    }
+    paint_fill(painter, rect, style.rounding, style.fill);
+    if let Some(shadow) = style.inner_shadow {
+        paint_inner_shadow(painter, rect, style.rounding, shadow);
+    }
+    painter.rect_stroke(rect, style.rounding, style.border);
+}
+
+/// Draws the plate, border and shadow behind an item's icon, if its style has any.
+pub fn paint_item_background(painter: &Painter, rect: Rect, style: &ItemStyle) {
+    if let (Some(shadow), Some(_)) = (style.shadow, style.background) {
+        paint_drop_shadow(painter, rect, style.rounding, shadow);
+    }
+    if let Some(fill) = style.background {
+        paint_fill(painter, rect, style.rounding, fill);
+    }
+    if let Some(border) = style.border {
+        painter.rect_stroke(rect, style.rounding, border);
+    }
+}
+
+/// Draws a shelf along the bottom of `rect`: its top surface in perspective, its front face
+/// and the highlight along its front edge.
+pub fn paint_shelf(painter: &Painter, rect: Rect, style: &ShelfStyle) {
+    let (surface, front) = shelf_geometry(rect, style);
+    let pixels_per_point = painter.ctx().pixels_per_point();
+    painter.add(Shape::mesh(polygon_mesh(&surface, style.surface, pixels_per_point)));
+    painter.rect_filled(front, 0.0, style.front);
+    if let Some(edge) = style.edge {
+        painter.line_segment([surface[3], surface[2]], edge);
+    }
+}
+
+/// Draws an upside-down copy of the icon in `texture` below `floor`, fading out downwards.
+/// `floor` is the bottom of the item's unmagnified slot and `icon` is where the icon is
+/// drawn; a magnified icon that reaches below the floor is mirrored from its own bottom edge.
+pub fn paint_reflection(
+    painter: &Painter,
+    texture: TextureId,
+    icon: Rect,
+    floor: f32,
+    tint: Color32,
+    style: &ReflectionStyle,
+) {
+    painter.add(Shape::mesh(reflection_mesh(texture, icon, floor, tint, style)));
+}
+
+/// Styles egui's tooltips, which are drawn as popup frames.
+pub fn apply_tooltip_style(ctx: &egui::Context, style: &TooltipStyle) {
+    ctx.style_mut(|egui_style| {
+        let visuals = &mut egui_style.visuals;
+        visuals.window_fill = style.fill;
+        visuals.window_stroke = style.border.into();
+        visuals.menu_rounding = Rounding::same(style.rounding);
+        visuals.popup_shadow = style.shadow.map_or(egui::epaint::Shadow::NONE, Into::into);
+        visuals.widgets.noninteractive.fg_stroke.color = style.text_color;
+    });
+}
+
+fn paint_drop_shadow(painter: &Painter, rect: Rect, rounding: f32, shadow: Shadow) {
+    let shadow = egui::epaint::Shadow::from(shadow);
+    painter.add(Shape::mesh(shadow.tessellate(rect, rounding)));
+}
+
+/// Darkens (or, with a light colour, lights) the inside of `rect` along its edges, fading
+/// inwards over the shadow's blur.
+fn paint_inner_shadow(painter: &Painter, rect: Rect, rounding: f32, shadow: Shadow) {
+    let painter = painter.with_clip_rect(rect.intersect(painter.clip_rect()));
+    let band = shadow.blur.max(1.0) / INNER_SHADOW_STEPS as f32;
+
+    for step in 0..INNER_SHADOW_STEPS {
+        let inset = shadow.spread + band * (step as f32 + 0.5);
+        let ring = rect.shrink(inset).translate(shadow.offset);
+        let strength = 1.0 - step as f32 / INNER_SHADOW_STEPS as f32;
+        painter.rect_stroke(
+            ring,
+            (rounding - inset).max(0.0),
+            Stroke::new(band, shadow.color.gamma_multiply(strength)),
+        );
+    }
+}
+
+/// The corners of a shelf's top surface, clockwise from its back left, and its front face.
+fn shelf_geometry(rect: Rect, style: &ShelfStyle) -> ([Pos2; 4], Rect) {
+    let height = (style.depth + style.front_height).min(rect.height());
+    let front_top = rect.bottom() - style.front_height.min(height);
+    let back = rect.bottom() - height;
+    let slant = style.slant.clamp(0.0, rect.width() / 2.0);
+    let surface = [
+        pos2(rect.left() + slant, back),
+        pos2(rect.right() - slant, back),
+        pos2(rect.right(), front_top),
+        pos2(rect.left(), front_top),
+    ];
+    (surface, Rect::from_min_max(pos2(rect.left(), front_top), rect.max))
+}
+
+/// Tessellates a convex polygon with anti-aliased edges, filled with `fill` across its
+/// bounding box.
+fn polygon_mesh(points: &[Pos2], fill: Fill, pixels_per_point: f32) -> Mesh {
+    let mut tessellator = Tessellator::new(pixels_per_point, TessellationOptions::default(), [1, 1], Vec::new());
+    let mut mesh = Mesh::default();
+    tessellator.tessellate_shape(Shape::convex_polygon(points.to_vec(), Color32::WHITE, Stroke::NONE), &mut mesh);
+
+    let gradient = match fill {
+        Fill::Solid(color) => Gradient {
+            from: color,
+            to: color,
+            direction: GradientDirection::Vertical,
+        },
+        Fill::Gradient(gradient) => gradient,
+    };
+    color_along(&mut mesh, Rect::from_points(points), &gradient);
+    mesh
+}
+
+/// A quad showing the bottom `style.length` of the texture upside down, starting as far below
+/// `floor` as `icon` is above it and fading from `tint` at the opacity to transparent.
+fn reflection_mesh(texture: TextureId, icon: Rect, floor: f32, tint: Color32, style: &ReflectionStyle) -> Mesh {
+    let length = style.length.clamp(0.0, 1.0);
+    let top = (2.0 * floor - icon.bottom()).max(icon.bottom());
+    let rect = Rect::from_min_size(pos2(icon.left(), top), vec2(icon.width(), icon.height() * length));
+    let color = tint.gamma_multiply(style.opacity.clamp(0.0, 1.0));
+
+    let mut mesh = Mesh::with_texture(texture);
+    let corners = [
+        (rect.left_top(), pos2(0.0, 1.0), color),
+        (rect.right_top(), pos2(1.0, 1.0), color),
+        (rect.right_bottom(), pos2(1.0, 1.0 - length), Color32::TRANSPARENT),
+        (rect.left_bottom(), pos2(0.0, 1.0 - length), Color32::TRANSPARENT),
+    ];
+    mesh.vertices
+        .extend(corners.map(|(pos, uv, color)| Vertex { pos, uv, color }));
+    mesh.add_triangle(0, 1, 2);
+    mesh.add_triangle(0, 2, 3);
+    mesh
+}
+
+/// Tessellates `rect` with anti-aliased edges, coloured along `gradient`.
+fn gradient_mesh(rect: Rect, rounding: Rounding, gradient: &Gradient, pixels_per_point: f32) -> Mesh {
+    let mut tessellator = Tessellator::new(pixels_per_point, TessellationOptions::default(), [1, 1], Vec::new());
+    let mut mesh = Mesh::default();
+    tessellator.tessellate_rect(&RectShape::filled(rect, rounding, Color32::WHITE), &mut mesh);
+    color_along(&mut mesh, rect, gradient);
+    mesh
+}
+
+/// Colours the vertices of a white mesh covering `rect` by where they fall along `gradient`.
+fn color_along(mesh: &mut Mesh, rect: Rect, gradient: &Gradient) {
+    for vertex in &mut mesh.vertices {
+        let (position, extent) = match gradient.direction {
+            GradientDirection::Vertical => (vertex.pos.y - rect.top(), rect.height()),
+            GradientDirection::Horizontal => (vertex.pos.x - rect.left(), rect.width()),
+        };
+        let t = if extent > 0.0 { (position / extent).clamp(0.0, 1.0) } else { 0.0 };
+
+        // Vertices on the anti-aliased edge are white with partial coverage
+        let coverage = vertex.color.a() as f32 / 255.0;
+        vertex.color = lerp_color(gradient.from, gradient.to, t).gamma_multiply(coverage);
+    }
+}
+
+/// Mixes two premultiplied colours, `t` of the way from `from` to `to`.
+fn lerp_color(from: Color32, to: Color32, t: f32) -> Color32 {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgba_premultiplied(
+        mix(from.r(), to.r()),
+        mix(from.g(), to.g()),
+        mix(from.b(), to.b()),
+        mix(from.a(), to.a()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::layout::{MonitorGeometry, ViewState, compute_layout};
+
+    #[test]
+    fn gradient_runs_from_edge_to_edge() {
+        let rect = Rect::from_min_size(egui::pos2(0.0, 100.0), egui::vec2(200.0, 50.0));
+        let gradient = Gradient {
+            from: Color32::WHITE,
+            to: Color32::BLACK,
+            direction: GradientDirection::Vertical,
+        };
+
+        let mesh = gradient_mesh(rect, Rounding::ZERO, &gradient, 1.0);
+
+        // Opaque vertices take the colour of their height; edge vertices fade out
+        let opaque: Vec<_> = mesh.vertices.iter().filter(|v| v.color.a() == 255).collect();
+        assert!(!opaque.is_empty());
+        for vertex in opaque {
+            let expected = lerp_color(Color32::WHITE, Color32::BLACK, (vertex.pos.y - 100.0) / 50.0);
+            assert_eq!(vertex.color, expected);
+        }
+        assert!(mesh.vertices.iter().any(|v| v.color.a() == 0));
+    }
+
+    #[test]
+    fn shelf_surface_narrows_towards_the_back() {
+        let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(400.0, 80.0));
+        let style = ShelfStyle {
+            depth: 20.0,
+            slant: 30.0,
+            front_height: 4.0,
+            ..ShelfStyle::default()
+        };
+
+        let (surface, front) = shelf_geometry(rect, &style);
+
+        assert_eq!(surface, [pos2(30.0, 56.0), pos2(370.0, 56.0), pos2(400.0, 76.0), pos2(0.0, 76.0)]);
+        assert_eq!(front, Rect::from_min_max(pos2(0.0, 76.0), pos2(400.0, 80.0)));
+    }
+
+    #[test]
+    fn reflections_mirror_magnification_and_bounce() {
+        let style = ReflectionStyle {
+            opacity: 0.5,
+            length: 0.5,
+        };
+        let monitor = MonitorGeometry {
+            bounds: Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1080.0)),
+            work_area: Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1040.0)),
+            dpi: 96.0,
+        };
+        let config = Config {
+            icon_size: vec2(40.0, 40.0),
+            hover_scale: 1.5,
+            ..Config::default()
+        };
+        let view = ViewState {
+            hovered: Some(0),
+            ..ViewState::default()
+        };
+        let item = compute_layout(&monitor, &config, 3, &view).items[0];
+        let floor = item.slot.bottom();
+        let reflect = |icon: Rect| {
+            let mesh = reflection_mesh(TextureId::default(), icon, floor, Color32::WHITE, &style);
+            mesh.vertices.iter().map(|v| v.pos - pos2(item.icon.left(), floor)).collect::<Vec<_>>()
+        };
+
+        // The 60 point magnified icon reaches 10 points below its slot and is mirrored from there
+        assert_eq!(item.icon.bottom(), floor + 10.0);
+        assert_eq!(reflect(item.icon), [vec2(0.0, 10.0), vec2(60.0, 10.0), vec2(60.0, 40.0), vec2(0.0, 40.0)]);
+        // Bouncing 14 points lifts it 4 points above the floor, so its reflection drops 4 below
+        let bounced = reflect(item.icon.translate(vec2(0.0, -14.0)));
+        assert_eq!(bounced, [vec2(0.0, 4.0), vec2(60.0, 4.0), vec2(60.0, 34.0), vec2(0.0, 34.0)]);
+
+        let mesh = reflection_mesh(TextureId::default(), item.icon, floor, Color32::WHITE, &style);
+        // Upside down: the icon's bottom edge is nearest the floor
+        assert_eq!(mesh.vertices[0].uv, pos2(0.0, 1.0));
+        assert_eq!(mesh.vertices[3].uv, pos2(0.0, 0.5));
+        assert_eq!(mesh.vertices[0].color, Color32::WHITE.gamma_multiply(0.5));
+        assert_eq!(mesh.vertices[3].color, Color32::TRANSPARENT);
+    }
+
+    #[test]
+    fn colours_mix_linearly() {
+        let grey = lerp_color(Color32::from_gray(0), Color32::from_gray(200), 0.25);
+
+        assert_eq!(grey, Color32::from_gray(50));
+        assert_eq!(lerp_color(Color32::RED, Color32::BLUE, 0.0), Color32::RED);
+        assert_eq!(lerp_color(Color32::RED, Color32::BLUE, 1.0), Color32::BLUE);
+    }
+}
@@ -0,0 +1,245 @@
+use eframe::egui::{self, Color32, ColorImage, Painter, Rect, Shape, TextureHandle, Vec2, epaint::Mesh, pos2};
+use std::path::{Path, PathBuf};
+
+/// Image layers drawn in place of, or over, a theme's flat frame, like RocketDock and
+/// ObjectDock skins. Image paths are relative to the theme file.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Skin {
+    /// Stretched over the dock behind the items.
+    pub background: Option<SkinImage>,
+    /// Draws the frame's fill, border and shadows under the background image instead of
+    /// leaving them out.
+    pub keep_frame: bool,
+    /// A strip along the bottom of the dock that the items stand on.
+    pub shelf: Option<SkinImage>,
+    /// Drawn over the bottom of the items, e.g. a glossy floor they are mirrored in.
+    pub reflection: Option<SkinImage>,
+    /// Drawn over the whole dock above the items, e.g. a glass highlight.
+    pub overlay: Option<SkinImage>,
+    /// Drawn under running items in place of the indicator dot.
+    pub indicator: Option<SkinImage>,
+}
+
+/// An image drawn as a nine-slice: its corners keep their size, its edges stretch along one
+/// axis and its middle along both.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SkinImage {
+    pub image: PathBuf,
+    /// The borders that do not stretch, in image pixels.
+    #[serde(default)]
+    pub insets: Insets,
+    /// Height of the shelf, reflection or indicator in points; the image's height if unset.
+    pub height: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Skin {
+    /// The images of every layer.
+    pub fn images(&self) -> impl Iterator<Item = &Path> {
+        [&self.background, &self.shelf, &self.reflection, &self.overlay, &self.indicator]
+            .into_iter()
+            .flatten()
+            .map(|layer| layer.image.as_path())
+    }
+
+    /// Makes relative image paths relative to `dir`, the directory of the theme file.
+    pub fn resolve_paths(&mut self, dir: &Path) {
+        for layer in self.layers_mut() {
+            layer.image = dir.join(&layer.image);
+        }
+    }
+
+    /// Separates the parts of image paths with forward slashes, as theme files do on every
+    /// platform.
+    pub fn use_forward_slashes(&mut self) {
+        for layer in self.layers_mut() {
+            let image = layer.image.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/");
+            layer.image = PathBuf::from(image);
+        }
+    }
+
+    fn layers_mut(&mut self) -> impl Iterator<Item = &mut SkinImage> {
+        [
+            &mut self.background,
+            &mut self.shelf,
+            &mut self.reflection,
+            &mut self.overlay,
+            &mut self.indicator,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// A skin layer with its texture, ready to draw.
+#[derive(Clone)]
+pub struct SkinLayer {
+    pub texture: TextureHandle,
+    pub insets: Insets,
+    pub height: Option<f32>,
+}
+
+impl SkinLayer {
+    pub fn new(image: &SkinImage, texture: TextureHandle) -> Self {
+        Self {
+            texture,
+            insets: image.insets,
+            height: image.height,
+        }
+    }
+
+    /// The layer's height in points.
+    pub fn height(&self) -> f32 {
+        self.height.unwrap_or(self.texture.size_vec2().y)
+    }
+
+    /// The layer's size at its height, keeping the image's aspect ratio.
+    pub fn size(&self) -> Vec2 {
+        let image = self.texture.size_vec2();
+        let height = self.height();
+        egui::vec2(image.x * height / image.y.max(1.0), height)
+    }
+
+    /// The strip of `rect` this layer covers when it sits along its bottom edge.
+    pub fn bottom_strip(&self, rect: Rect) -> Rect {
+        Rect::from_min_max(pos2(rect.left(), rect.bottom() - self.height()), rect.max)
+    }
+
+    /// Stretches the layer over `rect` as a nine-slice.
+    pub fn paint(&self, painter: &Painter, rect: Rect, tint: Color32) {
+        let mut mesh = Mesh::with_texture(self.texture.id());
+        for (patch, uv) in nine_slice(rect, self.texture.size_vec2(), self.insets) {
+            mesh.add_rect_with_uv(patch, uv, tint);
+        }
+        painter.add(Shape::mesh(mesh));
+    }
+}
+
+/// Reads a skin image at its full size.
+pub fn load_skin_image(path: &Path) -> Result<ColorImage, String> {
+    let image = image::open(path).map_err(|err| format!("failed to load {}: {err}", path.display()))?;
+    let image = image.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+/// Splits `dest` into the nine patches of a nine-slice of an image of `image_size`, paired
+/// with the part of the image each shows in UV coordinates. Corners are drawn one point per
+/// image pixel, shrinking evenly when `dest` is too small for them.
+fn nine_slice(dest: Rect, image_size: Vec2, insets: Insets) -> Vec<(Rect, Rect)> {
+    let fit = |available: f32, start: f32, end: f32| {
+        let total = start + end;
+        if total > available && total > 0.0 { available / total } else { 1.0 }
+    };
+    let scale_x = fit(dest.width(), insets.left, insets.right);
+    let scale_y = fit(dest.height(), insets.top, insets.bottom);
+
+    let xs = [
+        dest.left(),
+        dest.left() + insets.left * scale_x,
+        dest.right() - insets.right * scale_x,
+        dest.right(),
+    ];
+    let ys = [
+        dest.top(),
+        dest.top() + insets.top * scale_y,
+        dest.bottom() - insets.bottom * scale_y,
+        dest.bottom(),
+    ];
+    let us = [
+        0.0,
+        insets.left / image_size.x,
+        1.0 - insets.right / image_size.x,
+        1.0,
+    ];
+    let vs = [
+        0.0,
+        insets.top / image_size.y,
+        1.0 - insets.bottom / image_size.y,
+        1.0,
+    ];
+
+    let mut patches = Vec::with_capacity(9);
+    for row in 0..3 {
+        for column in 0..3 {
+            let patch = Rect::from_min_max(pos2(xs[column], ys[row]), pos2(xs[column + 1], ys[row + 1]));
+            if patch.width() > 0.0 && patch.height() > 0.0 {
+                let uv = Rect::from_min_max(pos2(us[column], vs[row]), pos2(us[column + 1], vs[row + 1]));
+                patches.push((patch, uv));
+            }
+        }
+    }
+    patches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSETS: Insets = Insets {
+        left: 10.0,
+        top: 10.0,
+        right: 10.0,
+        bottom: 10.0,
+    };
+
+    #[test]
+    fn corners_keep_their_size() {
+        let dest = Rect::from_min_size(pos2(100.0, 0.0), egui::vec2(300.0, 60.0));
+        let patches = nine_slice(dest, egui::vec2(40.0, 40.0), INSETS);
+
+        assert_eq!(patches.len(), 9);
+        let (top_left, top_left_uv) = patches[0];
+        assert_eq!(top_left, Rect::from_min_size(pos2(100.0, 0.0), egui::vec2(10.0, 10.0)));
+        assert_eq!(top_left_uv, Rect::from_min_max(pos2(0.0, 0.0), pos2(0.25, 0.25)));
+        let (middle, middle_uv) = patches[4];
+        assert_eq!(middle, Rect::from_min_max(pos2(110.0, 10.0), pos2(390.0, 50.0)));
+        assert_eq!(middle_uv, Rect::from_min_max(pos2(0.25, 0.25), pos2(0.75, 0.75)));
+    }
+
+    #[test]
+    fn corners_shrink_to_fit() {
+        let dest = Rect::from_min_size(pos2(0.0, 0.0), egui::vec2(100.0, 10.0));
+        let patches = nine_slice(dest, egui::vec2(40.0, 40.0), INSETS);
+
+        // No room for the middle row, so the corners meet
+        assert_eq!(patches.len(), 6);
+        assert_eq!(patches[0].0, Rect::from_min_size(pos2(0.0, 0.0), egui::vec2(10.0, 5.0)));
+        assert!(patches.iter().all(|(patch, _)| dest.contains_rect(*patch)));
+    }
+
+    #[test]
+    fn no_insets_stretch_the_whole_image() {
+        let dest = Rect::from_min_size(pos2(0.0, 0.0), egui::vec2(50.0, 20.0));
+        let patches = nine_slice(dest, egui::vec2(8.0, 8.0), Insets::default());
+
+        assert_eq!(patches, [(dest, Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)))]);
+    }
+
+    #[test]
+    fn image_paths_are_relative_to_the_theme() {
+        let mut skin: Skin = toml::from_str(
+            r#"
+            background = { image = "bg.png", insets = { left = 12.0, right = 12.0 } }
+            indicator = { image = "sprites/dot.png", height = 6.0 }
+            "#,
+        )
+        .unwrap();
+        let dir = Path::new("themes").join("glass");
+        skin.resolve_paths(&dir);
+
+        let images: Vec<_> = skin.images().collect();
+        assert_eq!(images, [dir.join("bg.png"), dir.join("sprites").join("dot.png")]);
+        assert_eq!(skin.background.unwrap().insets.left, 12.0);
+    }
+}
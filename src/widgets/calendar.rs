@@ -0,0 +1,184 @@
+use eframe::egui::{self, Vec2, vec2};
+use std::time::Duration;
+
+use crate::config::WidgetPlacement;
+use crate::menu_bar::LocalTime;
+use crate::widgets::{SettingDefault, SettingSpec, Widget, WidgetKind, WidgetStyle};
+use crate::windows::get_local_time;
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+const WEEKDAY_INITIALS: [&str; 7] = ["S", "M", "T", "W", "T", "F", "S"];
+
+pub const KIND: WidgetKind = WidgetKind {
+    name: "calendar",
+    schema: &[SettingSpec {
+        name: "first_day_of_week",
+        default: SettingDefault::Text("monday"),
+    }],
+    create: |settings| {
+        let first_weekday = match settings.text("first_day_of_week").to_ascii_lowercase().as_str() {
+            "sunday" => 0,
+            "monday" => 1,
+            other => return Err(format!("`first_day_of_week` must be sunday or monday, not `{other}`")),
+        };
+
+        Ok(Box::new(Calendar {
+            first_weekday,
+            today: None,
+        }))
+    },
+};
+
+/// The current month as a grid, with today highlighted.
+struct Calendar {
+    /// The weekday in the first column, with Sunday as 0.
+    first_weekday: u16,
+    today: Option<LocalTime>,
+}
+
+impl Widget for Calendar {
+    fn default_size(&self) -> Vec2 {
+        vec2(220.0, 200.0)
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn update(&mut self) {
+        self.today = Some(get_local_time());
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, style: &WidgetStyle) {
+        let Some(today) = &self.today else {
+            return;
+        };
+
+        // Outside the desktop there is only room for the date
+        if style.placement != WidgetPlacement::Desktop {
+            ui.centered_and_justified(|ui| {
+                let label = egui::RichText::new(today.day.to_string()).strong().color(style.text_color);
+                ui.label(label);
+            });
+            return;
+        }
+
+        let month = MONTHS.get(usize::from(today.month).wrapping_sub(1)).copied().unwrap_or("");
+        ui.vertical_centered(|ui| {
+            ui.label(egui::RichText::new(format!("{month} {}", today.year)).strong().color(style.text_color));
+        });
+
+        egui::Grid::new("calendar")
+            .num_columns(7)
+            .spacing(vec2(6.0, 2.0))
+            .show(ui, |ui| {
+                for column in 0..7 {
+                    let weekday = (self.first_weekday + column) % 7;
+                    let initial = egui::RichText::new(WEEKDAY_INITIALS[usize::from(weekday)]).weak();
+                    ui.label(initial.color(style.text_color));
+                }
+                ui.end_row();
+
+                for week in month_grid(today.year, today.month, self.first_weekday) {
+                    for day in week {
+                        let text = day.map(|day| day.to_string()).unwrap_or_default();
+                        let text = if day == Some(today.day) {
+                            egui::RichText::new(text).strong().color(style.accent_color)
+                        } else {
+                            egui::RichText::new(text).color(style.text_color)
+                        };
+                        ui.label(text);
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+/// The number of days in `month` (1–12) of `year`.
+fn days_in_month(year: u16, month: u16) -> u16 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The weekday of a date in the Gregorian calendar, with Sunday as 0.
+fn weekday(year: u16, month: u16, day: u16) -> u16 {
+    // Sakamoto's method
+    const OFFSETS: [u16; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let year = if month < 3 { year - 1 } else { year };
+    let offset = OFFSETS[usize::from(month.clamp(1, 12) - 1)];
+    (year + year / 4 - year / 100 + year / 400 + offset + day) % 7
+}
+
+/// Lays out `month` of `year` as weeks starting on `first_weekday`, with `None` for the
+/// days before the 1st and after the last day.
+fn month_grid(year: u16, month: u16, first_weekday: u16) -> Vec<[Option<u16>; 7]> {
+    let leading = (weekday(year, month, 1) + 7 - first_weekday) % 7;
+    let mut days = (0..leading)
+        .map(|_| None)
+        .chain((1..=days_in_month(year, month)).map(Some))
+        .peekable();
+
+    let mut weeks = Vec::new();
+    while days.peek().is_some() {
+        let mut week = [None; 7];
+        for slot in &mut week {
+            *slot = days.next().flatten();
+        }
+        weeks.push(week);
+    }
+    weeks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::Settings;
+
+    #[test]
+    fn knows_month_lengths_and_leap_years() {
+        assert_eq!(days_in_month(2025, 10), 31);
+        assert_eq!(days_in_month(2025, 11), 30);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+    }
+
+    #[test]
+    fn finds_the_weekday_of_a_date() {
+        assert_eq!(weekday(2025, 10, 18), 6);
+        assert_eq!(weekday(2024, 2, 29), 4);
+        assert_eq!(weekday(2000, 1, 1), 6);
+    }
+
+    #[test]
+    fn grid_starts_on_the_chosen_weekday() {
+        // October 2025 starts on a Wednesday
+        let monday_first = month_grid(2025, 10, 1);
+        let sunday_first = month_grid(2025, 10, 0);
+
+        assert_eq!(monday_first[0], [None, None, Some(1), Some(2), Some(3), Some(4), Some(5)]);
+        assert_eq!(sunday_first[0][3], Some(1));
+        assert_eq!(monday_first.len(), 5);
+        assert_eq!(monday_first[4], [Some(27), Some(28), Some(29), Some(30), Some(31), None, None]);
+    }
+
+    #[test]
+    fn invalid_first_day_of_week_is_rejected() {
+        let table: toml::Table = toml::from_str("first_day_of_week = \"friday\"").unwrap();
+        let settings = Settings::new(KIND.schema, &table).unwrap();
+
+        assert!((KIND.create)(&settings).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use eframe::egui::{self, Vec2, vec2};
+use std::time::Duration;
+
+use crate::config::WidgetPlacement;
+use crate::menu_bar::{LocalTime, format_date, format_time};
+use crate::widgets::{SettingDefault, SettingSpec, Widget, WidgetKind, WidgetStyle};
+use crate::windows::get_local_time;
+
+pub const KIND: WidgetKind = WidgetKind {
+    name: "clock",
+    schema: &[
+        SettingSpec {
+            name: "seconds",
+            default: SettingDefault::Bool(false),
+        },
+        SettingSpec {
+            name: "twenty_four_hour",
+            default: SettingDefault::Bool(true),
+        },
+    ],
+    create: |settings| {
+        Ok(Box::new(Clock {
+            seconds: settings.bool("seconds"),
+            twenty_four_hour: settings.bool("twenty_four_hour"),
+            now: None,
+        }))
+    },
+};
+
+/// The time in large digits above the date.
+struct Clock {
+    seconds: bool,
+    twenty_four_hour: bool,
+    now: Option<LocalTime>,
+}
+
+impl Widget for Clock {
+    fn default_size(&self) -> Vec2 {
+        vec2(if self.seconds { 200.0 } else { 160.0 }, 90.0)
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn update(&mut self) {
+        self.now = Some(get_local_time());
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, style: &WidgetStyle) {
+        let Some(now) = &self.now else {
+            return;
+        };
+        let time = format_time(now, self.twenty_four_hour, self.seconds);
+
+        if style.placement != WidgetPlacement::Desktop {
+            let size = (ui.available_height() * 0.4).clamp(10.0, 16.0);
+            ui.centered_and_justified(|ui| {
+                ui.label(egui::RichText::new(time).size(size).color(style.text_color));
+            });
+            return;
+        }
+
+        ui.vertical_centered(|ui| {
+            ui.label(egui::RichText::new(time).size(34.0).strong().color(style.text_color));
+            ui.label(egui::RichText::new(format_date(now)).color(style.text_color));
+        });
+    }
+}
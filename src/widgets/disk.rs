@@ -0,0 +1,143 @@
+use eframe::egui::{self, Vec2, vec2};
+use std::time::Duration;
+
+use crate::config::WidgetPlacement;
+use crate::widgets::{SettingDefault, SettingSpec, Widget, WidgetKind, WidgetStyle, gauge};
+use crate::windows::get_disk_usage;
+
+pub const KIND: WidgetKind = WidgetKind {
+    name: "disk",
+    schema: &[
+        SettingSpec {
+            name: "drive",
+            default: SettingDefault::Text("C:\\"),
+        },
+        SettingSpec {
+            name: "warn_above_percent",
+            default: SettingDefault::Integer(90),
+        },
+    ],
+    create: |settings| {
+        let drive = settings.text("drive");
+        if drive.is_empty() {
+            return Err("`drive` must not be empty".to_string());
+        }
+
+        let warn_above_percent = settings.integer("warn_above_percent");
+        if !(0..=100).contains(&warn_above_percent) {
+            return Err("`warn_above_percent` must be between 0 and 100".to_string());
+        }
+
+        Ok(Box::new(Disk {
+            drive: drive.to_string(),
+            warn_above: warn_above_percent as f32 / 100.0,
+            usage: None,
+        }))
+    },
+};
+
+/// A volume's capacity, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DiskUsage {
+    pub total: u64,
+    pub free: u64,
+}
+
+/// Formats a byte count with a binary unit, e.g. `"118.3 GB"`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(format_bytes(1536), "1.5 KB");
+/// ```
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Used and free space on one drive.
+struct Disk {
+    drive: String,
+    /// The used fraction above which the gauge is drawn in the warning colour.
+    warn_above: f32,
+    usage: Option<DiskUsage>,
+}
+
+impl Widget for Disk {
+    fn default_size(&self) -> Vec2 {
+        vec2(200.0, 50.0)
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn update(&mut self) {
+        self.usage = get_disk_usage(&self.drive);
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, style: &WidgetStyle) {
+        let Some(usage) = &self.usage else {
+            return;
+        };
+        let used = usage.total.saturating_sub(usage.free);
+        let fraction = if usage.total == 0 {
+            0.0
+        } else {
+            used as f32 / usage.total as f32
+        };
+
+        if style.placement != WidgetPlacement::Desktop {
+            ui.centered_and_justified(|ui| {
+                let text = format!("{} {}", self.drive.trim_end_matches('\\'), format_bytes(usage.free));
+                ui.label(egui::RichText::new(text).small().color(style.text_color));
+            });
+            return;
+        }
+
+        let free = format!("{} free", format_bytes(usage.free));
+        let style = if fraction > self.warn_above {
+            WidgetStyle {
+                accent_color: style.warning_color,
+                ..*style
+            }
+        } else {
+            *style
+        };
+        gauge(ui, &style, &self.drive, &free, fraction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::Settings;
+
+    #[test]
+    fn formats_bytes_with_binary_units() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 << 30), "5.0 GB");
+        assert_eq!(format_bytes(3 << 50), "3072.0 TB");
+    }
+
+    #[test]
+    fn warning_threshold_must_be_a_percentage() {
+        let table: toml::Table = toml::from_str("warn_above_percent = 150").unwrap();
+        let settings = Settings::new(KIND.schema, &table).unwrap();
+
+        assert!((KIND.create)(&settings).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+mod calendar;
+mod clock;
+mod disk;
+mod system;
+
+pub use disk::DiskUsage;
+pub use system::{CpuTimes, MemoryUsage};
+
+use eframe::egui::{self, Color32, Pos2, Rect, Vec2, pos2, vec2};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::{WidgetConfig, WidgetPlacement};
+
+/// Gap between desktop widgets, and between them and the edge of the work area.
+const DESKTOP_MARGIN: f32 = 16.0;
+
+/// Bounds of a configured `update_interval_secs`; a day is as good as never for a widget.
+const MIN_UPDATE_INTERVAL_SECS: f32 = 0.1;
+const MAX_UPDATE_INTERVAL_SECS: f32 = 24.0 * 60.0 * 60.0;
+
+/// A small panel showing live information, drawn with egui.
+///
+/// A widget is created from its validated settings, refreshed by `update` once straight away
+/// and then every `update_interval`, and drawn by `ui` on every frame in between.
+pub trait Widget {
+    /// The size the widget opens at on the desktop, in points. Dock and menu bar widgets
+    /// get the height of their slot and use the width as a hint.
+    fn default_size(&self) -> Vec2;
+
+    /// How often `update` runs, unless the config overrides it.
+    fn update_interval(&self) -> Duration;
+
+    /// Refreshes the data the widget shows.
+    fn update(&mut self);
+
+    /// Draws the widget into `ui`, which spans the space the widget was given.
+    fn ui(&self, ui: &mut egui::Ui, style: &WidgetStyle);
+}
+
+/// Colours and placement a widget is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WidgetStyle {
+    pub placement: WidgetPlacement,
+    pub text_color: Color32,
+    /// Used for gauges and highlights, such as today's date.
+    pub accent_color: Color32,
+    /// Used for gauges past a warning threshold, such as a nearly full disk.
+    pub warning_color: Color32,
+}
+
+/// A setting a widget accepts, and its value when the config leaves it out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SettingSpec {
+    pub name: &'static str,
+    pub default: SettingDefault,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingDefault {
+    Bool(bool),
+    Integer(i64),
+    Text(&'static str),
+}
+
+/// A built-in widget: its name in the config, the settings it accepts and its constructor.
+pub struct WidgetKind {
+    pub name: &'static str,
+    pub schema: &'static [SettingSpec],
+    pub create: fn(&Settings) -> Result<Box<dyn Widget>, String>,
+}
+
+pub const BUILT_IN_WIDGETS: [WidgetKind; 4] = [
+    clock::KIND,
+    calendar::KIND,
+    system::KIND,
+    disk::KIND,
+];
+
+/// A widget's settings, checked against its schema. Settings the config leaves out read as
+/// their defaults.
+pub struct Settings<'a> {
+    schema: &'static [SettingSpec],
+    table: &'a toml::Table,
+}
+
+impl<'a> Settings<'a> {
+    /// Checks that `table` only holds settings from `schema`, each of the declared type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let table: toml::Table = toml::from_str("seconds = 1").unwrap();
+    /// assert!(Settings::new(clock::KIND.schema, &table).is_err());
+    /// ```
+    pub fn new(schema: &'static [SettingSpec], table: &'a toml::Table) -> Result<Self, String> {
+        for (name, value) in table {
+            let spec = schema
+                .iter()
+                .find(|spec| spec.name == name)
+                .ok_or_else(|| format!("unknown setting `{name}`"))?;
+
+            let expected = match (spec.default, value) {
+                (SettingDefault::Bool(_), toml::Value::Boolean(_))
+                | (SettingDefault::Integer(_), toml::Value::Integer(_))
+                | (SettingDefault::Text(_), toml::Value::String(_)) => continue,
+                (SettingDefault::Bool(_), _) => "a boolean",
+                (SettingDefault::Integer(_), _) => "an integer",
+                (SettingDefault::Text(_), _) => "a string",
+            };
+            return Err(format!("`{name}` must be {expected}"));
+        }
+
+        Ok(Self { schema, table })
+    }
+
+    pub fn bool(&self, name: &str) -> bool {
+        match (self.table.get(name), self.default(name)) {
+            (Some(toml::Value::Boolean(value)), _) => *value,
+            (_, Some(SettingDefault::Bool(value))) => value,
+            _ => false,
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> i64 {
+        match (self.table.get(name), self.default(name)) {
+            (Some(toml::Value::Integer(value)), _) => *value,
+            (_, Some(SettingDefault::Integer(value))) => value,
+            _ => 0,
+        }
+    }
+
+    pub fn text(&self, name: &str) -> &str {
+        match (self.table.get(name), self.default(name)) {
+            (Some(toml::Value::String(value)), _) => value,
+            (_, Some(SettingDefault::Text(value))) => value,
+            _ => "",
+        }
+    }
+
+    fn default(&self, name: &str) -> Option<SettingDefault> {
+        self.schema
+            .iter()
+            .find(|spec| spec.name == name)
+            .map(|spec| spec.default)
+    }
+}
+
+/// Creates the widget described by `config`.
+pub fn create_widget(config: &WidgetConfig) -> Result<Box<dyn Widget>, String> {
+    let kind = BUILT_IN_WIDGETS
+        .iter()
+        .find(|kind| kind.name == config.kind)
+        .ok_or_else(|| format!("unknown widget `{}`", config.kind))?;
+    let settings = Settings::new(kind.schema, &config.settings)?;
+
+    (kind.create)(&settings)
+}
+
+/// Where a desktop widget's window is and how big it is, in points.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WidgetGeometry {
+    pub pos: Pos2,
+    pub size: Vec2,
+}
+
+/// A running widget.
+pub struct WidgetInstance {
+    pub id: String,
+    pub placement: WidgetPlacement,
+    pub widget: Box<dyn Widget>,
+    interval: Duration,
+    next_update: Instant,
+}
+
+/// The configured widgets, their refresh schedule and the saved geometry of desktop widgets.
+#[derive(Default)]
+pub struct WidgetHost {
+    pub instances: Vec<WidgetInstance>,
+    /// Saved positions and sizes, keyed by widget id. Kept for widgets that are no longer
+    /// configured so they reappear in place if they come back.
+    pub geometry: HashMap<String, WidgetGeometry>,
+}
+
+impl WidgetHost {
+    /// Creates the widgets in `configs`. Invalid widgets are reported and skipped.
+    pub fn new(configs: &[WidgetConfig]) -> Self {
+        let now = Instant::now();
+        let mut instances = Vec::new();
+
+        for (config, id) in configs.iter().zip(assign_ids(configs)) {
+            match create_widget(config) {
+                Ok(widget) => {
+                    let interval = config
+                        .update_interval_secs
+                        .and_then(|secs| {
+                            let secs = secs.clamp(MIN_UPDATE_INTERVAL_SECS, MAX_UPDATE_INTERVAL_SECS);
+                            Duration::try_from_secs_f32(secs).ok()
+                        })
+                        .unwrap_or_else(|| widget.update_interval());
+
+                    instances.push(WidgetInstance {
+                        id,
+                        placement: config.placement,
+                        widget,
+                        interval,
+                        next_update: now,
+                    });
+                }
+                Err(err) => eprintln!("Ignoring widget `{id}`: {err}"),
+            }
+        }
+
+        Self {
+            instances,
+            geometry: HashMap::new(),
+        }
+    }
+
+    /// Adds a widget that does not come from the config, such as one a plugin created.
+    pub fn add(&mut self, id: String, placement: WidgetPlacement, widget: Box<dyn Widget>) {
+        self.instances.push(WidgetInstance {
+            id,
+            placement,
+            interval: widget.update_interval(),
+            widget,
+            next_update: Instant::now(),
+        });
+    }
+
+    /// Updates every widget that is due at `now` and returns how long until the next one is.
+    pub fn tick(&mut self, now: Instant) -> Option<Duration> {
+        for instance in &mut self.instances {
+            if instance.next_update <= now {
+                instance.widget.update();
+                instance.next_update = now + instance.interval;
+            }
+        }
+
+        self.instances
+            .iter()
+            .map(|instance| instance.next_update.saturating_duration_since(now))
+            .min()
+    }
+
+    /// The widgets shown at `placement`, in config order.
+    pub fn placed(&self, placement: WidgetPlacement) -> impl Iterator<Item = &WidgetInstance> {
+        self.instances
+            .iter()
+            .filter(move |instance| instance.placement == placement)
+    }
+
+    /// Where each desktop widget opens: its saved geometry, or stacked down the right edge of
+    /// `work_area` (in logical points) at its default size.
+    pub fn desktop_geometry(&self, work_area: Rect) -> Vec<(&WidgetInstance, WidgetGeometry)> {
+        let mut next_top = work_area.top() + DESKTOP_MARGIN;
+
+        self.placed(WidgetPlacement::Desktop)
+            .map(|instance| {
+                let geometry = self.geometry.get(&instance.id).copied().unwrap_or_else(|| {
+                    let size = instance.widget.default_size();
+                    let pos = pos2(work_area.right() - DESKTOP_MARGIN - size.x, next_top);
+                    next_top += size.y + DESKTOP_MARGIN;
+                    WidgetGeometry { pos, size }
+                });
+                (instance, geometry)
+            })
+            .collect()
+    }
+}
+
+/// Gives every widget a unique id: its configured id, or its kind with `-2`, `-3`, ... added
+/// to repeats.
+fn assign_ids(configs: &[WidgetConfig]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    configs
+        .iter()
+        .map(|config| {
+            let base = config.id.clone().unwrap_or_else(|| config.kind.clone());
+            let count = seen.entry(base.clone()).or_default();
+            *count += 1;
+            match *count {
+                1 => base,
+                n => format!("{base}-{n}"),
+            }
+        })
+        .collect()
+}
+
+/// Draws a horizontal gauge filled to `fraction`, with `label` on the left and `value` on
+/// the right above it.
+fn gauge(ui: &mut egui::Ui, style: &WidgetStyle, label: &str, value: &str, fraction: f32) {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new(label).color(style.text_color));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(egui::RichText::new(value).color(style.text_color).strong());
+        });
+    });
+
+    let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), 6.0), egui::Sense::hover());
+    let rounding = egui::Rounding::same(3.0);
+    ui.painter().rect_filled(rect, rounding, style.text_color.gamma_multiply(0.2));
+
+    let filled = Rect::from_min_size(rect.min, vec2(rect.width() * fraction.clamp(0.0, 1.0), rect.height()));
+    ui.painter().rect_filled(filled, rounding, style.accent_color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &[SettingSpec] = &[
+        SettingSpec {
+            name: "seconds",
+            default: SettingDefault::Bool(false),
+        },
+        SettingSpec {
+            name: "count",
+            default: SettingDefault::Integer(3),
+        },
+        SettingSpec {
+            name: "drive",
+            default: SettingDefault::Text("C:\\"),
+        },
+    ];
+
+    fn widget(kind: &str, id: Option<&str>) -> WidgetConfig {
+        WidgetConfig {
+            kind: kind.to_string(),
+            id: id.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn settings_fall_back_to_their_defaults() {
+        let table: toml::Table = toml::from_str("count = 7").unwrap();
+        let settings = Settings::new(SCHEMA, &table).unwrap();
+
+        assert_eq!(settings.integer("count"), 7);
+        assert!(!settings.bool("seconds"));
+        assert_eq!(settings.text("drive"), "C:\\");
+    }
+
+    #[test]
+    fn settings_are_checked_against_the_schema() {
+        let unknown: toml::Table = toml::from_str("colour = 1").unwrap();
+        let mistyped: toml::Table = toml::from_str("seconds = \"yes\"").unwrap();
+
+        assert_eq!(Settings::new(SCHEMA, &unknown).err().unwrap(), "unknown setting `colour`");
+        assert_eq!(Settings::new(SCHEMA, &mistyped).err().unwrap(), "`seconds` must be a boolean");
+    }
+
+    #[test]
+    fn every_built_in_widget_accepts_its_defaults() {
+        for kind in &BUILT_IN_WIDGETS {
+            let widget = create_widget(&widget(kind.name, None));
+
+            assert!(widget.is_ok(), "{} failed to start", kind.name);
+        }
+        assert!(create_widget(&widget("weather", None)).is_err());
+    }
+
+    #[test]
+    fn repeated_kinds_get_numbered_ids() {
+        let configs = [
+            widget("clock", None),
+            widget("disk", None),
+            widget("clock", None),
+            widget("disk", Some("data")),
+        ];
+
+        assert_eq!(assign_ids(&configs), ["clock", "disk", "clock-2", "data"]);
+    }
+
+    #[test]
+    fn invalid_widgets_are_skipped() {
+        let host = WidgetHost::new(&[widget("clock", None), widget("weather", None)]);
+
+        assert_eq!(host.instances.len(), 1);
+        assert_eq!(host.instances[0].id, "clock");
+    }
+
+    /// Counts its updates instead of reading the system.
+    struct Counter(usize);
+
+    impl Widget for Counter {
+        fn default_size(&self) -> Vec2 {
+            vec2(100.0, 50.0)
+        }
+
+        fn update_interval(&self) -> Duration {
+            Duration::from_secs(5)
+        }
+
+        fn update(&mut self) {
+            self.0 += 1;
+        }
+
+        fn ui(&self, _ui: &mut egui::Ui, _style: &WidgetStyle) {}
+    }
+
+    #[test]
+    fn widgets_update_on_their_interval() {
+        let start = Instant::now();
+        let mut host = WidgetHost::default();
+        host.instances.push(WidgetInstance {
+            id: "counter".to_string(),
+            placement: WidgetPlacement::Desktop,
+            widget: Box::new(Counter(0)),
+            interval: Duration::from_secs(5),
+            next_update: start,
+        });
+
+        assert_eq!(host.tick(start), Some(Duration::from_secs(5)));
+        assert_eq!(host.tick(start + Duration::from_secs(2)), Some(Duration::from_secs(3)));
+        assert_eq!(host.tick(start + Duration::from_secs(5)), Some(Duration::from_secs(5)));
+        assert_eq!(host.instances[0].next_update, start + Duration::from_secs(10));
+    }
+
+    #[test]
+    fn configured_interval_overrides_the_widget() {
+        let mut config = widget("clock", None);
+        config.update_interval_secs = Some(30.0);
+        let host = WidgetHost::new(&[config, widget("clock", None)]);
+
+        assert_eq!(host.instances[0].interval, Duration::from_secs(30));
+        assert_eq!(host.instances[1].interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn configured_intervals_are_clamped_to_a_finite_range() {
+        let intervals = [0.0, f32::INFINITY, f32::NAN].map(|secs| {
+            let mut config = widget("clock", None);
+            config.update_interval_secs = Some(secs);
+            config
+        });
+        let host = WidgetHost::new(&intervals);
+
+        assert_eq!(host.instances[0].interval.as_millis(), 100);
+        assert_eq!(host.instances[1].interval, Duration::from_secs(24 * 60 * 60));
+        assert_eq!(host.instances[2].interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn desktop_widgets_stack_down_the_right_edge_until_moved() {
+        let mut host = WidgetHost::new(&[widget("clock", None), widget("calendar", None)]);
+        let work_area = Rect::from_min_size(Pos2::ZERO, vec2(1920.0, 1040.0));
+
+        let placed = host.desktop_geometry(work_area);
+        let clock = placed[0].1;
+        let calendar = placed[1].1;
+        assert_eq!(clock.pos.x + clock.size.x, 1920.0 - DESKTOP_MARGIN);
+        assert_eq!(calendar.pos.y, clock.pos.y + clock.size.y + DESKTOP_MARGIN);
+
+        let moved = WidgetGeometry {
+            pos: pos2(100.0, 200.0),
+            size: vec2(300.0, 150.0),
+        };
+        host.geometry.insert("clock".to_string(), moved);
+        let placed = host.desktop_geometry(work_area);
+        assert_eq!(placed[0].1, moved);
+        assert_eq!(placed[1].1.pos.y, DESKTOP_MARGIN);
+    }
+}
@@ -0,0 +1,160 @@
+use eframe::egui::{self, Vec2, vec2};
+use std::time::Duration;
+
+use crate::config::WidgetPlacement;
+use crate::widgets::{SettingDefault, SettingSpec, Widget, WidgetKind, WidgetStyle, disk::format_bytes, gauge};
+use crate::windows::{get_cpu_times, get_memory_usage};
+
+pub const KIND: WidgetKind = WidgetKind {
+    name: "system",
+    schema: &[SettingSpec {
+        name: "show_memory",
+        default: SettingDefault::Bool(true),
+    }],
+    create: |settings| {
+        Ok(Box::new(SystemMonitor {
+            show_memory: settings.bool("show_memory"),
+            last_sample: None,
+            cpu: 0.0,
+            memory: None,
+        }))
+    },
+};
+
+/// Cumulative processor time since boot, summed over all cores, in any fixed unit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CpuTimes {
+    pub idle: u64,
+    /// Kernel time, which includes idle time.
+    pub kernel: u64,
+    pub user: u64,
+}
+
+/// Physical memory, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryUsage {
+    pub total: u64,
+    pub available: u64,
+}
+
+impl MemoryUsage {
+    pub fn used_fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.total.saturating_sub(self.available) as f32 / self.total as f32
+    }
+}
+
+/// The share of processor time spent busy between two samples, from 0 to 1.
+///
+/// # Examples
+///
+/// ```
+/// let before = CpuTimes { idle: 100, kernel: 150, user: 50 };
+/// let after = CpuTimes { idle: 175, kernel: 250, user: 150 };
+/// assert_eq!(cpu_usage(&before, &after), 0.625);
+/// ```
+pub fn cpu_usage(before: &CpuTimes, after: &CpuTimes) -> f32 {
+    let idle = after.idle.saturating_sub(before.idle);
+    let total = after.kernel.saturating_sub(before.kernel) + after.user.saturating_sub(before.user);
+    if total == 0 {
+        return 0.0;
+    }
+    1.0 - idle.min(total) as f32 / total as f32
+}
+
+/// Processor load and memory use.
+struct SystemMonitor {
+    show_memory: bool,
+    last_sample: Option<CpuTimes>,
+    cpu: f32,
+    memory: Option<MemoryUsage>,
+}
+
+impl Widget for SystemMonitor {
+    fn default_size(&self) -> Vec2 {
+        vec2(200.0, if self.show_memory { 90.0 } else { 50.0 })
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn update(&mut self) {
+        if let Some(sample) = get_cpu_times() {
+            if let Some(last) = &self.last_sample {
+                self.cpu = cpu_usage(last, &sample);
+            }
+            self.last_sample = Some(sample);
+        }
+        if self.show_memory {
+            self.memory = get_memory_usage();
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, style: &WidgetStyle) {
+        let cpu = format!("{:.0}%", self.cpu * 100.0);
+
+        if style.placement != WidgetPlacement::Desktop {
+            let text = match &self.memory {
+                Some(memory) => format!("CPU {cpu} · RAM {:.0}%", memory.used_fraction() * 100.0),
+                None => format!("CPU {cpu}"),
+            };
+            ui.centered_and_justified(|ui| {
+                ui.label(egui::RichText::new(text).small().color(style.text_color));
+            });
+            return;
+        }
+
+        gauge(ui, style, "CPU", &cpu, self.cpu);
+        if let Some(memory) = &self.memory {
+            ui.add_space(6.0);
+            let used = format!(
+                "{} of {}",
+                format_bytes(memory.total.saturating_sub(memory.available)),
+                format_bytes(memory.total)
+            );
+            gauge(ui, style, "Memory", &used, memory.used_fraction());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_usage_is_the_busy_share_of_elapsed_time() {
+        let before = CpuTimes {
+            idle: 1_000,
+            kernel: 1_500,
+            user: 500,
+        };
+        let idle = CpuTimes {
+            idle: 2_000,
+            kernel: 2_500,
+            ..before
+        };
+        let busy = CpuTimes {
+            kernel: 1_600,
+            user: 1_400,
+            ..before
+        };
+
+        assert_eq!(cpu_usage(&before, &idle), 0.0);
+        assert_eq!(cpu_usage(&before, &busy), 1.0);
+        assert_eq!(cpu_usage(&before, &before), 0.0);
+    }
+
+    #[test]
+    fn memory_use_excludes_available_memory() {
+        let memory = MemoryUsage {
+            total: 16 << 30,
+            available: 4 << 30,
+        };
+
+        assert_eq!(memory.used_fraction(), 0.75);
+        assert_eq!(MemoryUsage::default().used_fraction(), 0.0);
+    }
+}
@@ -0,0 +1,50 @@
+use windows::Win32::Foundation::{HWND, RECT};
+use windows::Win32::UI::Shell::{
+    ABE_TOP, ABM_NEW, ABM_QUERYPOS, ABM_REMOVE, ABM_SETPOS, APPBARDATA, SHAppBarMessage,
+};
+use windows::Win32::UI::WindowsAndMessaging::WM_APP;
+
+/// Message the shell would send with app bar notifications; FerroDock re-reads the work area
+/// through `WM_SETTINGCHANGE` instead, so it is never handled.
+const APPBAR_CALLBACK: u32 = WM_APP + 1;
+
+/// Registers `hwnd` as an app bar along the top edge and reserves `rect` (physical pixels),
+/// so the work area, and therefore maximized windows, start below it.
+///
+/// Returns the strip the shell granted, which lies below other top app bars and is where the
+/// window belongs. Registering an already registered window only updates its position.
+pub fn reserve_top_edge(hwnd: HWND, rect: RECT) -> RECT {
+    let mut data = APPBARDATA {
+        cbSize: std::mem::size_of::<APPBARDATA>() as u32,
+        hWnd: hwnd,
+        uCallbackMessage: APPBAR_CALLBACK,
+        uEdge: ABE_TOP,
+        rc: rect,
+        ..Default::default()
+    };
+
+    unsafe {
+        SHAppBarMessage(ABM_NEW, &mut data);
+
+        // The shell may move the strip below other top app bars
+        let height = rect.bottom - rect.top;
+        SHAppBarMessage(ABM_QUERYPOS, &mut data);
+        data.rc.bottom = data.rc.top + height;
+        SHAppBarMessage(ABM_SETPOS, &mut data);
+    }
+
+    data.rc
+}
+
+/// Releases the strip reserved by `reserve_top_edge`.
+pub fn release_top_edge(hwnd: HWND) {
+    let mut data = APPBARDATA {
+        cbSize: std::mem::size_of::<APPBARDATA>() as u32,
+        hWnd: hwnd,
+        ..Default::default()
+    };
+
+    unsafe {
+        SHAppBarMessage(ABM_REMOVE, &mut data);
+    }
+}
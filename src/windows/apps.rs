@@ -1,9 +1,5 @@
-use windows::Win32::Foundation::{BOOL, CloseHandle, HMODULE, HWND, LPARAM};
-use windows::Win32::System::ProcessStatus::GetModuleFileNameExW;
-use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
-use windows::Win32::UI::WindowsAndMessaging::{
-    EnumChildWindows, GetClassNameW, GetWindowThreadProcessId,
-};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{EnumChildWindows, GetWindowThreadProcessId};
 
 /// Determines whether a path identifies a UWP application.
 ///
@@ -44,36 +40,29 @@ pub fn is_uwp_app(path: &str) -> bool {
     false
 }
 
-/// Retrieves the executable path of a UWP process associated with an application frame window.
+/// Retrieves the ID of the UWP process hosted by an application frame window.
 ///
 /// # Examples
 ///
 /// ```no_run
-/// let path = get_uwp_real_process_path(HWND(0));
-/// assert!(path.is_none() || path.is_some());
+/// let pid = get_uwp_child_process_id(HWND(0));
+/// assert!(pid.is_none() || pid.is_some());
 /// ```
 ///
 /// # Returns
 ///
-/// The child process executable path when `hwnd` identifies an application frame
-/// window and a process path can be retrieved; otherwise, `None`.
+/// The ID of the first child window's process that differs from the frame host when
+/// `hwnd` identifies an application frame window; otherwise, `None`.
 ///
 /// # Parameters
 ///
 /// * `hwnd` - Handle to the application frame window to inspect.
-pub fn get_uwp_real_process_path(hwnd: HWND) -> Option<String> {
-    unsafe {
-        let mut class_name: [u16; 256] = [0; 256];
-        let len = GetClassNameW(hwnd, &mut class_name);
-        if len == 0 {
-            return None;
-        }
-
-        let name = String::from_utf16_lossy(&class_name[..len as usize]);
-        if name != "ApplicationFrameWindow" {
-            return None;
-        }
+pub fn get_uwp_child_process_id(hwnd: HWND) -> Option<u32> {
+    if crate::windows::get_window_class(hwnd) != "ApplicationFrameWindow" {
+        return None;
+    }
 
+    unsafe {
         let afh_pid = {
             let mut pid: u32 = 0;
             GetWindowThreadProcessId(hwnd, Some(&mut pid));
@@ -82,13 +71,10 @@ pub fn get_uwp_real_process_path(hwnd: HWND) -> Option<String> {
 
         struct TargetData {
             afh_pid: u32,
-            path: Option<String>,
+            pid: Option<u32>,
         }
 
-        let mut data = TargetData {
-            afh_pid,
-            path: None,
-        };
+        let mut data = TargetData { afh_pid, pid: None };
 
         extern "system" fn check_child(hwnd: HWND, lparam: LPARAM) -> BOOL {
             unsafe {
@@ -97,17 +83,8 @@ pub fn get_uwp_real_process_path(hwnd: HWND) -> Option<String> {
                 GetWindowThreadProcessId(hwnd, Some(&mut pid));
 
                 if pid != 0 && pid != data.afh_pid {
-                    if let Ok(handle) = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid) {
-                        let mut buf: [u16; 260] = [0; 260];
-                        let len = GetModuleFileNameExW(handle, HMODULE(0), &mut buf);
-                        let _ = CloseHandle(handle);
-
-                        if len > 0 {
-                            let child_path = String::from_utf16_lossy(&buf[..len as usize]);
-                            data.path = Some(child_path);
-                            return false.into();
-                        }
-                    }
+                    data.pid = Some(pid);
+                    return false.into();
                 }
                 true.into()
             }
@@ -119,29 +96,15 @@ pub fn get_uwp_real_process_path(hwnd: HWND) -> Option<String> {
             LPARAM(&mut data as *mut _ as isize),
         );
 
-        data.path
+        data.pid
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::is_uwp_app;
+    use crate::test_support::unique_temp_dir;
     use std::fs;
-    use std::path::PathBuf;
-
-    /// Creates a fresh, uniquely-named temporary directory for a test so
-    /// that filesystem-walking assertions in `is_uwp_app` are deterministic
-    /// and independent from other tests running in parallel.
-    fn unique_temp_dir(label: &str) -> PathBuf {
-        let pid = std::process::id();
-        let nanos = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        let dir = std::env::temp_dir().join(format!("ferro_dock_test_{label}_{pid}_{nanos}"));
-        fs::create_dir_all(&dir).expect("failed to create temp test dir");
-        dir
-    }
 
     #[test]
     fn detects_windowsapps_substring_case_insensitively() {
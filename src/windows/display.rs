@@ -0,0 +1,64 @@
+use eframe::egui::{Rect, pos2};
+use windows::Win32::Foundation::{POINT, RECT};
+use windows::Win32::Graphics::Gdi::{
+    GetDC, GetDeviceCaps, GetMonitorInfoW, LOGPIXELSX, MONITOR_DEFAULTTOPRIMARY, MONITORINFO,
+    MonitorFromPoint, ReleaseDC,
+};
+use windows::Win32::UI::HiDpi::{
+    DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, GetDpiForMonitor, MDT_EFFECTIVE_DPI,
+    SetProcessDpiAwarenessContext,
+};
+
+use crate::layout::MonitorGeometry;
+
+/// Opts the process into per-monitor DPI awareness.
+///
+/// Must run before any window is created, including the shell hook window, so that every
+/// thread sees physical pixels and receives `WM_DPICHANGED` when the scaling changes.
+/// Failure (e.g. awareness already set by a manifest) is harmless.
+pub fn enable_per_monitor_dpi_awareness() {
+    let _ = unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+}
+
+/// Queries the bounds, work area and effective DPI of the primary monitor.
+pub fn get_primary_monitor() -> MonitorGeometry {
+    // The primary monitor always contains the origin
+    let monitor = unsafe { MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY) };
+
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let _ = unsafe { GetMonitorInfoW(monitor, &mut info) };
+
+    let (mut dpi_x, mut dpi_y) = (0u32, 0u32);
+    let dpi = match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+        Ok(()) if dpi_x > 0 => dpi_x as f32,
+        _ => system_dpi(),
+    };
+
+    MonitorGeometry {
+        bounds: rect_from_win32(info.rcMonitor),
+        work_area: rect_from_win32(info.rcWork),
+        dpi,
+    }
+}
+
+/// The system-wide DPI, used when the per-monitor DPI is unavailable.
+fn system_dpi() -> f32 {
+    let hdc = unsafe { GetDC(None) };
+    if hdc.is_invalid() {
+        return 96.0;
+    }
+
+    let dpi = unsafe { GetDeviceCaps(hdc, LOGPIXELSX) } as f32;
+    let _ = unsafe { ReleaseDC(None, hdc) };
+    dpi
+}
+
+fn rect_from_win32(rect: RECT) -> Rect {
+    Rect::from_min_max(
+        pos2(rect.left as f32, rect.top as f32),
+        pos2(rect.right as f32, rect.bottom as f32),
+    )
+}
@@ -2,29 +2,36 @@ use std::path::Path;
 
 use windows::core::PCWSTR;
 use windows::Win32::{
-    Foundation::{CloseHandle, HMODULE, HWND, LPARAM, WPARAM},
+    Foundation::{HWND, LPARAM, WPARAM},
     Graphics::Gdi::{
         BI_RGB, BITMAP, BITMAPINFO, BITMAPINFOHEADER, CreateCompatibleDC, DIB_RGB_COLORS, DeleteDC,
         DeleteObject, GetDIBits, GetObjectW, HBITMAP,
     },
-    System::{
-        ProcessStatus::GetModuleFileNameExW,
-        Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ},
-    },
     UI::Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON},
     UI::WindowsAndMessaging::{
         CopyIcon, DestroyIcon, GCLP_HICON, GetClassLongPtrW, GetIconInfo,
-        GetWindowThreadProcessId, HICON, ICON_BIG, ICONINFO, SendMessageW, WM_GETICON,
+        GetWindowThreadProcessId, HICON, ICON_BIG, ICONINFO, IsWindowVisible, PrivateExtractIconsW,
+        SendMessageW, WM_GETICON,
     },
 };
 
-use crate::windows::is_dock_worthy_window;
+use crate::rules::{RuleSet, WindowFacts};
+use crate::windows::{IdentityError, ProcessResolver, fallback_identity, get_window_facts};
 
 #[derive(Clone, PartialEq)]
 pub struct DockIcon {
     pub path: String,
+    /// Display name set by a `rename` window rule.
+    pub name: Option<String>,
+    /// Icon set by an `icon` window rule.
+    pub icon: Option<String>,
+    /// Package identity and window class of the first window, which icon packs match on.
+    pub aumid: Option<String>,
+    pub class: Option<String>,
     pub hicon: HICON,
     pub hwnd: HWND,
+    /// Every dock-worthy window belonging to this application, in enumeration order.
+    pub windows: Vec<HWND>,
 }
 
 pub fn hicon_to_color_image(hicon: HICON) -> Option<eframe::egui::ColorImage> {
@@ -119,7 +126,9 @@ pub fn hicon_to_color_image(hicon: HICON) -> Option<eframe::egui::ColorImage> {
     })
 }
 
-pub fn get_uwp_icon(exe_path: &str) -> Option<eframe::egui::ColorImage> {
+/// Finds the logo of the packaged app at `exe_path`, in its variant for a dark or light dock.
+/// The logo may be an SVG.
+pub fn get_uwp_icon_path(exe_path: &str, dark: bool) -> Option<std::path::PathBuf> {
     let mut current_dir = Path::new(exe_path).parent();
 
     while let Some(dir) = current_dir {
@@ -127,10 +136,8 @@ pub fn get_uwp_icon(exe_path: &str) -> Option<eframe::egui::ColorImage> {
         if manifest_path.exists() {
             if let Ok(manifest_content) = std::fs::read_to_string(&manifest_path) {
                 if let Some(icon_relative) = parse_logo_from_manifest(&manifest_content) {
-                    if let Some(icon_path) = find_best_icon(dir, &icon_relative) {
-                        if let Some(img) = load_png_as_color_image(&icon_path) {
-                            return Some(img);
-                        }
+                    if let Some(icon_path) = find_best_icon(dir, &icon_relative, dark) {
+                        return Some(icon_path);
                     }
                 }
             }
@@ -141,6 +148,27 @@ pub fn get_uwp_icon(exe_path: &str) -> Option<eframe::egui::ColorImage> {
     None
 }
 
+/// The tile colour the packaged app at `exe_path` declares for its logo, or `None` when it
+/// has none or asks for a transparent one.
+pub fn get_uwp_background_color(exe_path: &str) -> Option<eframe::egui::Color32> {
+    let manifest_path = Path::new(exe_path)
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("AppxManifest.xml"))
+        .find(|path| path.exists())?;
+    parse_background_color_from_manifest(&std::fs::read_to_string(manifest_path).ok()?)
+}
+
+/// Reads a `BackgroundColor="#RRGGBB"` attribute; named colours such as `transparent` are
+/// `None`.
+fn parse_background_color_from_manifest(xml: &str) -> Option<eframe::egui::Color32> {
+    let start = xml.find("BackgroundColor=\"")? + "BackgroundColor=\"".len();
+    let value = &xml[start..start + xml[start..].find('"')?];
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(eframe::egui::Color32::from_rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
 fn parse_logo_from_manifest(xml: &str) -> Option<String> {
     for attr in ["Square150x150Logo", "Square44x44Logo", "Logo"] {
         if let Some(start) = xml.find(&format!("{}=\"", attr)) {
@@ -154,38 +182,58 @@ fn parse_logo_from_manifest(xml: &str) -> Option<String> {
     None
 }
 
-fn find_best_icon(package_dir: &Path, relative_path: &str) -> Option<std::path::PathBuf> {
+/// Finds the file of a manifest logo, which packages usually store only as qualified variants
+/// such as `Logo.scale-200.png` or `Logo.targetsize-48_altform-unplated.png`.
+fn find_best_icon(package_dir: &Path, relative_path: &str, dark: bool) -> Option<std::path::PathBuf> {
     let base_path = package_dir.join(relative_path);
-
-    if base_path.exists() {
-        return Some(base_path);
-    }
-
     let stem = base_path.file_stem()?.to_str()?;
     let parent = base_path.parent()?;
 
-    for scale in ["400", "300", "200", "150", "125", "100"] {
-        let scaled_name = format!("{}.scale-{}.png", stem, scale);
-        let scaled_path = parent.join(&scaled_name);
+    std::fs::read_dir(parent)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|name| Some((rank_asset(&name, stem, dark)?, name)))
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, name)| parent.join(name))
+}
 
-        if scaled_path.exists() {
-            return Some(scaled_path);
-        }
-    }
+/// Ranks `file_name` as a variant of the logo `stem`: first by whether it is drawn for the
+/// dock's light or dark appearance, then by its size in pixels, with SVGs above any size.
+/// Other files and high-contrast variants are `None`.
+fn rank_asset(file_name: &str, stem: &str, dark: bool) -> Option<(u8, u32)> {
+    let file_name = file_name.to_ascii_lowercase();
+    let rest = file_name.strip_prefix(&stem.to_ascii_lowercase())?;
+    let (rest, scalable) = match rest.strip_suffix(".png") {
+        Some(rest) => (rest, false),
+        None => (rest.strip_suffix(".svg")?, true),
+    };
+    let qualifiers = if rest.is_empty() { rest } else { rest.strip_prefix('.')? };
 
-    for size in ["256", "128", "64", "48", "44", "32"] {
-        let sized_name = format!("{}.targetsize-{}.png", stem, size);
-        let sized_path = parent.join(&sized_name);
+    // Logos are named after their size, e.g. `Square44x44Logo`
+    let digits: String = stem.chars().skip_while(|c| !c.is_ascii_digit()).take_while(char::is_ascii_digit).collect();
+    let base_size = digits.parse().unwrap_or(50);
 
-        if sized_path.exists() {
-            return Some(sized_path);
+    let (mut fit, mut size) = (1, base_size);
+    for qualifier in qualifiers.split('_') {
+        let Some((name, value)) = qualifier.split_once('-') else {
+            continue;
+        };
+        match (name, value) {
+            ("scale", percent) => size = base_size * percent.parse::<u32>().ok()? / 100,
+            ("targetsize", pixels) => size = pixels.parse().ok()?,
+            ("contrast", _) => return None,
+            // Unplated logos are what the dark taskbar shows
+            ("altform", "unplated") | ("theme", "dark") => fit = if dark { 2 } else { 0 },
+            ("altform", "lightunplated") | ("theme", "light") => fit = if dark { 0 } else { 2 },
+            _ => {}
         }
     }
 
-    None
+    Some((fit, if scalable { u32::MAX } else { size }))
 }
 
-fn load_png_as_color_image(path: &std::path::PathBuf) -> Option<eframe::egui::ColorImage> {
+/// Loads a PNG, scaled down to at most 128 pixels square.
+pub fn load_png_as_color_image(path: &std::path::PathBuf) -> Option<eframe::egui::ColorImage> {
     let img = image::open(path).ok()?.to_rgba8();
     let (w, h) = (img.width(), img.height());
 
@@ -212,108 +260,281 @@ fn destroy_icon_data(hbm_color: HBITMAP, hbm_mask: HBITMAP, hicon: HICON) {
     }
 }
 
-pub fn get_dock_icon_for_window(hwnd: HWND) -> Option<DockIcon> {
-    if !is_dock_worthy_window(hwnd) {
-        return None;
+/// Where the icon of a dock item comes from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IconSource {
+    /// The icon the window returned for `WM_GETICON`.
+    WindowMessage,
+    /// The icon registered with the window class.
+    WindowClass,
+    /// The executable's icon as reported by the shell.
+    ShellFileInfo,
+    /// A logo referenced by the package's `AppxManifest.xml`.
+    PackageManifest,
+    None,
+}
+
+/// Why a window is or is not shown in the dock.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict {
+    /// Shown, either because of the include rule at the given index or by default.
+    Shown(Option<usize>),
+    Invisible,
+    /// The window is not owned by any process.
+    UnresolvedProcess(IdentityError),
+    OwnWindow,
+    /// Hidden by the exclude rule at the given index.
+    ExcludedByRule(usize),
+    NoIcon,
+}
+
+/// Everything learned about a top-level window while deciding whether to show it.
+pub struct WindowInspection {
+    pub hwnd: HWND,
+    pub process_id: u32,
+    /// Why the process identity is only a best-effort fallback, if it is.
+    pub identity_error: Option<IdentityError>,
+    pub facts: Option<WindowFacts>,
+    pub icon_source: IconSource,
+    pub verdict: Verdict,
+    pub icon: Option<DockIcon>,
+}
+
+impl WindowInspection {
+    fn rejected(hwnd: HWND, process_id: u32, verdict: Verdict) -> Self {
+        Self {
+            hwnd,
+            process_id,
+            identity_error: None,
+            facts: None,
+            icon_source: IconSource::None,
+            verdict,
+            icon: None,
+        }
     }
+}
 
-    let uwp_real_path = crate::windows::get_uwp_real_process_path(hwnd);
+/// How much of a window `inspect_window` resolves before rejecting it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InspectDepth {
+    /// Rejects windows that rules hide by style alone without opening their process, leaving
+    /// `exe_path` and `aumid` unset.
+    Quick,
+    /// Resolves the process of every window, so its identity can be reported.
+    Full,
+}
 
-    let path_str = if let Some(real_path) = uwp_real_path {
-        real_path
-    } else {
+/// Resolves the process, rule decision and icon for `hwnd`, recording why it was
+/// accepted or rejected.
+pub fn inspect_window(
+    hwnd: HWND,
+    rules: &RuleSet,
+    processes: &mut ProcessResolver,
+    depth: InspectDepth,
+) -> WindowInspection {
+    if !unsafe { IsWindowVisible(hwnd).as_bool() } {
+        return WindowInspection::rejected(hwnd, 0, Verdict::Invisible);
+    }
+
+    let process_id = crate::windows::get_uwp_child_process_id(hwnd).unwrap_or_else(|| {
         let mut process_id: u32 = 0;
         unsafe {
             let _ = GetWindowThreadProcessId(hwnd, Some(&mut process_id));
         }
+        process_id
+    });
+
+    // Style and ownership alone hide most windows, so only open the process when a rule needs it
+    let mut facts = get_window_facts(hwnd);
+    if depth == InspectDepth::Quick
+        && let Some(index) = rules.excluded_without_process(&facts)
+    {
+        return WindowInspection {
+            facts: Some(facts),
+            ..WindowInspection::rejected(hwnd, process_id, Verdict::ExcludedByRule(index))
+        };
+    }
 
-        if process_id == 0 {
-            return None;
+    // Windows of processes we cannot open (e.g. protected ones) are still shown
+    let (identity, identity_error) = match processes.resolve(process_id) {
+        Ok(identity) => (identity, None),
+        Err(IdentityError::NoProcess) => {
+            let verdict = Verdict::UnresolvedProcess(IdentityError::NoProcess);
+            return WindowInspection::rejected(hwnd, process_id, verdict);
         }
+        Err(err) => (fallback_identity(process_id), Some(err)),
+    };
+    let path_str = identity.path;
+
+    facts.exe_path = path_str.clone();
+    facts.aumid = identity.aumid;
+    let decision = rules.evaluate(&facts);
+    let (aumid, class) = (facts.aumid.clone(), facts.class.clone());
+    let mut inspection = WindowInspection {
+        identity_error,
+        facts: Some(facts),
+        ..WindowInspection::rejected(hwnd, process_id, Verdict::OwnWindow)
+    };
 
-        let process_handle = unsafe {
-            OpenProcess(
-                PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
-                false,
-                process_id,
-            )
-        };
-
-        let Ok(handle) = process_handle else {
-            return None;
-        };
+    // Skip our own window
+    if let Ok(own_path) = std::env::current_exe()
+        && path_str == own_path.to_string_lossy()
+    {
+        return inspection;
+    }
 
-        let mut path_buf: [u16; 260] = [0; 260];
-        let len = unsafe { GetModuleFileNameExW(handle, HMODULE(0), &mut path_buf) };
-        let _ = unsafe { CloseHandle(handle) };
+    if !decision.included {
+        // A window is only hidden by a rule, never by default
+        inspection.verdict = Verdict::ExcludedByRule(decision.deciding_rule.unwrap_or_default());
+        return inspection;
+    }
 
-        if len == 0 {
-            return None;
-        }
+    let (hicon, icon_source) = get_window_hicon(hwnd, &path_str);
+    let is_uwp = crate::windows::is_uwp_app(&path_str);
 
-        String::from_utf16_lossy(&path_buf[..len as usize])
+    inspection.icon_source = if is_uwp {
+        IconSource::PackageManifest
+    } else {
+        icon_source
     };
 
-    // Skip our own window
-    if let Ok(own_path) = std::env::current_exe() {
-        if path_str == own_path.to_string_lossy() {
-            return None;
+    if hicon.is_invalid() && !is_uwp {
+        inspection.verdict = Verdict::NoIcon;
+        return inspection;
+    }
+
+    inspection.verdict = Verdict::Shown(decision.deciding_rule);
+    inspection.icon = Some(DockIcon {
+        hicon,
+        path: decision.group_under.unwrap_or(path_str),
+        name: decision.rename,
+        icon: decision.icon,
+        aumid,
+        class: Some(class),
+        hwnd,
+        windows: vec![hwnd],
+    });
+
+    inspection
+}
+
+fn get_window_hicon(hwnd: HWND, path: &str) -> (HICON, IconSource) {
+    if let Some(own) = get_window_own_hicon(hwnd) {
+        return own;
+    }
+
+    match shell_file_icon(path) {
+        Some(hicon) => (hicon, IconSource::ShellFileInfo),
+        None => (HICON(0), IconSource::None),
+    }
+}
+
+/// The icon the window draws itself with: the one it returns for `WM_GETICON`, else its
+/// class icon. Both belong to the window and must not be destroyed.
+pub fn get_window_own_hicon(hwnd: HWND) -> Option<(HICON, IconSource)> {
+    unsafe {
+        let result = SendMessageW(hwnd, WM_GETICON, WPARAM(ICON_BIG as usize), LPARAM(0));
+        if result.0 != 0 {
+            return Some((HICON(result.0), IconSource::WindowMessage));
         }
+
+        let class_icon = GetClassLongPtrW(hwnd, GCLP_HICON);
+        (class_icon != 0).then_some((HICON(class_icon as isize), IconSource::WindowClass))
     }
+}
 
-    // Filter out known background system apps that aren't user-facing
-    let background_apps = [
-        "TextInputHost.exe",           // Windows IME
-        "SearchHost.exe",              // Windows Search
-        "StartMenuExperienceHost.exe", // Start Menu
-        "ShellExperienceHost.exe",     // Shell components
-        "LockApp.exe",                 // Lock screen
-    ];
+/// Reads the icon of the file at `path`, e.g. of a pinned application that is not running.
+pub fn get_file_icon(path: &str) -> Option<eframe::egui::ColorImage> {
+    let hicon = shell_file_icon(path)?;
+    let image = hicon_to_color_image(hicon);
+    let _ = unsafe { DestroyIcon(hicon) };
+    image
+}
 
-    if background_apps.iter().any(|app| path_str.ends_with(app)) {
+/// Reads the icon at `index` inside an executable or DLL, as a shortcut's icon location
+/// names it, at `size` pixels. Negative indices are resource IDs.
+pub fn get_resource_icon(path: &Path, index: i32, size: u32) -> Option<eframe::egui::ColorImage> {
+    let wide: Vec<u16> = path.to_string_lossy().encode_utf16().collect();
+    let mut file_name = [0u16; 260];
+    // Leave room for the terminating NUL
+    if wide.len() >= file_name.len() {
         return None;
     }
+    file_name[..wide.len()].copy_from_slice(&wide);
 
-    if !is_dock_worthy_window(hwnd) {
+    let mut icons = [HICON(0)];
+    let size = size as i32;
+    let extracted = unsafe { PrivateExtractIconsW(&file_name, index, size, size, Some(&mut icons), None, 0) };
+    // u32::MAX reports a missing file
+    if extracted == 0 || extracted == u32::MAX || icons[0].is_invalid() {
         return None;
     }
 
-    let hicon = unsafe {
-        let result = SendMessageW(hwnd, WM_GETICON, WPARAM(ICON_BIG as usize), LPARAM(0));
+    let image = hicon_to_color_image(icons[0]);
+    let _ = unsafe { DestroyIcon(icons[0]) };
+    image
+}
 
-        if result.0 != 0 {
-            HICON(result.0)
-        } else {
-            let class_icon = GetClassLongPtrW(hwnd, GCLP_HICON);
-            if class_icon != 0 {
-                HICON(class_icon as isize)
-            } else {
-                let utf16_path: Vec<u16> = path_str.encode_utf16().chain(std::iter::once(0)).collect();
-                let mut shfi = SHFILEINFOW::default();
-                let res = SHGetFileInfoW(
-                    PCWSTR(utf16_path.as_ptr()),
-                    windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES(0),
-                    Some(&mut shfi as *mut _ as *mut _),
-                    std::mem::size_of::<SHFILEINFOW>() as u32,
-                    SHGFI_ICON | SHGFI_LARGEICON,
-                );
-
-                if res != 0 && !shfi.hIcon.is_invalid() {
-                    shfi.hIcon
-                } else {
-                    HICON(0)
-                }
-            }
-        }
+/// The large icon the shell shows for the file at `path`. The caller owns the icon.
+fn shell_file_icon(path: &str) -> Option<HICON> {
+    let utf16_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut shfi = SHFILEINFOW::default();
+    let res = unsafe {
+        SHGetFileInfoW(
+            PCWSTR(utf16_path.as_ptr()),
+            windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut shfi as *mut _ as *mut _),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_ICON | SHGFI_LARGEICON,
+        )
     };
 
-    if hicon.is_invalid() && !crate::windows::is_uwp_app(&path_str) {
-        return None;
+    (res != 0 && !shfi.hIcon.is_invalid()).then_some(shfi.hIcon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tile_background_colours() {
+        let manifest = |color: &str| format!(r#"<uap:VisualElements DisplayName="Mail" BackgroundColor="{color}"/>"#);
+
+        assert_eq!(
+            parse_background_color_from_manifest(&manifest("#0078D7")),
+            Some(eframe::egui::Color32::from_rgb(0x00, 0x78, 0xd7))
+        );
+        assert_eq!(parse_background_color_from_manifest(&manifest("transparent")), None);
+        assert_eq!(parse_background_color_from_manifest("<Package/>"), None);
     }
 
-    Some(DockIcon {
-        hicon,
-        path: path_str,
-        hwnd,
-    })
+    #[test]
+    fn assets_for_the_appearance_are_preferred() {
+        let files = [
+            "Square44x44Logo.scale-200.png",
+            "Square44x44Logo.targetsize-48_altform-unplated.png",
+            "Square44x44Logo.targetsize-48_altform-lightunplated.png",
+            "Square44x44Logo.targetsize-256.png",
+        ];
+        let best = |dark| {
+            files
+                .iter()
+                .max_by_key(|name| rank_asset(name, "Square44x44Logo", dark))
+                .copied()
+        };
+
+        assert_eq!(best(true), Some("Square44x44Logo.targetsize-48_altform-unplated.png"));
+        assert_eq!(best(false), Some("Square44x44Logo.targetsize-48_altform-lightunplated.png"));
+    }
+
+    #[test]
+    fn assets_are_ranked_by_size() {
+        assert_eq!(rank_asset("Square44x44Logo.png", "Square44x44Logo", true), Some((1, 44)));
+        assert_eq!(rank_asset("square44x44logo.scale-200.png", "Square44x44Logo", true), Some((1, 88)));
+        assert_eq!(rank_asset("StoreLogo.theme-dark_scale-100.png", "StoreLogo", true), Some((2, 50)));
+        assert_eq!(rank_asset("Square44x44Logo.contrast-black_scale-100.png", "Square44x44Logo", true), None);
+        assert_eq!(rank_asset("Square44x44LogoWide.png", "Square44x44Logo", true), None);
+        assert_eq!(rank_asset("Square44x44Logo.altform-unplated.svg", "Square44x44Logo", true), Some((2, u32::MAX)));
+        assert_eq!(rank_asset("Square44x44Logo.svgz", "Square44x44Logo", true), None);
+    }
 }
@@ -0,0 +1,136 @@
+use windows::Win32::Foundation::{HWND, POINT};
+use windows::Win32::UI::WindowsAndMessaging::{
+    AppendMenuW, CreatePopupMenu, DestroyMenu, GetCursorPos, HMENU, MENU_ITEM_FLAGS, MF_CHECKED,
+    MF_GRAYED, MF_POPUP, MF_SEPARATOR, MF_STRING, SetForegroundWindow, TPM_BOTTOMALIGN,
+    TPM_CENTERALIGN, TPM_LEFTALIGN, TPM_RETURNCMD, TPM_RIGHTBUTTON, TPM_TOPALIGN, TRACK_POPUP_MENU_FLAGS,
+    TrackPopupMenu,
+};
+use windows::core::HSTRING;
+
+use crate::menu_model::{MenuCommand, MenuItem, MenuItemKind, flatten_commands};
+
+/// One entry of a popup menu: its label and whether it can be chosen.
+pub struct MenuEntry<'a> {
+    pub label: &'a str,
+    pub enabled: bool,
+}
+
+/// Where a popup menu opens, in physical screen pixels.
+#[derive(Clone, Copy, Debug)]
+pub enum MenuPosition {
+    /// At the mouse cursor, opening upwards.
+    Cursor,
+    /// Centred above the point, e.g. above a dock item.
+    Above(POINT),
+    /// Centred below the point, e.g. below the menu bar.
+    Below(POINT),
+}
+
+/// Shows a native popup menu at `position` and blocks until it closes.
+///
+/// Native menus are keyboard-operable and exposed to screen readers by the OS. Returns the
+/// index of the chosen entry, or `None` if the menu was dismissed.
+pub fn show_popup_menu(owner: HWND, position: MenuPosition, entries: &[MenuEntry]) -> Option<usize> {
+    unsafe {
+        let menu = CreatePopupMenu().ok()?;
+
+        for (index, entry) in entries.iter().enumerate() {
+            let flags = if entry.enabled { MF_STRING } else { MF_STRING | MF_GRAYED };
+            // Command ids start at 1 because 0 means the menu was dismissed
+            let _ = AppendMenuW(menu, flags, index + 1, &HSTRING::from(entry.label));
+        }
+
+        let command = track_menu(menu, owner, position, TPM_CENTERALIGN);
+        let _ = DestroyMenu(menu);
+
+        (command as usize).checked_sub(1)
+    }
+}
+
+/// Shows `items` as a native popup menu, with submenus, separators, check marks and
+/// shortcuts, and blocks until it closes. Returns the chosen command.
+///
+/// The menu opens with its left edge at `position`, like a menu bar dropdown.
+pub fn show_menu_model(owner: HWND, position: MenuPosition, items: &[MenuItem]) -> Option<MenuCommand> {
+    unsafe {
+        let menu = CreatePopupMenu().ok()?;
+        append_menu_items(menu, items, &mut 0);
+
+        let command = track_menu(menu, owner, position, TPM_LEFTALIGN);
+        // Destroying the menu also destroys its submenus
+        let _ = DestroyMenu(menu);
+
+        // Ids follow the same depth-first order as `flatten_commands`
+        let index = (command as usize).checked_sub(1)?;
+        flatten_commands(items).get(index).copied()
+    }
+}
+
+/// Appends `items` to `menu`, numbering commands from `next_id` + 1 in depth-first order.
+fn append_menu_items(menu: HMENU, items: &[MenuItem], next_id: &mut usize) {
+    for item in items {
+        let mut flags = MF_STRING;
+        if !item.enabled {
+            flags |= MF_GRAYED;
+        }
+        if item.checked {
+            flags |= MF_CHECKED;
+        }
+        let label = match &item.shortcut {
+            Some(shortcut) => format!("{}\t{shortcut}", escape_mnemonics(&item.label)),
+            None => escape_mnemonics(&item.label),
+        };
+
+        let (flags, id): (MENU_ITEM_FLAGS, usize) = match &item.kind {
+            MenuItemKind::Separator => {
+                let _ = unsafe { AppendMenuW(menu, MF_SEPARATOR, 0, None) };
+                continue;
+            }
+            MenuItemKind::Command(_) => {
+                *next_id += 1;
+                (flags, *next_id)
+            }
+            MenuItemKind::Submenu(children) => {
+                let Ok(submenu) = (unsafe { CreatePopupMenu() }) else {
+                    continue;
+                };
+                append_menu_items(submenu, children, next_id);
+                (flags | MF_POPUP, submenu.0 as usize)
+            }
+        };
+        let _ = unsafe { AppendMenuW(menu, flags, id, &HSTRING::from(label)) };
+    }
+}
+
+/// Labels in the model are plain text, so literal ampersands must not become mnemonics.
+fn escape_mnemonics(label: &str) -> String {
+    label.replace('&', "&&")
+}
+
+/// Tracks `menu` at `position` and returns the chosen command id, or 0 if it was dismissed.
+fn track_menu(menu: HMENU, owner: HWND, position: MenuPosition, horizontal: TRACK_POPUP_MENU_FLAGS) -> i32 {
+    unsafe {
+        let (at, vertical) = match position {
+            MenuPosition::Cursor => {
+                let mut cursor = POINT::default();
+                let _ = GetCursorPos(&mut cursor);
+                (cursor, TPM_BOTTOMALIGN)
+            }
+            MenuPosition::Above(at) => (at, TPM_BOTTOMALIGN),
+            MenuPosition::Below(at) => (at, TPM_TOPALIGN),
+        };
+
+        // The owner must be in the foreground for the menu to close when focus moves away
+        let _ = SetForegroundWindow(owner);
+        TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_RIGHTBUTTON | vertical | horizontal,
+            at.x,
+            at.y,
+            0,
+            owner,
+            None,
+        )
+        .0
+    }
+}
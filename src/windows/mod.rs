@@ -1,7 +1,25 @@
+mod appbar;
 mod apps;
+mod display;
 mod icons;
+mod menu;
+mod native_menu;
+mod preferences;
+mod process;
+mod shortcuts;
+mod system_stats;
+mod time;
 mod windows;
 
+pub use appbar::*;
 pub use apps::*;
+pub use display::*;
 pub use icons::*;
+pub use menu::*;
+pub use native_menu::*;
+pub use preferences::*;
+pub use process::*;
+pub use shortcuts::*;
+pub use system_stats::*;
+pub use time::*;
 pub use windows::*;
@@ -0,0 +1,351 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::Com::{
+    CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED, CoCreateInstance, CoInitializeEx,
+};
+use windows::Win32::System::Threading::GetCurrentProcessId;
+use windows::Win32::UI::Accessibility::{
+    CUIAutomation, IUIAutomation, IUIAutomationElement, IUIAutomationExpandCollapsePattern,
+    IUIAutomationInvokePattern, IUIAutomationTreeWalker, UIA_ExpandCollapsePatternId,
+    UIA_InvokePatternId, UIA_MenuBarControlTypeId, UIA_MenuItemControlTypeId,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetMenu, GetMenuItemCount, GetMenuItemInfoW, GetSubMenu, GetWindowThreadProcessId, HMENU,
+    MENUITEMINFOW, MFS_CHECKED, MFS_DISABLED, MFT_SEPARATOR, MIIM_FTYPE, MIIM_ID, MIIM_STATE,
+    MIIM_STRING, MIIM_SUBMENU, PostMessageW, SMTO_ABORTIFHUNG, SendMessageTimeoutW,
+    SetForegroundWindow, WM_COMMAND, WM_INITMENU, WM_INITMENUPOPUP,
+};
+use windows::core::PWSTR;
+
+use crate::menu_model::{AppMenu, MenuCommand, MenuItem, MenuItemKind, parse_menu_label};
+
+/// How deep to look for a menu bar in a window's automation tree. Menu bars sit near the top,
+/// and walking the whole tree of a large window is slow.
+const MENU_BAR_SEARCH_DEPTH: usize = 3;
+
+/// Classic menus can be nested arbitrarily; anything deeper than this is not shown.
+const MAX_SUBMENU_DEPTH: usize = 8;
+
+/// How long a window may take to fill in a menu it is told is opening, in milliseconds.
+const INIT_MENU_TIMEOUT_MS: u32 = 200;
+
+/// The menus of a window, as read by a `MenuReader`.
+pub struct MenuSnapshot {
+    pub hwnd: HWND,
+    /// The `MenuReader::read` call this answers.
+    pub request: u64,
+    /// Empty when the window has no menu.
+    pub menu: AppMenu,
+}
+
+enum MenuRequest {
+    Read {
+        hwnd: HWND,
+        request: u64,
+        opening: Option<usize>,
+    },
+    Invoke {
+        hwnd: HWND,
+        command: MenuCommand,
+    },
+}
+
+/// Reads and invokes the menus of other apps' windows on a worker thread, since both call into
+/// the app and wait for it, which takes long or hangs while the app is busy.
+pub struct MenuReader {
+    requests: Sender<MenuRequest>,
+    snapshots: Receiver<MenuSnapshot>,
+    next_request: u64,
+    /// Reads sent whose snapshot was not received yet.
+    outstanding: usize,
+}
+
+impl MenuReader {
+    pub fn start() -> Self {
+        let (requests, request_receiver) = mpsc::channel();
+        let (snapshot_sender, snapshots) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            // The menu last read; automation commands refer to its elements
+            let mut current: Option<NativeMenu> = None;
+
+            while let Ok(request) = request_receiver.recv() {
+                match request {
+                    MenuRequest::Read { hwnd, request, opening } => {
+                        current = NativeMenu::read(hwnd, opening);
+                        let menu = current.as_ref().map(|native| native.menu.clone()).unwrap_or_default();
+                        if snapshot_sender.send(MenuSnapshot { hwnd, request, menu }).is_err() {
+                            break;
+                        }
+                    }
+                    MenuRequest::Invoke { hwnd, command } => {
+                        if let Some(native) = current.as_ref().filter(|native| native.hwnd == hwnd) {
+                            native.invoke(command);
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            requests,
+            snapshots,
+            next_request: 0,
+            outstanding: 0,
+        }
+    }
+
+    /// Asks for the menus of `hwnd` and returns the request the snapshot will carry.
+    ///
+    /// `opening` names the top-level menu about to be shown; the window is told so first and
+    /// fills in its submenus, as many apps do only then.
+    pub fn read(&mut self, hwnd: HWND, opening: Option<usize>) -> u64 {
+        self.next_request += 1;
+        let request = self.next_request;
+        if self.requests.send(MenuRequest::Read { hwnd, request, opening }).is_ok() {
+            self.outstanding += 1;
+        }
+        request
+    }
+
+    /// Carries out `command` of the menu last read from `hwnd`.
+    pub fn invoke(&self, hwnd: HWND, command: MenuCommand) {
+        let _ = self.requests.send(MenuRequest::Invoke { hwnd, command });
+    }
+
+    /// Returns the next snapshot read, if any.
+    pub fn try_recv(&mut self) -> Option<MenuSnapshot> {
+        let snapshot = self.snapshots.try_recv().ok()?;
+        self.outstanding = self.outstanding.saturating_sub(1);
+        Some(snapshot)
+    }
+
+    /// Whether a snapshot is still to come.
+    pub fn is_reading(&self) -> bool {
+        self.outstanding > 0
+    }
+}
+
+/// The menus of a top-level window and what is needed to invoke them.
+struct NativeMenu {
+    hwnd: HWND,
+    menu: AppMenu,
+    /// Elements behind `MenuCommand::Automation` indices.
+    automation_items: Vec<IUIAutomationElement>,
+}
+
+impl NativeMenu {
+    /// Reads the menus of `hwnd`: its classic `HMENU` if it has one, otherwise the menu bar
+    /// it exposes through UI Automation. With `opening`, the window first gets the messages
+    /// it would for opening that top-level menu and its submenus.
+    ///
+    /// Windows without any menu yield `None`.
+    fn read(hwnd: HWND, opening: Option<usize>) -> Option<Self> {
+        if hwnd.0 == 0 {
+            return None;
+        }
+
+        let menu = unsafe { GetMenu(hwnd) };
+        if menu.0 != 0 {
+            if let Some(index) = opening {
+                init_menu(hwnd, menu, index);
+            }
+            let items = read_hmenu(menu, 0);
+            if !items.is_empty() {
+                return Some(Self {
+                    hwnd,
+                    menu: AppMenu { items },
+                    automation_items: Vec::new(),
+                });
+            }
+        }
+
+        read_automation_menu(hwnd)
+    }
+
+    /// Carries out `command` on the window the menu was read from.
+    fn invoke(&self, command: MenuCommand) {
+        unsafe {
+            // The command should act on the window the user was working in
+            let _ = SetForegroundWindow(self.hwnd);
+
+            match command {
+                MenuCommand::Command(id) => {
+                    let _ = PostMessageW(self.hwnd, WM_COMMAND, WPARAM(id as usize), LPARAM(0));
+                }
+                MenuCommand::Automation(index) => {
+                    if let Some(element) = self.automation_items.get(index) {
+                        invoke_element(element);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether `hwnd` belongs to this process, e.g. the dock or the menu bar.
+pub fn is_own_window(hwnd: HWND) -> bool {
+    let mut process_id = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut process_id)) };
+    process_id == unsafe { GetCurrentProcessId() }
+}
+
+/// Tells `hwnd` that the `index`th menu of its menu bar and the submenus below it open, so it
+/// fills them in as it would for the user.
+fn init_menu(hwnd: HWND, menu: HMENU, index: usize) {
+    let send = |message: u32, wparam: usize, lparam: isize| unsafe {
+        SendMessageTimeoutW(
+            hwnd,
+            message,
+            WPARAM(wparam),
+            LPARAM(lparam),
+            SMTO_ABORTIFHUNG,
+            INIT_MENU_TIMEOUT_MS,
+            None,
+        )
+    };
+    send(WM_INITMENU, menu.0 as usize, 0);
+
+    let mut popups = vec![(unsafe { GetSubMenu(menu, index as i32) }, index, 0)];
+    while let Some((popup, position, depth)) = popups.pop() {
+        if popup.0 == 0 || depth > MAX_SUBMENU_DEPTH {
+            continue;
+        }
+        send(WM_INITMENUPOPUP, popup.0 as usize, position as isize);
+
+        let count = unsafe { GetMenuItemCount(popup) }.max(0);
+        popups.extend((0..count).map(|child| (unsafe { GetSubMenu(popup, child) }, child as usize, depth + 1)));
+    }
+}
+
+/// Reads the items of a classic menu, recursing into submenus.
+fn read_hmenu(menu: HMENU, depth: usize) -> Vec<MenuItem> {
+    let count = unsafe { GetMenuItemCount(menu) };
+    (0..count.max(0) as u32)
+        .filter_map(|position| read_hmenu_item(menu, position, depth))
+        .collect()
+}
+
+fn read_hmenu_item(menu: HMENU, position: u32, depth: usize) -> Option<MenuItem> {
+    let mut info = MENUITEMINFOW {
+        cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+        fMask: MIIM_FTYPE | MIIM_STATE | MIIM_ID | MIIM_SUBMENU | MIIM_STRING,
+        ..Default::default()
+    };
+    // The first call reports the length of the label, the second copies it
+    unsafe { GetMenuItemInfoW(menu, position, true, &mut info) }.ok()?;
+
+    if info.fType.0 & MFT_SEPARATOR.0 != 0 {
+        return Some(MenuItem::separator());
+    }
+
+    let mut buffer = vec![0u16; info.cch as usize + 1];
+    info.dwTypeData = PWSTR(buffer.as_mut_ptr());
+    info.cch = buffer.len() as u32;
+    unsafe { GetMenuItemInfoW(menu, position, true, &mut info) }.ok()?;
+    let raw = String::from_utf16_lossy(&buffer[..info.cch as usize]);
+    let (label, shortcut) = parse_menu_label(&raw);
+
+    let kind = if info.hSubMenu.0 != 0 {
+        if depth >= MAX_SUBMENU_DEPTH {
+            return None;
+        }
+        MenuItemKind::Submenu(read_hmenu(info.hSubMenu, depth + 1))
+    } else {
+        MenuItemKind::Command(MenuCommand::Command(info.wID))
+    };
+
+    Some(MenuItem {
+        label,
+        shortcut,
+        enabled: info.fState.0 & MFS_DISABLED.0 == 0,
+        checked: info.fState.0 & MFS_CHECKED.0 != 0,
+        kind,
+    })
+}
+
+/// Reads the top-level items of the first menu bar in the window's automation tree.
+///
+/// Their dropdowns are usually built only when opened, so each item becomes a command that
+/// opens the app's own menu.
+fn read_automation_menu(hwnd: HWND) -> Option<NativeMenu> {
+    unsafe {
+        // Harmless if COM is already initialized on this thread
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let automation: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+        let walker = automation.ControlViewWalker().ok()?;
+        let root = automation.ElementFromHandle(hwnd).ok()?;
+        let menu_bar = find_menu_bar(&walker, &root, MENU_BAR_SEARCH_DEPTH)?;
+
+        let mut items = Vec::new();
+        let mut automation_items = Vec::new();
+        for element in children(&walker, &menu_bar) {
+            if element.CurrentControlType().ok() != Some(UIA_MenuItemControlTypeId) {
+                continue;
+            }
+            let Ok(name) = element.CurrentName() else {
+                continue;
+            };
+
+            items.push(MenuItem {
+                label: parse_menu_label(&name.to_string()).0,
+                shortcut: None,
+                enabled: element.CurrentIsEnabled().is_ok_and(|enabled| enabled.as_bool()),
+                checked: false,
+                kind: MenuItemKind::Command(MenuCommand::Automation(automation_items.len())),
+            });
+            automation_items.push(element);
+        }
+
+        (!items.is_empty()).then_some(NativeMenu {
+            hwnd,
+            menu: AppMenu { items },
+            automation_items,
+        })
+    }
+}
+
+/// Searches breadth-first so the window's main menu bar wins over ones nested in panes.
+fn find_menu_bar(
+    walker: &IUIAutomationTreeWalker,
+    root: &IUIAutomationElement,
+    depth: usize,
+) -> Option<IUIAutomationElement> {
+    let mut level = vec![root.clone()];
+    for _ in 0..depth {
+        let next: Vec<IUIAutomationElement> = level.iter().flat_map(|e| children(walker, e)).collect();
+        if let Some(menu_bar) = next.iter().find(|element| {
+            unsafe { element.CurrentControlType() }.ok() == Some(UIA_MenuBarControlTypeId)
+        }) {
+            return Some(menu_bar.clone());
+        }
+        level = next;
+    }
+    None
+}
+
+fn children(walker: &IUIAutomationTreeWalker, parent: &IUIAutomationElement) -> Vec<IUIAutomationElement> {
+    let mut children = Vec::new();
+    let mut child = unsafe { walker.GetFirstChildElement(parent) }.ok();
+    while let Some(element) = child {
+        child = unsafe { walker.GetNextSiblingElement(&element) }.ok();
+        children.push(element);
+    }
+    children
+}
+
+/// Opens a menu item's dropdown, or invokes it if it has none.
+fn invoke_element(element: &IUIAutomationElement) {
+    unsafe {
+        if let Ok(pattern) =
+            element.GetCurrentPatternAs::<IUIAutomationExpandCollapsePattern>(UIA_ExpandCollapsePatternId)
+            && pattern.Expand().is_ok()
+        {
+            return;
+        }
+        if let Ok(pattern) = element.GetCurrentPatternAs::<IUIAutomationInvokePattern>(UIA_InvokePatternId) {
+            let _ = pattern.Invoke();
+        }
+    }
+}
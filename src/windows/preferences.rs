@@ -0,0 +1,91 @@
+use eframe::egui::Color32;
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::Graphics::Gdi::{
+    COLOR_HIGHLIGHT, COLOR_HOTLIGHT, COLOR_WINDOW, COLOR_WINDOWTEXT, GetSysColor, SYS_COLOR_INDEX,
+};
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW};
+use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+use windows::core::{PCWSTR, w};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SPI_GETCLIENTAREAANIMATION, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    SystemParametersInfoW,
+};
+
+use crate::accessibility::{ContrastPalette, SystemPreferences};
+use crate::theme::SystemAppearance;
+
+/// Reads the animation and high contrast settings from the OS.
+pub fn get_system_preferences() -> SystemPreferences {
+    let mut animations = BOOL(1);
+    let mut contrast = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+
+    unsafe {
+        let _ = SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut animations as *mut _ as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        );
+        let _ = SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            contrast.cbSize,
+            Some(&mut contrast as *mut _ as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        );
+    }
+
+    let high_contrast = contrast.dwFlags.contains(HCF_HIGHCONTRASTON).then(|| ContrastPalette {
+        background: system_color(COLOR_WINDOW),
+        foreground: system_color(COLOR_WINDOWTEXT),
+        highlight: system_color(COLOR_HIGHLIGHT),
+        hot: system_color(COLOR_HOTLIGHT),
+    });
+
+    SystemPreferences {
+        animations_enabled: animations.as_bool(),
+        high_contrast,
+    }
+}
+
+/// Reads the taskbar's light or dark mode and the accent colour from the user's settings.
+pub fn get_system_appearance() -> SystemAppearance {
+    let defaults = SystemAppearance::default();
+    let personalize = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize");
+    let light = read_user_dword(personalize, w!("SystemUsesLightTheme"));
+    // Stored as 0xAABBGGRR
+    let accent = read_user_dword(w!("Software\\Microsoft\\Windows\\DWM"), w!("AccentColor")).map(|value| {
+        let [r, g, b, _] = value.to_le_bytes();
+        Color32::from_rgb(r, g, b)
+    });
+
+    SystemAppearance {
+        dark: light.map_or(defaults.dark, |light| light == 0),
+        accent: accent.unwrap_or(defaults.accent),
+    }
+}
+
+fn read_user_dword(key: PCWSTR, value: PCWSTR) -> Option<u32> {
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key,
+            value,
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut data as *mut _ as *mut _),
+            Some(&mut size),
+        )
+    };
+    result.is_ok().then_some(data)
+}
+
+fn system_color(index: SYS_COLOR_INDEX) -> Color32 {
+    // COLORREF is 0x00BBGGRR
+    let [r, g, b, _] = unsafe { GetSysColor(index) }.to_le_bytes();
+    Color32::from_rgb(r, g, b)
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS, FILETIME, HANDLE,
+};
+use windows::Win32::Storage::Packaging::Appx::GetApplicationUserModelId;
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW, TH32CS_SNAPPROCESS,
+};
+use windows::Win32::System::Threading::{
+    GetProcessTimes, OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    QueryFullProcessImageNameW,
+};
+use windows::core::PWSTR;
+
+/// Longest path `QueryFullProcessImageNameW` can return, in UTF-16 units.
+const MAX_LONG_PATH: usize = 32_768;
+
+/// Who a process is, as far as the dock is concerned.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessIdentity {
+    pub pid: u32,
+    /// Full executable path, or just the executable name when the process could not be opened.
+    pub path: String,
+    /// Application User Model ID of packaged apps.
+    pub aumid: Option<String>,
+}
+
+/// Why a process identity could not be fully resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IdentityError {
+    /// The window is not owned by any process.
+    NoProcess,
+    /// The process could not be opened, typically because it is protected.
+    OpenFailed { pid: u32, reason: String },
+    /// The process was opened but its image path could not be read.
+    QueryFailed { pid: u32, reason: String },
+}
+
+impl fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentityError::NoProcess => write!(f, "window has no owning process"),
+            IdentityError::OpenFailed { pid, reason } => {
+                write!(f, "could not open process {pid}: {reason}")
+            }
+            IdentityError::QueryFailed { pid, reason } => {
+                write!(f, "could not query image path of process {pid}: {reason}")
+            }
+        }
+    }
+}
+
+/// Resolved identities keyed by PID, validated by the process creation time so a
+/// reused PID is never mistaken for the process that previously held it.
+#[derive(Default)]
+pub struct IdentityCache {
+    entries: HashMap<u32, (u64, ProcessIdentity)>,
+}
+
+impl IdentityCache {
+    pub fn get(&self, pid: u32, creation_time: u64) -> Option<&ProcessIdentity> {
+        self.entries
+            .get(&pid)
+            .filter(|(created, _)| *created == creation_time)
+            .map(|(_, identity)| identity)
+    }
+
+    pub fn insert(&mut self, creation_time: u64, identity: ProcessIdentity) {
+        self.entries.insert(identity.pid, (creation_time, identity));
+    }
+
+    /// Drops entries for PIDs that no longer own any window.
+    pub fn retain_pids(&mut self, live: &[u32]) {
+        self.entries.retain(|pid, _| live.contains(pid));
+    }
+}
+
+/// Resolves process identities using only `PROCESS_QUERY_LIMITED_INFORMATION`, which is
+/// granted even for elevated processes, and caches the results.
+#[derive(Default)]
+pub struct ProcessResolver {
+    cache: IdentityCache,
+}
+
+impl ProcessResolver {
+    /// Returns the identity of the process with ID `pid`.
+    ///
+    /// When the process cannot be opened or queried, the error is returned; use
+    /// `fallback_identity` to still obtain a best-effort identity for the window.
+    pub fn resolve(&mut self, pid: u32) -> Result<ProcessIdentity, IdentityError> {
+        if pid == 0 {
+            return Err(IdentityError::NoProcess);
+        }
+
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }
+            .map_err(|err| IdentityError::OpenFailed {
+                pid,
+                reason: err.message().to_string(),
+            })?;
+
+        let result = self.resolve_handle(handle, pid);
+        let _ = unsafe { CloseHandle(handle) };
+        result
+    }
+
+    fn resolve_handle(&mut self, handle: HANDLE, pid: u32) -> Result<ProcessIdentity, IdentityError> {
+        let creation_time = process_creation_time(handle);
+
+        if let Some(identity) = creation_time.and_then(|created| self.cache.get(pid, created)) {
+            return Ok(identity.clone());
+        }
+
+        let path = query_image_path(handle).map_err(|reason| IdentityError::QueryFailed { pid, reason })?;
+        let identity = ProcessIdentity {
+            pid,
+            path,
+            aumid: query_aumid(handle),
+        };
+
+        if let Some(created) = creation_time {
+            self.cache.insert(created, identity.clone());
+        }
+
+        Ok(identity)
+    }
+
+    /// Forgets cached identities of processes that no longer own a window.
+    pub fn retain_pids(&mut self, live: &[u32]) {
+        self.cache.retain_pids(live);
+    }
+}
+
+/// Builds a best-effort identity for a process that could not be resolved, using the
+/// executable name from a process snapshot, which needs no access to the process itself.
+pub fn fallback_identity(pid: u32) -> ProcessIdentity {
+    ProcessIdentity {
+        pid,
+        path: snapshot_exe_name(pid).unwrap_or_else(|| format!("pid:{pid}")),
+        aumid: None,
+    }
+}
+
+fn process_creation_time(handle: HANDLE) -> Option<u64> {
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+
+    unsafe { GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user) }.ok()?;
+
+    Some(((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64)
+}
+
+/// Reads the full image path, growing the buffer for paths longer than `MAX_PATH`.
+fn query_image_path(handle: HANDLE) -> Result<String, String> {
+    let mut capacity = 260;
+
+    loop {
+        let mut buf = vec![0u16; capacity];
+        let mut len = capacity as u32;
+
+        match unsafe {
+            QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buf.as_mut_ptr()), &mut len)
+        } {
+            Ok(()) => return Ok(String::from_utf16_lossy(&buf[..len as usize])),
+            Err(err)
+                if err.code() == ERROR_INSUFFICIENT_BUFFER.to_hresult() && capacity < MAX_LONG_PATH =>
+            {
+                capacity = (capacity * 4).min(MAX_LONG_PATH);
+            }
+            Err(err) => return Err(err.message().to_string()),
+        }
+    }
+}
+
+fn query_aumid(handle: HANDLE) -> Option<String> {
+    let mut buf: [u16; 256] = [0; 256];
+    let mut len = buf.len() as u32;
+    let result = unsafe { GetApplicationUserModelId(handle, &mut len, PWSTR(buf.as_mut_ptr())) };
+
+    if result != ERROR_SUCCESS || len == 0 {
+        return None;
+    }
+
+    // The reported length includes the terminating null
+    let len = (len as usize - 1).min(buf.len());
+    Some(String::from_utf16_lossy(&buf[..len]))
+}
+
+fn snapshot_exe_name(pid: u32) -> Option<String> {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).ok()?;
+
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+
+        let mut name = None;
+        let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+        while found {
+            if entry.th32ProcessID == pid {
+                let len = entry
+                    .szExeFile
+                    .iter()
+                    .position(|c| *c == 0)
+                    .unwrap_or(entry.szExeFile.len());
+                name = Some(String::from_utf16_lossy(&entry.szExeFile[..len]));
+                break;
+            }
+            found = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+
+        let _ = CloseHandle(snapshot);
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(pid: u32, path: &str) -> ProcessIdentity {
+        ProcessIdentity {
+            pid,
+            path: path.to_string(),
+            aumid: None,
+        }
+    }
+
+    #[test]
+    fn cache_hits_only_for_matching_creation_time() {
+        let mut cache = IdentityCache::default();
+        cache.insert(100, identity(4, "a.exe"));
+
+        assert_eq!(cache.get(4, 100), Some(&identity(4, "a.exe")));
+        // Same PID, different process: the PID was reused
+        assert_eq!(cache.get(4, 200), None);
+        assert_eq!(cache.get(5, 100), None);
+    }
+
+    #[test]
+    fn reused_pid_replaces_stale_entry() {
+        let mut cache = IdentityCache::default();
+        cache.insert(100, identity(4, "old.exe"));
+        cache.insert(200, identity(4, "new.exe"));
+
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.get(4, 200).map(|i| i.path.as_str()), Some("new.exe"));
+    }
+
+    #[test]
+    fn retain_pids_drops_exited_processes() {
+        let mut cache = IdentityCache::default();
+        cache.insert(1, identity(4, "a.exe"));
+        cache.insert(2, identity(8, "b.exe"));
+
+        cache.retain_pids(&[8]);
+
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.get(8, 2).is_some());
+    }
+
+    #[test]
+    fn identity_errors_explain_the_failure() {
+        let err = IdentityError::OpenFailed {
+            pid: 42,
+            reason: "Access is denied.".to_string(),
+        };
+
+        assert_eq!(err.to_string(), "could not open process 42: Access is denied.");
+        assert_eq!(IdentityError::NoProcess.to_string(), "window has no owning process");
+    }
+}
@@ -0,0 +1,280 @@
+use std::path::{Path, PathBuf};
+
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_BINARY, RegGetValueW};
+use windows::core::{PCWSTR, w};
+
+/// Folder of the shortcuts pinned to the taskbar and Start menu, under `%APPDATA%`.
+const USER_PINNED_DIR: &str = "Microsoft\\Internet Explorer\\Quick Launch\\User Pinned";
+
+const HEADER_SIZE: usize = 0x4C;
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+
+/// What a `.lnk` shortcut opens.
+#[derive(Debug, Default, PartialEq)]
+pub struct ShellLink {
+    /// The file the shortcut opens; `None` for shortcuts to shell items such as packaged apps.
+    pub target: Option<String>,
+    pub arguments: Option<String>,
+    /// The icon file and index, e.g. `C:\Icons\app.ico,0`.
+    pub icon_location: Option<String>,
+}
+
+/// The shortcuts pinned to the taskbar, in the order the taskbar shows them.
+pub fn taskbar_pin_shortcuts() -> Vec<PathBuf> {
+    let mut shortcuts = pinned_shortcuts("TaskBar");
+    let order = read_user_binary(
+        w!("Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Taskband"),
+        w!("Favorites"),
+    );
+    if let Some(order) = order {
+        sort_by_taskband(&mut shortcuts, &order);
+    }
+    shortcuts
+}
+
+/// The shortcuts pinned to the Windows 7 Start menu, by name. The Start menus of Windows 10
+/// and 11 keep their pins in undocumented databases, which are not read, so this is empty there.
+pub fn start_menu_pin_shortcuts() -> Vec<PathBuf> {
+    pinned_shortcuts("StartMenu")
+}
+
+/// Reads a `.lnk` file.
+pub fn read_shell_link(path: &Path) -> Option<ShellLink> {
+    parse_shell_link(&std::fs::read(path).ok()?)
+}
+
+/// Parses the Shell Link binary format (MS-SHLLINK) far enough to find a shortcut's target,
+/// arguments and icon.
+pub fn parse_shell_link(bytes: &[u8]) -> Option<ShellLink> {
+    if read_u32(bytes, 0)? as usize != HEADER_SIZE {
+        return None;
+    }
+    let flags = read_u32(bytes, 0x14)?;
+    let mut offset = HEADER_SIZE;
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        offset += 2 + read_u16(bytes, offset)? as usize;
+    }
+
+    let mut link = ShellLink::default();
+    if flags & HAS_LINK_INFO != 0 {
+        let info = bytes.get(offset..)?;
+        link.target = local_path(info);
+        offset += read_u32(info, 0)? as usize;
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    let mut strings = [None, None, None, None, None];
+    let present = [HAS_NAME, HAS_RELATIVE_PATH, HAS_WORKING_DIR, HAS_ARGUMENTS, HAS_ICON_LOCATION];
+    for (string, flag) in strings.iter_mut().zip(present) {
+        if flags & flag != 0 {
+            let (text, length) = read_counted_string(bytes.get(offset..)?, unicode)?;
+            *string = Some(text).filter(|text| !text.is_empty());
+            offset += length;
+        }
+    }
+    let [_, _, _, arguments, icon_location] = strings;
+    link.arguments = arguments;
+    link.icon_location = icon_location;
+
+    Some(link)
+}
+
+/// Orders shortcuts by where their file names appear in the taskbar's `Favorites` data,
+/// which embeds each pinned shortcut's name. Shortcuts it does not name go last, by name.
+pub fn sort_by_taskband(shortcuts: &mut [PathBuf], favorites: &[u8]) {
+    shortcuts.sort_by_cached_key(|shortcut| {
+        let name = shortcut.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let position = find_utf16(favorites, &name).unwrap_or(usize::MAX);
+        (position, name)
+    });
+}
+
+fn pinned_shortcuts(folder: &str) -> Vec<PathBuf> {
+    let Some(app_data) = std::env::var_os("APPDATA") else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(Path::new(&app_data).join(USER_PINNED_DIR).join(folder)) else {
+        return Vec::new();
+    };
+
+    let mut shortcuts: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lnk")))
+        .collect();
+    shortcuts.sort();
+    shortcuts
+}
+
+/// The local path in a LinkInfo structure, preferring its Unicode fields.
+fn local_path(info: &[u8]) -> Option<String> {
+    let header_size = read_u32(info, 4)? as usize;
+    let flags = read_u32(info, 8)?;
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+
+    let (base, suffix) = if header_size >= 0x24 {
+        let base = read_utf16z(info.get(read_u32(info, 0x1C)? as usize..)?);
+        let suffix = read_utf16z(info.get(read_u32(info, 0x20)? as usize..)?);
+        (base, suffix)
+    } else {
+        let base = read_ansiz(info.get(read_u32(info, 0x10)? as usize..)?);
+        let suffix = read_ansiz(info.get(read_u32(info, 0x18)? as usize..)?);
+        (base, suffix)
+    };
+    Some(base + &suffix).filter(|path| !path.is_empty())
+}
+
+/// Reads a string prefixed by its length in characters, returning it with its size in bytes.
+fn read_counted_string(bytes: &[u8], unicode: bool) -> Option<(String, usize)> {
+    let count = read_u16(bytes, 0)? as usize;
+    if unicode {
+        let data = bytes.get(2..2 + count * 2)?;
+        let units: Vec<u16> = data.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        Some((String::from_utf16_lossy(&units), 2 + count * 2))
+    } else {
+        let data = bytes.get(2..2 + count)?;
+        Some((String::from_utf8_lossy(data).into_owned(), 2 + count))
+    }
+}
+
+fn read_utf16z(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn read_ansiz(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// The byte offset of `text` encoded as UTF-16, compared case-insensitively.
+fn find_utf16(haystack: &[u8], text: &str) -> Option<usize> {
+    let needle: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<_>>().to_ascii_lowercase();
+    if needle.is_empty() {
+        return None;
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window.to_ascii_lowercase() == needle)
+}
+
+fn read_user_binary(key: PCWSTR, value: PCWSTR) -> Option<Vec<u8>> {
+    let mut size = 0u32;
+    let result = unsafe { RegGetValueW(HKEY_CURRENT_USER, key, value, RRF_RT_REG_BINARY, None, None, Some(&mut size)) };
+    if result.is_err() {
+        return None;
+    }
+
+    let mut data = vec![0u8; size as usize];
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key,
+            value,
+            RRF_RT_REG_BINARY,
+            None,
+            Some(data.as_mut_ptr() as *mut _),
+            Some(&mut size),
+        )
+    };
+    data.truncate(size as usize);
+    result.is_ok().then_some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn counted(text: &str) -> Vec<u8> {
+        let mut bytes = (text.encode_utf16().count() as u16).to_le_bytes().to_vec();
+        bytes.extend(utf16(text));
+        bytes
+    }
+
+    /// A shortcut with an ID list, a Unicode LinkInfo and the given string data.
+    fn shell_link(path: &str, arguments: &str) -> Vec<u8> {
+        let flags = HAS_LINK_TARGET_ID_LIST | HAS_LINK_INFO | HAS_NAME | HAS_ARGUMENTS | IS_UNICODE;
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        bytes[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+
+        // An ID list of a single empty item
+        bytes.extend(4u16.to_le_bytes());
+        bytes.extend([2, 0, 0, 0]);
+
+        let base = 0x24;
+        let mut base_path = utf16(path);
+        base_path.extend([0, 0]);
+        let suffix = base + base_path.len();
+        let size = suffix + 2;
+        let header = [size as u32, 0x24, VOLUME_ID_AND_LOCAL_BASE_PATH, 0, 0, 0, 0, base as u32, suffix as u32];
+        bytes.extend(header.iter().flat_map(|value| value.to_le_bytes()));
+        bytes.extend(base_path);
+        bytes.extend([0, 0]);
+
+        bytes.extend(counted("Editor"));
+        bytes.extend(counted(arguments));
+        bytes
+    }
+
+    #[test]
+    fn reads_target_and_arguments() {
+        let link = parse_shell_link(&shell_link("C:\\Apps\\editor.exe", "--new-window")).unwrap();
+
+        assert_eq!(link.target.as_deref(), Some("C:\\Apps\\editor.exe"));
+        assert_eq!(link.arguments.as_deref(), Some("--new-window"));
+        assert_eq!(link.icon_location, None);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(parse_shell_link(b"MZ not a shortcut"), None);
+        assert_eq!(parse_shell_link(&shell_link("C:\\a.exe", "")[..0x50]), None);
+    }
+
+    #[test]
+    fn sorts_by_taskband_order() {
+        let mut favorites = vec![0u8, 1, 2];
+        favorites.extend(utf16("Terminal.lnk"));
+        favorites.extend([0, 0, 7]);
+        favorites.extend(utf16("FILE EXPLORER.LNK"));
+        let mut shortcuts = vec![
+            PathBuf::from("File Explorer.lnk"),
+            PathBuf::from("Notes.lnk"),
+            PathBuf::from("Terminal.lnk"),
+            PathBuf::from("Calendar.lnk"),
+        ];
+
+        sort_by_taskband(&mut shortcuts, &favorites);
+
+        let names: Vec<_> = shortcuts.iter().map(|path| path.to_str().unwrap()).collect();
+        assert_eq!(names, ["Terminal.lnk", "File Explorer.lnk", "Calendar.lnk", "Notes.lnk"]);
+    }
+}
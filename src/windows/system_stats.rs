@@ -0,0 +1,45 @@
+use windows::Win32::Foundation::FILETIME;
+use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+use windows::Win32::System::Threading::GetSystemTimes;
+use windows::core::HSTRING;
+
+use crate::widgets::{CpuTimes, DiskUsage, MemoryUsage};
+
+/// Reads the processor time spent idle, in the kernel and in user mode since boot.
+pub fn get_cpu_times() -> Option<CpuTimes> {
+    let (mut idle, mut kernel, mut user) = (FILETIME::default(), FILETIME::default(), FILETIME::default());
+    unsafe { GetSystemTimes(Some(&mut idle), Some(&mut kernel), Some(&mut user)) }.ok()?;
+
+    Some(CpuTimes {
+        idle: filetime_ticks(idle),
+        kernel: filetime_ticks(kernel),
+        user: filetime_ticks(user),
+    })
+}
+
+/// Reads the size of physical memory and how much of it is available.
+pub fn get_memory_usage() -> Option<MemoryUsage> {
+    let mut status = MEMORYSTATUSEX {
+        dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+        ..Default::default()
+    };
+    unsafe { GlobalMemoryStatusEx(&mut status) }.ok()?;
+
+    Some(MemoryUsage {
+        total: status.ullTotalPhys,
+        available: status.ullAvailPhys,
+    })
+}
+
+/// Reads the capacity and free space of the volume holding `path`, e.g. `C:\`.
+pub fn get_disk_usage(path: &str) -> Option<DiskUsage> {
+    let (mut total, mut free) = (0u64, 0u64);
+    unsafe { GetDiskFreeSpaceExW(&HSTRING::from(path), None, Some(&mut total), Some(&mut free)) }.ok()?;
+
+    Some(DiskUsage { total, free })
+}
+
+fn filetime_ticks(time: FILETIME) -> u64 {
+    (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime)
+}
@@ -0,0 +1,18 @@
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+use crate::menu_bar::LocalTime;
+
+/// Reads the current time in the user's time zone.
+pub fn get_local_time() -> LocalTime {
+    let time = unsafe { GetLocalTime() };
+
+    LocalTime {
+        year: time.wYear,
+        weekday: time.wDayOfWeek,
+        day: time.wDay,
+        month: time.wMonth,
+        hour: time.wHour,
+        minute: time.wMinute,
+        second: time.wSecond,
+    }
+}
@@ -1,18 +1,20 @@
-use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, WPARAM};
 use windows::Win32::Graphics::Dwm::{DWMWA_CLOAKED, DwmGetWindowAttribute};
 use windows::Win32::System::Threading::{
     AttachThreadInput, GetCurrentThreadId,
 };
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyState, VK_APPS};
 use windows::Win32::UI::WindowsAndMessaging::{
-    BringWindowToTop, EnumWindows, GW_OWNER, GWL_EXSTYLE, GetForegroundWindow, GetWindow,
-    GetWindowLongPtrW, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindow,
-    IsWindowVisible, SetForegroundWindow, ShowWindow, SW_MINIMIZE, SW_RESTORE, SW_SHOW,
-    WS_EX_APPWINDOW, WS_EX_TOOLWINDOW,
+    BringWindowToTop, EnumWindows, GW_OWNER, GWL_EXSTYLE, GetClassNameW, GetForegroundWindow,
+    GetWindow, GetWindowLongPtrW, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindow,
+    IsWindowVisible, PostMessageW, SetForegroundWindow, ShowWindow, SW_MINIMIZE, SW_RESTORE,
+    SW_SHOW, SetWindowLongPtrW, WM_CLOSE, WS_EX_APPWINDOW, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW,
 };
 
-use crate::windows::DockIcon;
+use crate::rules::{RuleSet, WindowFacts};
+use crate::windows::{DockIcon, InspectDepth, ProcessResolver};
 
-pub fn update_running_apps() -> Vec<DockIcon> {
+pub fn update_running_apps(rules: &RuleSet, processes: &mut ProcessResolver) -> Vec<DockIcon> {
     let mut open_windows: Vec<HWND> = Vec::new();
     unsafe {
         let _ = EnumWindows(
@@ -22,54 +24,74 @@ pub fn update_running_apps() -> Vec<DockIcon> {
     }
 
     let mut current_icons: Vec<DockIcon> = Vec::new();
+    let mut live_pids: Vec<u32> = Vec::new();
 
     for hwnd in open_windows {
-        if let Some(icon) = crate::windows::get_dock_icon_for_window(hwnd) {
-            if !current_icons.iter().any(|i| i.path == icon.path) {
+        let inspection = crate::windows::inspect_window(hwnd, rules, processes, InspectDepth::Quick);
+        live_pids.push(inspection.process_id);
+
+        if let Some(icon) = inspection.icon {
+            if let Some(existing) = current_icons.iter_mut().find(|i| i.path == icon.path) {
+                existing.windows.push(hwnd);
+            } else {
                 current_icons.push(icon);
             }
         }
     }
 
+    processes.retain_pids(&live_pids);
     current_icons
 }
 
-pub fn is_dock_worthy_window(hwnd: HWND) -> bool {
+/// Returns every top-level window, including hidden ones, in z-order.
+pub fn enumerate_top_level_windows() -> Vec<HWND> {
+    let mut all_windows: Vec<HWND> = Vec::new();
     unsafe {
-        if !IsWindowVisible(hwnd).as_bool() {
-            return false;
-        }
-
-        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32;
-        let is_tool_window = (ex_style & WS_EX_TOOLWINDOW.0) != 0;
-        let is_app_window = (ex_style & WS_EX_APPWINDOW.0) != 0;
-        let has_owner = GetWindow(hwnd, GW_OWNER).0 != 0;
+        let _ = EnumWindows(
+            Some(enum_all_windows_proc),
+            LPARAM(&mut all_windows as *mut _ as isize),
+        );
+    }
 
-        if is_tool_window {
-            return false;
-        }
+    all_windows
+}
 
-        if has_owner && !is_app_window {
-            return false;
-        }
+/// Collects the window properties that window rules match on.
+///
+/// Only reads the window itself; `exe_path` and `aumid` are left empty for the caller to fill in
+/// once the window's process is resolved.
+pub fn get_window_facts(hwnd: HWND) -> WindowFacts {
+    unsafe {
+        let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32;
 
-        let mut is_cloacked: u32 = 0;
-        if DwmGetWindowAttribute(
+        let mut is_cloaked: u32 = 0;
+        let cloaked = DwmGetWindowAttribute(
             hwnd,
             DWMWA_CLOAKED,
-            &mut is_cloacked as *mut _ as *mut _,
+            &mut is_cloaked as *mut _ as *mut _,
             std::mem::size_of::<u32>() as u32,
         )
         .is_ok()
-            && is_cloacked != 0
-        {
-            return false;
+            && is_cloaked != 0;
+
+        WindowFacts {
+            class: get_window_class(hwnd),
+            title: get_window_title(hwnd),
+            tool_window: (ex_style & WS_EX_TOOLWINDOW.0) != 0,
+            app_window: (ex_style & WS_EX_APPWINDOW.0) != 0,
+            owned: GetWindow(hwnd, GW_OWNER).0 != 0,
+            cloaked,
+            ..Default::default()
         }
-
-        true
     }
 }
 
+/// Whether the Apps (context menu) key is held, as of the last keyboard message this thread
+/// processed. egui has no key for it, so it cannot be read from egui's input.
+pub fn context_menu_key_down() -> bool {
+    unsafe { GetKeyState(VK_APPS.0 as i32) < 0 }
+}
+
 pub fn focus_or_minimize_window(hwnd: HWND) {
     if hwnd.0 == 0 {
         return;
@@ -110,6 +132,21 @@ pub fn focus_or_minimize_window(hwnd: HWND) {
     }
 }
 
+/// Stops `hwnd` from becoming the foreground window when clicked.
+pub fn prevent_activation(hwnd: HWND) {
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_NOACTIVATE.0 as isize);
+    }
+}
+
+/// Asks `hwnd` to close, as its close button would; the application may still prompt or refuse.
+pub fn close_window(hwnd: HWND) {
+    if hwnd.0 != 0 {
+        let _ = unsafe { PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0)) };
+    }
+}
+
 pub fn get_window_title(hwnd: HWND) -> String {
     if hwnd.0 == 0 {
         return String::new();
@@ -125,8 +162,20 @@ pub fn get_window_title(hwnd: HWND) -> String {
     }
 }
 
+pub fn get_window_class(hwnd: HWND) -> String {
+    unsafe {
+        let mut buf: [u16; 256] = [0; 256];
+        let len = GetClassNameW(hwnd, &mut buf);
+        if len > 0 {
+            String::from_utf16_lossy(&buf[..len as usize])
+        } else {
+            String::new()
+        }
+    }
+}
+
 extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
-    if is_dock_worthy_window(hwnd) {
+    if unsafe { IsWindowVisible(hwnd).as_bool() } {
         unsafe {
             let windows: &mut Vec<HWND> = &mut *(lparam.0 as *mut _);
             windows.push(hwnd);
@@ -135,3 +184,12 @@ extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
 
     true.into()
 }
+
+extern "system" fn enum_all_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    unsafe {
+        let windows: &mut Vec<HWND> = &mut *(lparam.0 as *mut _);
+        windows.push(hwnd);
+    }
+
+    true.into()
+}